@@ -0,0 +1,770 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use mongodb::bson::{self, oid::ObjectId, Document};
+use std::sync::RwLock;
+use crate::db::repositories::{
+    AlertRepository, ApiKeyRepository, AssetEquivalenceRepository, AssetRepository, BacktestRepository, ExchangeRepository, IntegrityRepository, MarketDataRepository,
+    MarketPairFilter, MarketPairRepository, PaperTradingRepository, ProfileUpdate, StrategyRepository, UserRepository,
+};
+use crate::helpers::app_error::AppError;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::modules::alert::alert_schema::{AlertDelivery, AlertRule};
+use crate::modules::api_key::api_key_schema::ApiKey;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, Leg, StatisticalParams};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::asset::asset_schema::Asset;
+use crate::modules::asset_equivalence::asset_equivalence_schema::AssetEquivalence;
+use crate::modules::backtest::backtest_schema::{BacktestJob, BacktestReport, BacktestStatus};
+use crate::modules::exchange::exchange_schema::Exchange;
+use crate::modules::integrity::integrity_schema::CatalogDocuments;
+use crate::modules::market_data::market_data_schema::{MarketQuote, OrderBook};
+use crate::modules::market_pair::market_pair_schema::MarketPair;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use crate::modules::paper_trading::paper_trading_schema::{PaperBalance, PaperTrade};
+use crate::modules::paper_trading::simulated_exchange::{Balances, BALANCE_EPSILON};
+use crate::modules::user::user_schema::{Role, Session, User};
+
+// Almacenamiento en memoria para los tests de la aplicación completa. Reproduce la semántica
+// de las consultas de Mongo que usan los servicios, no su rendimiento.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    exchanges: RwLock<Vec<Exchange>>,
+    assets: RwLock<Vec<Asset>>,
+    market_pairs: RwLock<Vec<MarketPair>>,
+    strategies: RwLock<Vec<ArbitrageStrategy>>,
+    asset_equivalences: RwLock<Vec<AssetEquivalence>>,
+    users: RwLock<Vec<User>>,
+    api_keys: RwLock<Vec<ApiKey>>,
+    market_quotes: RwLock<Vec<MarketQuote>>,
+    market_quote_history: RwLock<Vec<MarketQuote>>,
+    order_books: RwLock<Vec<OrderBook>>,
+    paper_balances: RwLock<Vec<PaperBalance>>,
+    paper_trades: RwLock<Vec<PaperTrade>>,
+    backtests: RwLock<Vec<BacktestJob>>,
+    alert_rules: RwLock<Vec<AlertRule>>,
+    alert_deliveries: RwLock<Vec<AlertDelivery>>,
+}
+
+fn contains_ignore_case(value: &str, term: &str) -> bool {
+    value.to_lowercase().contains(&term.to_lowercase())
+}
+
+fn page_of<T: Clone>(items: Vec<T>, page: &PageQuery) -> Paginated<T> {
+    let total = items.len() as u64;
+    let items = items.into_iter().skip(page.skip() as usize).take(page.per_page() as usize).collect();
+    Paginated::new(items, total, page)
+}
+
+impl InMemoryStorage {
+    fn populate(&self, pair: &MarketPair) -> Option<PopulatedMarketPair> {
+        let exchanges = self.exchanges.read().unwrap();
+        let assets = self.assets.read().unwrap();
+        let asset = |id: ObjectId| assets.iter().find(|a| a.id == Some(id)).cloned();
+
+        Some(PopulatedMarketPair {
+            id: pair.id,
+            exchange: exchanges.iter().find(|e| e.id == Some(pair._exchange)).cloned()?,
+            base_asset: asset(pair._base_asset)?,
+            quote_asset: asset(pair._quote_asset)?,
+            maker_fee: pair.maker_fee,
+            taker_fee: pair.taker_fee,
+            created_at: pair.created_at,
+            updated_at: pair.updated_at,
+            status: pair.status,
+        })
+    }
+
+    fn populated_where(&self, predicate: impl Fn(&PopulatedMarketPair) -> bool) -> Vec<PopulatedMarketPair> {
+        let pairs = self.market_pairs.read().unwrap().clone();
+        pairs.iter().filter_map(|pair| self.populate(pair)).filter(|pair| predicate(pair)).collect()
+    }
+
+    // Equivalente al índice único (exchange, base, quote) de Mongo
+    fn check_unique_pair(&self, pair: &MarketPair, exclude: Option<ObjectId>) -> Result<(), AppError> {
+        let duplicated = self.market_pairs.read().unwrap().iter().any(|p| {
+            p.id != exclude && p._exchange == pair._exchange && p._base_asset == pair._base_asset && p._quote_asset == pair._quote_asset
+        });
+        if duplicated {
+            return Err(AppError::Conflict("A record with the same unique fields already exists".to_string()));
+        }
+        Ok(())
+    }
+
+    fn update_user_where(&self, id: ObjectId, update: impl FnOnce(&mut User) -> bool) -> bool {
+        let mut users = self.users.write().unwrap();
+        users.iter_mut().find(|u| u.id == Some(id)).is_some_and(update)
+    }
+}
+
+// Inserta con un id nuevo si no trae uno y devuelve lo guardado
+fn insert<T: Clone>(collection: &RwLock<Vec<T>>, mut item: T, id: impl FnOnce(&mut T) -> &mut Option<ObjectId>) -> T {
+    id(&mut item).get_or_insert_with(ObjectId::new);
+    collection.write().unwrap().push(item.clone());
+    item
+}
+
+#[async_trait]
+impl ExchangeRepository for InMemoryStorage {
+    async fn insert_exchange(&self, exchange: Exchange) -> Result<Exchange, AppError> {
+        Ok(insert(&self.exchanges, exchange, |e| &mut e.id))
+    }
+
+    async fn find_exchange(&self, id: ObjectId) -> Result<Option<Exchange>, AppError> {
+        Ok(self.exchanges.read().unwrap().iter().find(|e| e.id == Some(id)).cloned())
+    }
+
+    async fn update_exchange(&self, id: ObjectId, exchange: Exchange) -> Result<Option<Exchange>, AppError> {
+        let mut exchanges = self.exchanges.write().unwrap();
+        Ok(exchanges.iter_mut().find(|e| e.id == Some(id)).map(|stored| {
+            *stored = Exchange { id: Some(id), created_at: stored.created_at, ..exchange };
+            stored.clone()
+        }))
+    }
+
+    async fn delete_exchange(&self, id: ObjectId) -> Result<bool, AppError> {
+        let mut exchanges = self.exchanges.write().unwrap();
+        let before = exchanges.len();
+        exchanges.retain(|e| e.id != Some(id));
+        Ok(exchanges.len() < before)
+    }
+
+    async fn list_exchanges(&self, page: &PageQuery) -> Result<Paginated<Exchange>, AppError> {
+        let mut exchanges = self.exchanges.read().unwrap().clone();
+        exchanges.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(page_of(exchanges, page))
+    }
+}
+
+#[async_trait]
+impl AssetRepository for InMemoryStorage {
+    async fn insert_asset(&self, asset: Asset) -> Result<Asset, AppError> {
+        Ok(insert(&self.assets, asset, |a| &mut a.id))
+    }
+
+    async fn find_asset(&self, id: ObjectId) -> Result<Option<Asset>, AppError> {
+        Ok(self.assets.read().unwrap().iter().find(|a| a.id == Some(id)).cloned())
+    }
+
+    async fn update_asset(&self, id: ObjectId, asset: Asset) -> Result<Option<Asset>, AppError> {
+        let mut assets = self.assets.write().unwrap();
+        Ok(assets.iter_mut().find(|a| a.id == Some(id)).map(|stored| {
+            *stored = Asset { id: Some(id), created_at: stored.created_at, ..asset };
+            stored.clone()
+        }))
+    }
+
+    async fn delete_asset(&self, id: ObjectId) -> Result<bool, AppError> {
+        let mut assets = self.assets.write().unwrap();
+        let before = assets.len();
+        assets.retain(|a| a.id != Some(id));
+        Ok(assets.len() < before)
+    }
+
+    async fn list_assets(&self, page: &PageQuery, search: Option<&str>, include_exchange: bool) -> Result<Paginated<Document>, AppError> {
+        let assets: Vec<Asset> = self.assets.read().unwrap().iter()
+            .filter(|a| search.is_none_or(|term| contains_ignore_case(&a.name, term) || contains_ignore_case(&a.short_name, term)))
+            .cloned()
+            .collect();
+        let page = page_of(assets, page);
+
+        let mut documents = Vec::with_capacity(page.items.len());
+        for asset in &page.items {
+            let mut document = bson::to_document(asset)?;
+            if include_exchange {
+                if let Some(exchange) = self.find_exchange(asset._exchange).await? {
+                    document.insert("exchange", bson::to_bson(&exchange)?);
+                }
+            }
+            documents.push(document);
+        }
+        Ok(Paginated { items: documents, total: page.total, page: page.page, per_page: page.per_page, next_cursor: page.next_cursor })
+    }
+
+    async fn find_asset_ids_by_exchange(&self, exchange: ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.assets.read().unwrap().iter().filter(|a| a._exchange == exchange).filter_map(|a| a.id).collect())
+    }
+}
+
+#[async_trait]
+impl MarketPairRepository for InMemoryStorage {
+    async fn insert_market_pair(&self, market_pair: MarketPair) -> Result<MarketPair, AppError> {
+        self.check_unique_pair(&market_pair, None)?;
+        Ok(insert(&self.market_pairs, market_pair, |p| &mut p.id))
+    }
+
+    async fn find_market_pair(&self, id: ObjectId) -> Result<Option<MarketPair>, AppError> {
+        Ok(self.market_pairs.read().unwrap().iter().find(|p| p.id == Some(id)).cloned())
+    }
+
+    async fn update_market_pair(&self, id: ObjectId, market_pair: MarketPair) -> Result<Option<MarketPair>, AppError> {
+        self.check_unique_pair(&market_pair, Some(id))?;
+        let mut market_pairs = self.market_pairs.write().unwrap();
+        Ok(market_pairs.iter_mut().find(|p| p.id == Some(id)).map(|stored| {
+            *stored = MarketPair { id: Some(id), created_at: stored.created_at, ..market_pair };
+            stored.clone()
+        }))
+    }
+
+    async fn find_market_pair_by_assets(&self, exchange: ObjectId, base_asset: ObjectId, quote_asset: ObjectId) -> Result<Option<MarketPair>, AppError> {
+        Ok(self.market_pairs.read().unwrap().iter()
+            .find(|p| p._exchange == exchange && p._base_asset == base_asset && p._quote_asset == quote_asset)
+            .cloned())
+    }
+
+    async fn delete_market_pair(&self, id: ObjectId) -> Result<bool, AppError> {
+        let mut market_pairs = self.market_pairs.write().unwrap();
+        let before = market_pairs.len();
+        market_pairs.retain(|p| p.id != Some(id));
+        Ok(market_pairs.len() < before)
+    }
+
+    async fn find_populated_market_pairs(&self, filter: MarketPairFilter) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        let links = |a: &PopulatedMarketPair, x: &dyn Fn(&Asset) -> bool, y: &dyn Fn(&Asset) -> bool| {
+            (x(&a.base_asset) && y(&a.quote_asset)) || (y(&a.base_asset) && x(&a.quote_asset))
+        };
+
+        Ok(match filter {
+            MarketPairFilter::Ids(ids) => self.populated_where(|p| p.id.is_some_and(|id| ids.contains(&id))),
+            MarketPairFilter::Exchange(exchange_id) => self.populated_where(|p| p.exchange.id == Some(exchange_id)),
+            MarketPairFilter::Active(exchange_ids) => self.populated_where(|p| {
+                p.status && (exchange_ids.is_empty() || p.exchange.id.is_some_and(|id| exchange_ids.contains(&id)))
+            }),
+            MarketPairFilter::Linking(asset_a, asset_b) => self.populated_where(|p| {
+                links(p, &|asset| asset.id == Some(asset_a), &|asset| asset.id == Some(asset_b))
+            }),
+            MarketPairFilter::LinkingSymbols(symbols_a, symbols_b) => self.populated_where(|p| {
+                links(p, &|asset| symbols_a.contains(&asset.short_name), &|asset| symbols_b.contains(&asset.short_name))
+            }),
+        })
+    }
+
+    async fn list_populated_market_pairs(&self, page: &PageQuery, exchange: Option<ObjectId>, search: Option<&str>) -> Result<Paginated<PopulatedMarketPair>, AppError> {
+        let pairs = self.populated_where(|p| {
+            exchange.is_none_or(|id| p.exchange.id == Some(id))
+                && search.is_none_or(|term| contains_ignore_case(&p.base_asset.short_name, term) || contains_ignore_case(&p.quote_asset.short_name, term))
+        });
+        Ok(page_of(pairs, page))
+    }
+
+    async fn find_market_pair_ids_referencing(&self, exchange: Option<ObjectId>, assets: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.market_pairs.read().unwrap().iter()
+            .filter(|p| exchange == Some(p._exchange) || assets.contains(&p._base_asset) || assets.contains(&p._quote_asset))
+            .filter_map(|p| p.id)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl StrategyRepository for InMemoryStorage {
+    async fn insert_strategy(&self, strategy: ArbitrageStrategy) -> Result<ArbitrageStrategy, AppError> {
+        Ok(insert(&self.strategies, strategy, |s| &mut s.id))
+    }
+
+    async fn find_strategy(&self, id: ObjectId) -> Result<Option<ArbitrageStrategy>, AppError> {
+        Ok(self.strategies.read().unwrap().iter().find(|s| s.id == Some(id)).cloned())
+    }
+
+    async fn find_owned_strategy(&self, id: ObjectId, owner: ObjectId) -> Result<Option<ArbitrageStrategy>, AppError> {
+        Ok(self.find_strategy(id).await?.filter(|s| s._owner == Some(owner)))
+    }
+
+    async fn find_active_strategies(&self) -> Result<Vec<ArbitrageStrategy>, AppError> {
+        Ok(self.strategies.read().unwrap().iter().filter(|s| s.status).cloned().collect())
+    }
+
+    async fn update_owned_strategy(&self, id: ObjectId, owner: ObjectId, strategy: ArbitrageStrategy) -> Result<Option<ArbitrageStrategy>, AppError> {
+        let mut strategies = self.strategies.write().unwrap();
+        Ok(strategies.iter_mut().find(|s| s.id == Some(id) && s._owner == Some(owner)).map(|stored| {
+            *stored = ArbitrageStrategy { id: Some(id), created_at: stored.created_at, _owner: Some(owner), ..strategy };
+            stored.clone()
+        }))
+    }
+
+    async fn delete_owned_strategy(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError> {
+        let mut strategies = self.strategies.write().unwrap();
+        let before = strategies.len();
+        strategies.retain(|s| !(s.id == Some(id) && s._owner == Some(owner)));
+        Ok(strategies.len() < before)
+    }
+
+    async fn list_owned_strategies(&self, owner: ObjectId, arbitrage_type: Option<ArbitrageType>, page: &PageQuery) -> Result<Paginated<ArbitrageStrategy>, AppError> {
+        let mut strategies: Vec<ArbitrageStrategy> = self.strategies.read().unwrap().iter()
+            .filter(|s| s._owner == Some(owner) && arbitrage_type.as_ref().is_none_or(|t| &s.arbitrage_type == t))
+            .cloned()
+            .collect();
+        strategies.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        Ok(page_of(strategies, page))
+    }
+
+    async fn find_strategy_ids_referencing(&self, market_pairs: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.strategies.read().unwrap().iter()
+            .filter(|s| ArbitrageStrategyService::leg_pair_ids(&s.legs).iter().any(|id| market_pairs.contains(id)))
+            .filter_map(|s| s.id)
+            .collect())
+    }
+
+    // Un documento con el formato anterior se lee como una estrategia sin patas
+    async fn find_unmigrated_strategy_ids(&self) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.strategies.read().unwrap().iter().filter(|s| s.legs.is_empty()).filter_map(|s| s.id).collect())
+    }
+
+    // Aquí solo se guardan estrategias ya tipadas, así que no hay details que convertir
+    async fn find_legacy_strategy_documents(&self) -> Result<Vec<Document>, AppError> {
+        Ok(Vec::new())
+    }
+
+    async fn set_migrated_legs(&self, id: ObjectId, arbitrage_type: ArbitrageType, legs: Vec<Leg>, statistical: Option<StatisticalParams>) -> Result<(), AppError> {
+        let mut strategies = self.strategies.write().unwrap();
+        if let Some(stored) = strategies.iter_mut().find(|s| s.id == Some(id)) {
+            stored.arbitrage_type = arbitrage_type;
+            stored.legs = legs;
+            stored.statistical = statistical;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AssetEquivalenceRepository for InMemoryStorage {
+    async fn insert_asset_equivalence(&self, equivalence: AssetEquivalence) -> Result<AssetEquivalence, AppError> {
+        Ok(insert(&self.asset_equivalences, equivalence, |e| &mut e.id))
+    }
+
+    async fn find_asset_equivalence(&self, id: ObjectId) -> Result<Option<AssetEquivalence>, AppError> {
+        Ok(self.asset_equivalences.read().unwrap().iter().find(|e| e.id == Some(id)).cloned())
+    }
+
+    async fn update_asset_equivalence(&self, id: ObjectId, equivalence: AssetEquivalence) -> Result<Option<AssetEquivalence>, AppError> {
+        let mut equivalences = self.asset_equivalences.write().unwrap();
+        Ok(equivalences.iter_mut().find(|e| e.id == Some(id)).map(|stored| {
+            *stored = AssetEquivalence { id: Some(id), created_at: stored.created_at, ..equivalence };
+            stored.clone()
+        }))
+    }
+
+    async fn delete_asset_equivalence(&self, id: ObjectId) -> Result<bool, AppError> {
+        let mut equivalences = self.asset_equivalences.write().unwrap();
+        let before = equivalences.len();
+        equivalences.retain(|e| e.id != Some(id));
+        Ok(equivalences.len() < before)
+    }
+
+    async fn find_asset_equivalences(&self) -> Result<Vec<AssetEquivalence>, AppError> {
+        let mut equivalences = self.asset_equivalences.read().unwrap().clone();
+        equivalences.sort_by_key(|e| e.priority);
+        Ok(equivalences)
+    }
+
+    async fn list_asset_equivalences(&self, page: &PageQuery) -> Result<Paginated<AssetEquivalence>, AppError> {
+        Ok(page_of(self.find_asset_equivalences().await?, page))
+    }
+}
+
+#[async_trait]
+impl IntegrityRepository for InMemoryStorage {
+    async fn delete_catalog_documents(&self, documents: &CatalogDocuments) -> Result<(), AppError> {
+        self.alert_deliveries.write().unwrap().retain(|d| !documents.alert_rules.contains(&d._alert_rule));
+        self.alert_rules.write().unwrap().retain(|r| !r.id.is_some_and(|id| documents.alert_rules.contains(&id)));
+        self.backtests.write().unwrap().retain(|j| !j.id.is_some_and(|id| documents.backtests.contains(&id)));
+        self.paper_trades.write().unwrap().retain(|t| !t.id.is_some_and(|id| documents.paper_trades.contains(&id)));
+        self.strategies.write().unwrap().retain(|s| !s.id.is_some_and(|id| documents.strategies.contains(&id)));
+        self.market_pairs.write().unwrap().retain(|p| !p.id.is_some_and(|id| documents.market_pairs.contains(&id)));
+        self.assets.write().unwrap().retain(|a| !a.id.is_some_and(|id| documents.assets.contains(&id)));
+        self.exchanges.write().unwrap().retain(|e| !e.id.is_some_and(|id| documents.exchanges.contains(&id)));
+        Ok(())
+    }
+
+    async fn find_orphans(&self) -> Result<CatalogDocuments, AppError> {
+        let exchanges: Vec<ObjectId> = self.exchanges.read().unwrap().iter().filter_map(|e| e.id).collect();
+        let assets: Vec<ObjectId> = self.assets.read().unwrap().iter().filter_map(|a| a.id).collect();
+        let market_pairs: Vec<ObjectId> = self.market_pairs.read().unwrap().iter().filter_map(|p| p.id).collect();
+        let strategies: Vec<ObjectId> = self.strategies.read().unwrap().iter().filter_map(|s| s.id).collect();
+
+        Ok(CatalogDocuments {
+            exchanges: Vec::new(),
+            assets: self.assets.read().unwrap().iter()
+                .filter(|a| !exchanges.contains(&a._exchange))
+                .filter_map(|a| a.id)
+                .collect(),
+            market_pairs: self.market_pairs.read().unwrap().iter()
+                .filter(|p| !exchanges.contains(&p._exchange) || !assets.contains(&p._base_asset) || !assets.contains(&p._quote_asset))
+                .filter_map(|p| p.id)
+                .collect(),
+            strategies: self.strategies.read().unwrap().iter()
+                .filter(|s| ArbitrageStrategyService::leg_pair_ids(&s.legs).iter().any(|id| !market_pairs.contains(id)))
+                .filter_map(|s| s.id)
+                .collect(),
+            alert_rules: self.alert_rules.read().unwrap().iter()
+                .filter(|r| !strategies.contains(&r._arbitrage_strategy))
+                .filter_map(|r| r.id)
+                .collect(),
+            backtests: self.backtests.read().unwrap().iter()
+                .filter(|j| !strategies.contains(&j._arbitrage_strategy))
+                .filter_map(|j| j.id)
+                .collect(),
+            paper_trades: self.paper_trades.read().unwrap().iter()
+                .filter(|t| !strategies.contains(&t._arbitrage_strategy))
+                .filter_map(|t| t.id)
+                .collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryStorage {
+    async fn insert_user(&self, user: User) -> Result<User, AppError> {
+        Ok(insert(&self.users, user, |u| &mut u.id))
+    }
+
+    async fn find_user(&self, id: ObjectId) -> Result<Option<User>, AppError> {
+        Ok(self.users.read().unwrap().iter().find(|u| u.id == Some(id)).cloned())
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        Ok(self.users.read().unwrap().iter().find(|u| u.email == email).cloned())
+    }
+
+    async fn update_profile(&self, id: ObjectId, update: ProfileUpdate) -> Result<Option<User>, AppError> {
+        self.update_user_where(id, |user| {
+            if let Some(name) = update.name {
+                user.name = name;
+            }
+            if let Some(email) = update.email {
+                user.email = email;
+            }
+            if let Some(password_hash) = update.password_hash {
+                user.password = password_hash;
+            }
+            if update.default_market_pair.is_some() {
+                user._default_market_pair = update.default_market_pair;
+            }
+            true
+        });
+        self.find_user(id).await
+    }
+
+    async fn set_role(&self, id: ObjectId, role: Role) -> Result<Option<User>, AppError> {
+        self.update_user_where(id, |user| {
+            user.role = role;
+            true
+        });
+        self.find_user(id).await
+    }
+
+    async fn set_password_reset(&self, id: ObjectId, token_hash: &str, expires: NaiveDateTime) -> Result<(), AppError> {
+        self.update_user_where(id, |user| {
+            user.password_reset_token = token_hash.to_string();
+            user.password_reset_expires = expires;
+            true
+        });
+        Ok(())
+    }
+
+    async fn find_user_by_reset_token(&self, token_hash: &str) -> Result<Option<User>, AppError> {
+        Ok(self.users.read().unwrap().iter().find(|u| u.password_reset_token == token_hash).cloned())
+    }
+
+    async fn consume_password_reset(&self, id: ObjectId, token_hash: &str, password_hash: &str) -> Result<bool, AppError> {
+        Ok(self.update_user_where(id, |user| {
+            if user.password_reset_token != token_hash {
+                return false;
+            }
+            user.password = password_hash.to_string();
+            user.password_reset_token.clear();
+            user.tokens.clear();
+            true
+        }))
+    }
+
+    async fn add_session(&self, user_id: ObjectId, session: Session, now: f64) -> Result<(), AppError> {
+        self.update_user_where(user_id, |user| {
+            user.tokens.retain(|s| s.expires_at > now);
+            user.tokens.push(session);
+            true
+        });
+        Ok(())
+    }
+
+    async fn find_user_by_session(&self, session_id: &str) -> Result<Option<User>, AppError> {
+        Ok(self.users.read().unwrap().iter().find(|u| u.tokens.iter().any(|s| s.id == session_id)).cloned())
+    }
+
+    async fn rotate_session(&self, user_id: ObjectId, session_id: &str, current_hash: &str, new_hash: &str, user_agent: &str, now: f64) -> Result<bool, AppError> {
+        Ok(self.update_user_where(user_id, |user| {
+            let Some(session) = user.tokens.iter_mut().find(|s| s.id == session_id && s.refresh_token_hash == current_hash) else {
+                return false;
+            };
+            session.refresh_token_hash = new_hash.to_string();
+            session.user_agent = user_agent.to_string();
+            session.last_used_at = now;
+            true
+        }))
+    }
+
+    async fn touch_session(&self, user_id: ObjectId, session_id: &str, now: f64) -> Result<bool, AppError> {
+        Ok(self.update_user_where(user_id, |user| {
+            let Some(session) = user.tokens.iter_mut().find(|s| s.id == session_id && s.expires_at > now) else {
+                return false;
+            };
+            session.last_used_at = now;
+            true
+        }))
+    }
+
+    async fn remove_session(&self, user_id: ObjectId, session_id: &str) -> Result<(), AppError> {
+        self.update_user_where(user_id, |user| {
+            user.tokens.retain(|s| s.id != session_id);
+            true
+        });
+        Ok(())
+    }
+
+    async fn clear_sessions(&self, user_id: ObjectId) -> Result<(), AppError> {
+        self.update_user_where(user_id, |user| {
+            user.tokens.clear();
+            true
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for InMemoryStorage {
+    async fn insert_api_key(&self, api_key: ApiKey) -> Result<ApiKey, AppError> {
+        Ok(insert(&self.api_keys, api_key, |k| &mut k.id))
+    }
+
+    async fn list_owned_api_keys(&self, owner: ObjectId, page: &PageQuery) -> Result<Paginated<ApiKey>, AppError> {
+        let mut api_keys: Vec<ApiKey> = self.api_keys.read().unwrap().iter().filter(|k| k._user == owner).cloned().collect();
+        api_keys.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        Ok(page_of(api_keys, page))
+    }
+
+    async fn delete_owned_api_key(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError> {
+        let mut api_keys = self.api_keys.write().unwrap();
+        let before = api_keys.len();
+        api_keys.retain(|k| !(k.id == Some(id) && k._user == owner));
+        Ok(api_keys.len() < before)
+    }
+
+    async fn use_api_key(&self, id: ObjectId, secret_hash: &str, now: f64) -> Result<Option<ApiKey>, AppError> {
+        let mut api_keys = self.api_keys.write().unwrap();
+        Ok(api_keys.iter_mut()
+            .find(|k| k.id == Some(id) && k.secret_hash == secret_hash && k.expires_at.is_none_or(|expires_at| expires_at > now))
+            .map(|stored| {
+                let found = stored.clone();
+                stored.last_used_at = Some(now);
+                found
+            }))
+    }
+}
+
+// Sustituye el documento del mismo par conservando su id, como el replace_one con upsert
+fn replace_latest<T: Clone>(collection: &RwLock<Vec<T>>, item: T, market_pair: impl Fn(&T) -> ObjectId, id: impl Fn(&mut T) -> &mut Option<ObjectId>) {
+    let mut items = collection.write().unwrap();
+    match items.iter_mut().find(|stored| market_pair(stored) == market_pair(&item)) {
+        Some(stored) => {
+            let stored_id = *id(stored);
+            *stored = item;
+            *id(stored) = stored_id;
+        },
+        None => {
+            let mut item = item;
+            id(&mut item).get_or_insert_with(ObjectId::new);
+            items.push(item);
+        },
+    }
+}
+
+#[async_trait]
+impl MarketDataRepository for InMemoryStorage {
+    async fn store_quote(&self, quote: MarketQuote) -> Result<(), AppError> {
+        replace_latest(&self.market_quotes, quote.clone(), |q| q._market_pair, |q| &mut q.id);
+        insert(&self.market_quote_history, quote, |q| &mut q.id);
+        Ok(())
+    }
+
+    async fn find_quote(&self, market_pair: ObjectId) -> Result<Option<MarketQuote>, AppError> {
+        Ok(self.market_quotes.read().unwrap().iter().find(|q| q._market_pair == market_pair).cloned())
+    }
+
+    async fn find_quotes(&self, market_pairs: &[ObjectId]) -> Result<Vec<MarketQuote>, AppError> {
+        Ok(self.market_quotes.read().unwrap().iter().filter(|q| market_pairs.contains(&q._market_pair)).cloned().collect())
+    }
+
+    async fn find_quote_history(&self, market_pairs: &[ObjectId], from: f64, to: f64) -> Result<Vec<MarketQuote>, AppError> {
+        let mut history: Vec<MarketQuote> = self.market_quote_history.read().unwrap().iter()
+            .filter(|q| market_pairs.contains(&q._market_pair) && q.timestamp >= from && q.timestamp <= to)
+            .cloned()
+            .collect();
+        history.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        Ok(history)
+    }
+
+    async fn store_order_book(&self, order_book: OrderBook) -> Result<(), AppError> {
+        replace_latest(&self.order_books, order_book, |b| b._market_pair, |b| &mut b.id);
+        Ok(())
+    }
+
+    async fn find_order_book(&self, market_pair: ObjectId) -> Result<Option<OrderBook>, AppError> {
+        Ok(self.order_books.read().unwrap().iter().find(|b| b._market_pair == market_pair).cloned())
+    }
+
+    async fn find_order_books(&self, market_pairs: &[ObjectId]) -> Result<Vec<OrderBook>, AppError> {
+        Ok(self.order_books.read().unwrap().iter().filter(|b| market_pairs.contains(&b._market_pair)).cloned().collect())
+    }
+}
+
+#[async_trait]
+impl PaperTradingRepository for InMemoryStorage {
+    async fn find_paper_balances(&self, user: ObjectId) -> Result<Vec<PaperBalance>, AppError> {
+        let mut balances: Vec<PaperBalance> = self.paper_balances.read().unwrap().iter().filter(|b| b._user == user).cloned().collect();
+        balances.sort_by(|a, b| (a._exchange, &a.asset).cmp(&(b._exchange, &b.asset)));
+        Ok(balances)
+    }
+
+    // Se comprueba todo antes de tocar nada; el lock de escritura hace el resto atómico
+    async fn apply_paper_deltas(&self, user: ObjectId, deltas: &Balances, now: f64) -> Result<(), AppError> {
+        let mut balances = self.paper_balances.write().unwrap();
+        let stored = |balances: &[PaperBalance], exchange: ObjectId, asset: &str| {
+            balances.iter().position(|b| b._user == user && b._exchange == exchange && b.asset == asset)
+        };
+
+        for ((exchange_id, asset), delta) in deltas.iter().filter(|(_, delta)| **delta < 0.0) {
+            let covered = stored(&balances, *exchange_id, asset).is_some_and(|index| balances[index].amount >= -delta - BALANCE_EPSILON);
+            if !covered {
+                return Err(AppError::Conflict(format!("Insufficient paper balance of {} on exchange {}", asset, exchange_id)));
+            }
+        }
+        for ((exchange_id, asset), delta) in deltas.iter() {
+            match stored(&balances, *exchange_id, asset) {
+                Some(index) => {
+                    balances[index].amount += delta;
+                    balances[index].updated_at = now;
+                },
+                None => balances.push(PaperBalance {
+                    id: Some(ObjectId::new()),
+                    _user: user,
+                    _exchange: *exchange_id,
+                    asset: asset.clone(),
+                    amount: *delta,
+                    updated_at: now,
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    async fn insert_paper_trade(&self, trade: PaperTrade) -> Result<PaperTrade, AppError> {
+        Ok(insert(&self.paper_trades, trade, |t| &mut t.id))
+    }
+
+    async fn list_paper_trades(&self, user: ObjectId, page: &PageQuery) -> Result<Paginated<PaperTrade>, AppError> {
+        let mut trades: Vec<PaperTrade> = self.paper_trades.read().unwrap().iter().filter(|t| t._user == user).cloned().collect();
+        trades.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        Ok(page_of(trades, page))
+    }
+
+    async fn find_paper_trade_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.paper_trades.read().unwrap().iter().filter(|t| strategies.contains(&t._arbitrage_strategy)).filter_map(|t| t.id).collect())
+    }
+}
+
+#[async_trait]
+impl BacktestRepository for InMemoryStorage {
+    async fn insert_backtest(&self, job: BacktestJob) -> Result<BacktestJob, AppError> {
+        Ok(insert(&self.backtests, job, |j| &mut j.id))
+    }
+
+    async fn find_backtest(&self, id: ObjectId) -> Result<Option<BacktestJob>, AppError> {
+        Ok(self.backtests.read().unwrap().iter().find(|j| j.id == Some(id)).cloned())
+    }
+
+    async fn set_backtest_status(&self, id: ObjectId, status: BacktestStatus, report: Option<BacktestReport>, error: Option<String>, now: f64) -> Result<(), AppError> {
+        let mut backtests = self.backtests.write().unwrap();
+        if let Some(stored) = backtests.iter_mut().find(|j| j.id == Some(id)) {
+            stored.status = status;
+            stored.report = report;
+            stored.error = error;
+            stored.updated_at = now;
+        }
+        Ok(())
+    }
+
+    async fn list_strategy_backtests(&self, strategy: ObjectId, page: &PageQuery) -> Result<Paginated<BacktestJob>, AppError> {
+        let mut jobs: Vec<BacktestJob> = self.backtests.read().unwrap().iter().filter(|j| j._arbitrage_strategy == strategy).cloned().collect();
+        jobs.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        Ok(page_of(jobs, page))
+    }
+
+    async fn find_backtest_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.backtests.read().unwrap().iter().filter(|j| strategies.contains(&j._arbitrage_strategy)).filter_map(|j| j.id).collect())
+    }
+}
+
+#[async_trait]
+impl AlertRepository for InMemoryStorage {
+    async fn insert_alert_rule(&self, rule: AlertRule) -> Result<AlertRule, AppError> {
+        Ok(insert(&self.alert_rules, rule, |r| &mut r.id))
+    }
+
+    async fn find_owned_alert_rule(&self, id: ObjectId, owner: ObjectId) -> Result<Option<AlertRule>, AppError> {
+        Ok(self.alert_rules.read().unwrap().iter().find(|r| r.id == Some(id) && r._user == owner).cloned())
+    }
+
+    async fn update_owned_alert_rule(&self, id: ObjectId, owner: ObjectId, rule: AlertRule) -> Result<Option<AlertRule>, AppError> {
+        let mut rules = self.alert_rules.write().unwrap();
+        Ok(rules.iter_mut().find(|r| r.id == Some(id) && r._user == owner).map(|stored| {
+            stored.min_profit = rule.min_profit;
+            stored.min_duration = rule.min_duration;
+            stored.webhook_url = rule.webhook_url;
+            stored.secret = rule.secret;
+            stored.status = rule.status;
+            stored.above_since = None;
+            stored.fired = false;
+            stored.updated_at = rule.updated_at;
+            stored.clone()
+        }))
+    }
+
+    async fn delete_owned_alert_rule(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError> {
+        let mut rules = self.alert_rules.write().unwrap();
+        let before = rules.len();
+        rules.retain(|r| !(r.id == Some(id) && r._user == owner));
+        Ok(rules.len() < before)
+    }
+
+    async fn list_owned_alert_rules(&self, owner: ObjectId, page: &PageQuery) -> Result<Paginated<AlertRule>, AppError> {
+        let mut rules: Vec<AlertRule> = self.alert_rules.read().unwrap().iter().filter(|r| r._user == owner).cloned().collect();
+        rules.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        Ok(page_of(rules, page))
+    }
+
+    async fn find_active_alert_rules(&self, strategy: ObjectId) -> Result<Vec<AlertRule>, AppError> {
+        Ok(self.alert_rules.read().unwrap().iter().filter(|r| r._arbitrage_strategy == strategy && r.status).cloned().collect())
+    }
+
+    async fn set_alert_rule_state(&self, id: ObjectId, above_since: Option<f64>, fired: bool) -> Result<(), AppError> {
+        let mut rules = self.alert_rules.write().unwrap();
+        if let Some(stored) = rules.iter_mut().find(|r| r.id == Some(id)) {
+            stored.above_since = above_since;
+            stored.fired = fired;
+        }
+        Ok(())
+    }
+
+    async fn insert_alert_delivery(&self, delivery: AlertDelivery) -> Result<(), AppError> {
+        insert(&self.alert_deliveries, delivery, |d| &mut d.id);
+        Ok(())
+    }
+
+    async fn list_alert_deliveries(&self, rule: ObjectId, page: &PageQuery) -> Result<Paginated<AlertDelivery>, AppError> {
+        let mut deliveries: Vec<AlertDelivery> = self.alert_deliveries.read().unwrap().iter().filter(|d| d._alert_rule == rule).cloned().collect();
+        deliveries.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        Ok(page_of(deliveries, page))
+    }
+
+    async fn find_alert_rule_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        Ok(self.alert_rules.read().unwrap().iter().filter(|r| strategies.contains(&r._arbitrage_strategy)).filter_map(|r| r.id).collect())
+    }
+}
@@ -1 +1,5 @@
-pub mod mongodb;
\ No newline at end of file
+pub mod mongodb;
+pub mod repositories;
+pub mod mongo_repositories;
+#[cfg(test)]
+pub mod memory;
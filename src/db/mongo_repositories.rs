@@ -0,0 +1,1027 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use futures::TryStreamExt;
+use mongodb::bson::{self, doc, oid::ObjectId, Document, Regex};
+use mongodb::options::IndexOptions;
+use mongodb::{Collection, Database, IndexModel};
+use serde::de::DeserializeOwned;
+use tracing::error;
+use crate::db::mongodb::MongoDbContext;
+use crate::db::repositories::{
+    AlertRepository, ApiKeyRepository, AssetEquivalenceRepository, AssetRepository, BacktestRepository, ExchangeRepository, IntegrityRepository, MarketDataRepository,
+    MarketPairFilter, MarketPairRepository, PaperTradingRepository, ProfileUpdate, StrategyRepository, UserRepository,
+};
+use crate::helpers::app_error::{db_error, AppError};
+use crate::helpers::pagination::{find_page, PageQuery, Paginated};
+use crate::modules::alert::alert_schema::{AlertDelivery, AlertRule};
+use crate::modules::api_key::api_key_schema::ApiKey;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, Leg, StatisticalParams};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::asset::asset_schema::Asset;
+use crate::modules::asset_equivalence::asset_equivalence_schema::AssetEquivalence;
+use crate::modules::backtest::backtest_schema::{BacktestJob, BacktestReport, BacktestStatus};
+use crate::modules::exchange::exchange_schema::Exchange;
+use crate::modules::integrity::integrity_schema::CatalogDocuments;
+use crate::modules::market_data::market_data_schema::{MarketQuote, OrderBook};
+use crate::modules::market_pair::market_pair_schema::MarketPair;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use crate::modules::paper_trading::paper_trading_schema::{PaperBalance, PaperTrade};
+use crate::modules::paper_trading::simulated_exchange::{Balances, BALANCE_EPSILON};
+use crate::modules::user::user_schema::{Role, Session, User};
+
+fn contains_ignore_case(term: &str) -> Regex {
+    Regex { pattern: format!(".*{}.*", regex_escape(term)), options: "i".to_string() }
+}
+
+fn regex_escape(term: &str) -> String {
+    term.chars().fold(String::new(), |mut escaped, c| {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        escaped
+    })
+}
+
+// Etapas que sustituyen las referencias de un MarketPair por los documentos completos
+fn populate_market_pair_stages() -> Vec<Document> {
+    vec![
+        doc! {
+            "$lookup": {
+                "from": "exchanges",
+                "localField": "_exchange",
+                "foreignField": "_id",
+                "as": "exchange"
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "assets",
+                "localField": "_base_asset",
+                "foreignField": "_id",
+                "as": "base_asset"
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "assets",
+                "localField": "_quote_asset",
+                "foreignField": "_id",
+                "as": "quote_asset"
+            }
+        },
+        doc! { "$unwind": "$exchange" },
+        doc! { "$unwind": "$base_asset" },
+        doc! { "$unwind": "$quote_asset" },
+    ]
+}
+
+async fn collect_aggregate<T: DeserializeOwned>(collection: &Collection<Document>, pipeline: Vec<Document>, context: &'static str) -> Result<Vec<T>, AppError> {
+    let mut cursor = collection.aggregate(pipeline).await.map_err(db_error(context))?;
+
+    let mut items = Vec::new();
+    while let Some(result) = cursor.try_next().await.map_err(db_error(context))? {
+        items.push(bson::from_document(result).map_err(|e| {
+            error!("{}: {}", context, e);
+            AppError::from(e)
+        })?);
+    }
+    Ok(items)
+}
+
+async fn ids_matching<T: Send + Sync>(collection: &Collection<T>, filter: Document, context: &'static str) -> Result<Vec<ObjectId>, AppError> {
+    let ids = collection.distinct("_id", filter).await.map_err(db_error(context))?;
+    Ok(ids.iter().filter_map(|id| id.as_object_id()).collect())
+}
+
+// Ids de los documentos que quedan tras el pipeline (se espera un $project de _id)
+async fn ids_from_aggregate(collection: &Collection<Document>, pipeline: Vec<Document>, context: &'static str) -> Result<Vec<ObjectId>, AppError> {
+    let documents: Vec<Document> = collect_aggregate(collection, pipeline, context).await?;
+    Ok(documents.iter().filter_map(|doc| doc.get_object_id("_id").ok()).collect())
+}
+
+// Ids de los documentos de la colección cuyo _arbitrage_strategy ya no existe
+async fn ids_without_strategy(db: &Database, collection: &str, context: &'static str) -> Result<Vec<ObjectId>, AppError> {
+    ids_from_aggregate(&db.collection(collection), vec![
+        doc! { "$lookup": { "from": "arbitrage_strategies", "localField": "_arbitrage_strategy", "foreignField": "_id", "as": "strategy" } },
+        doc! { "$match": { "strategy": { "$size": 0 } } },
+        doc! { "$project": { "_id": 1 } },
+    ], context).await
+}
+
+impl MongoDbContext {
+    fn exchanges(&self) -> Collection<Exchange> {
+        self.get_database().collection("exchanges")
+    }
+
+    fn assets(&self) -> Collection<Asset> {
+        self.get_database().collection("assets")
+    }
+
+    fn market_pairs(&self) -> Collection<MarketPair> {
+        self.get_database().collection("marketpairs")
+    }
+
+    fn strategies(&self) -> Collection<ArbitrageStrategy> {
+        self.get_database().collection("arbitrage_strategies")
+    }
+
+    fn asset_equivalences(&self) -> Collection<AssetEquivalence> {
+        self.get_database().collection("asset_equivalences")
+    }
+
+    fn users(&self) -> Collection<User> {
+        self.get_database().collection("users")
+    }
+
+    fn api_keys(&self) -> Collection<ApiKey> {
+        self.get_database().collection("api_keys")
+    }
+
+    fn market_quotes(&self) -> Collection<MarketQuote> {
+        self.get_database().collection("market_quotes")
+    }
+
+    fn market_quote_history(&self) -> Collection<MarketQuote> {
+        self.get_database().collection("market_quote_history")
+    }
+
+    fn order_books(&self) -> Collection<OrderBook> {
+        self.get_database().collection("order_books")
+    }
+
+    fn paper_balances(&self) -> Collection<PaperBalance> {
+        self.get_database().collection("paper_balances")
+    }
+
+    fn paper_trades(&self) -> Collection<PaperTrade> {
+        self.get_database().collection("paper_trades")
+    }
+
+    fn backtests(&self) -> Collection<BacktestJob> {
+        self.get_database().collection("backtests")
+    }
+
+    fn alert_rules(&self) -> Collection<AlertRule> {
+        self.get_database().collection("alert_rules")
+    }
+
+    fn alert_deliveries(&self) -> Collection<AlertDelivery> {
+        self.get_database().collection("alert_deliveries")
+    }
+
+    // Índices de los que depende la integridad de los datos; create_index no hace nada si ya existen
+    pub async fn ensure_indexes(&self) -> Result<(), AppError> {
+        let unique_pair = IndexModel::builder()
+            .keys(doc! { "_exchange": 1, "_base_asset": 1, "_quote_asset": 1 })
+            .options(IndexOptions::builder().unique(true).name("unique_exchange_base_quote".to_string()).build())
+            .build();
+        self.market_pairs().create_index(unique_pair).await.map_err(db_error("Failed to create market pair index"))?;
+
+        // Población de patas y comprobación de dependientes al borrar un par
+        let leg_pairs = IndexModel::builder().keys(doc! { "legs.market_pair": 1 }).build();
+        self.strategies().create_index(leg_pairs).await.map_err(db_error("Failed to create arbitrage strategy index"))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExchangeRepository for MongoDbContext {
+    async fn insert_exchange(&self, exchange: Exchange) -> Result<Exchange, AppError> {
+        let insert_result = self.exchanges().insert_one(&exchange).await.map_err(db_error("Failed to insert exchange"))?;
+        Ok(Exchange { id: insert_result.inserted_id.as_object_id(), ..exchange })
+    }
+
+    async fn find_exchange(&self, id: ObjectId) -> Result<Option<Exchange>, AppError> {
+        self.exchanges().find_one(doc! { "_id": id }).await.map_err(db_error("Failed to fetch exchange"))
+    }
+
+    async fn update_exchange(&self, id: ObjectId, exchange: Exchange) -> Result<Option<Exchange>, AppError> {
+        let update_doc = doc! {
+            "$set": {
+                "name": exchange.name,
+                "short_name": exchange.short_name,
+                "url": exchange.url,
+                "maker_fee": exchange.maker_fee,
+                "taker_fee": exchange.taker_fee,
+                "updated_at": exchange.updated_at,
+            }
+        };
+        let update_result = self.exchanges().update_one(doc! { "_id": id }, update_doc).await.map_err(db_error("Failed to update exchange"))?;
+        if update_result.matched_count == 0 {
+            return Ok(None);
+        }
+        self.find_exchange(id).await
+    }
+
+    async fn delete_exchange(&self, id: ObjectId) -> Result<bool, AppError> {
+        let delete_result = self.exchanges().delete_one(doc! { "_id": id }).await.map_err(db_error("Failed to delete exchange"))?;
+        Ok(delete_result.deleted_count > 0)
+    }
+
+    async fn list_exchanges(&self, page: &PageQuery) -> Result<Paginated<Exchange>, AppError> {
+        find_page(&self.exchanges(), doc! {}, doc! { "name": 1 }, page).await.map_err(db_error("Failed to fetch exchanges"))
+    }
+}
+
+#[async_trait]
+impl AssetRepository for MongoDbContext {
+    async fn insert_asset(&self, asset: Asset) -> Result<Asset, AppError> {
+        let insert_result = self.assets().insert_one(&asset).await.map_err(db_error("Failed to insert asset"))?;
+        Ok(Asset { id: insert_result.inserted_id.as_object_id(), ..asset })
+    }
+
+    async fn find_asset(&self, id: ObjectId) -> Result<Option<Asset>, AppError> {
+        self.assets().find_one(doc! { "_id": id }).await.map_err(db_error("Failed to fetch asset"))
+    }
+
+    async fn update_asset(&self, id: ObjectId, asset: Asset) -> Result<Option<Asset>, AppError> {
+        let update_doc = doc! {
+            "$set": {
+                "name": asset.name,
+                "short_name": asset.short_name,
+                "withdrawal_fee": asset.withdrawal_fee,
+                "updated_at": asset.updated_at,
+                "status": asset.status,
+                "_exchange": asset._exchange,
+            }
+        };
+        let update_result = self.assets().update_one(doc! { "_id": id }, update_doc).await.map_err(db_error("Failed to update asset"))?;
+        if update_result.matched_count == 0 {
+            return Ok(None);
+        }
+        self.find_asset(id).await
+    }
+
+    async fn delete_asset(&self, id: ObjectId) -> Result<bool, AppError> {
+        let delete_result = self.assets().delete_one(doc! { "_id": id }).await.map_err(db_error("Failed to delete asset"))?;
+        Ok(delete_result.deleted_count > 0)
+    }
+
+    async fn list_assets(&self, page: &PageQuery, search: Option<&str>, include_exchange: bool) -> Result<Paginated<Document>, AppError> {
+        let collection = self.get_database().collection::<Document>("assets");
+
+        // Construir el filtro basado en el término de búsqueda
+        let filter = match search {
+            Some(term) => doc! {
+                "$or": [
+                    { "name": contains_ignore_case(term) },
+                    { "short_name": contains_ignore_case(term) }
+                ]
+            },
+            None => doc! {},
+        };
+
+        let mut pipeline = vec![
+            doc! { "$match": filter.clone() },
+            doc! { "$skip": page.skip() as i64 },
+            doc! { "$limit": page.per_page() as i64 },
+        ];
+
+        if include_exchange {
+            pipeline.push(doc! {
+                "$lookup": {
+                    "from": "exchanges",
+                    "localField": "_exchange",
+                    "foreignField": "_id",
+                    "as": "exchange"
+                }
+            });
+            pipeline.push(doc! {
+                "$unwind": {
+                    "path": "$exchange",
+                    "preserveNullAndEmptyArrays": true
+                }
+            });
+        }
+
+        let assets = collect_aggregate(&collection, pipeline, "Failed to fetch assets").await?;
+        let total = collection.count_documents(filter).await.map_err(db_error("Failed to count assets"))?;
+
+        Ok(Paginated::new(assets, total, page))
+    }
+
+    async fn find_asset_ids_by_exchange(&self, exchange: ObjectId) -> Result<Vec<ObjectId>, AppError> {
+        ids_matching(&self.assets(), doc! { "_exchange": exchange }, "Failed to fetch assets of exchange").await
+    }
+}
+
+#[async_trait]
+impl MarketPairRepository for MongoDbContext {
+    async fn insert_market_pair(&self, market_pair: MarketPair) -> Result<MarketPair, AppError> {
+        let insert_result = self.market_pairs().insert_one(&market_pair).await.map_err(db_error("Failed to insert market pair"))?;
+        Ok(MarketPair { id: insert_result.inserted_id.as_object_id(), ..market_pair })
+    }
+
+    async fn find_market_pair(&self, id: ObjectId) -> Result<Option<MarketPair>, AppError> {
+        self.market_pairs().find_one(doc! { "_id": id }).await.map_err(db_error("Failed to fetch market pair"))
+    }
+
+    async fn update_market_pair(&self, id: ObjectId, market_pair: MarketPair) -> Result<Option<MarketPair>, AppError> {
+        let update_doc = doc! {
+            "$set": {
+                "_exchange": market_pair._exchange,
+                "_base_asset": market_pair._base_asset,
+                "_quote_asset": market_pair._quote_asset,
+                "maker_fee": market_pair.maker_fee,
+                "taker_fee": market_pair.taker_fee,
+                "updated_at": market_pair.updated_at,
+                "status": market_pair.status,
+            }
+        };
+        let update_result = self.market_pairs().update_one(doc! { "_id": id }, update_doc).await.map_err(db_error("Failed to update market pair"))?;
+        if update_result.matched_count == 0 {
+            return Ok(None);
+        }
+        self.find_market_pair(id).await
+    }
+
+    async fn find_market_pair_by_assets(&self, exchange: ObjectId, base_asset: ObjectId, quote_asset: ObjectId) -> Result<Option<MarketPair>, AppError> {
+        self.market_pairs()
+            .find_one(doc! { "_exchange": exchange, "_base_asset": base_asset, "_quote_asset": quote_asset })
+            .await
+            .map_err(db_error("Failed to fetch market pair"))
+    }
+
+    async fn delete_market_pair(&self, id: ObjectId) -> Result<bool, AppError> {
+        let delete_result = self.market_pairs().delete_one(doc! { "_id": id }).await.map_err(db_error("Failed to delete market pair"))?;
+        Ok(delete_result.deleted_count > 0)
+    }
+
+    async fn find_populated_market_pairs(&self, filter: MarketPairFilter) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        let collection = self.get_database().collection::<Document>("marketpairs");
+
+        // Filtro sobre el documento del par (antes de los lookups) o sobre los activos resueltos (después)
+        let (pair_match, populated_match) = match filter {
+            MarketPairFilter::Ids(ids) => (doc! { "_id": { "$in": ids } }, None),
+            MarketPairFilter::Exchange(exchange_id) => (doc! { "_exchange": exchange_id }, None),
+            MarketPairFilter::Active(exchange_ids) => {
+                let mut active = doc! { "status": true };
+                if !exchange_ids.is_empty() {
+                    active.insert("_exchange", doc! { "$in": exchange_ids });
+                }
+                (active, None)
+            },
+            MarketPairFilter::Linking(asset_a, asset_b) => (doc! {
+                "$or": [
+                    { "_base_asset": asset_a, "_quote_asset": asset_b },
+                    { "_base_asset": asset_b, "_quote_asset": asset_a },
+                ]
+            }, None),
+            MarketPairFilter::LinkingSymbols(symbols_a, symbols_b) => (doc! {}, Some(doc! {
+                "$or": [
+                    { "base_asset.short_name": { "$in": &symbols_a }, "quote_asset.short_name": { "$in": &symbols_b } },
+                    { "base_asset.short_name": { "$in": &symbols_b }, "quote_asset.short_name": { "$in": &symbols_a } },
+                ]
+            })),
+        };
+
+        let mut pipeline = vec![doc! { "$match": pair_match }];
+        pipeline.extend(populate_market_pair_stages());
+        if let Some(populated_match) = populated_match {
+            pipeline.push(doc! { "$match": populated_match });
+        }
+
+        collect_aggregate(&collection, pipeline, "Failed to aggregate market pairs").await
+    }
+
+    async fn list_populated_market_pairs(&self, page: &PageQuery, exchange: Option<ObjectId>, search: Option<&str>) -> Result<Paginated<PopulatedMarketPair>, AppError> {
+        let collection = self.get_database().collection::<Document>("marketpairs");
+
+        let mut initial_filter = doc! {};
+        if let Some(exchange_id) = exchange {
+            initial_filter.insert("_exchange", exchange_id);
+        }
+
+        let mut pipeline = vec![doc! { "$match": initial_filter }];
+        pipeline.extend(populate_market_pair_stages());
+
+        if let Some(term) = search {
+            pipeline.push(doc! {
+                "$match": {
+                    "$or": [
+                        { "base_asset.short_name": contains_ignore_case(term) },
+                        { "quote_asset.short_name": contains_ignore_case(term) }
+                    ]
+                }
+            });
+        }
+
+        // El total se cuenta sobre el mismo pipeline sin las etapas de paginación
+        let mut count_pipeline = pipeline.clone();
+        count_pipeline.push(doc! { "$count": "total" });
+
+        pipeline.push(doc! { "$skip": page.skip() as i64 });
+        pipeline.push(doc! { "$limit": page.per_page() as i64 });
+        let market_pairs = collect_aggregate(&collection, pipeline, "Failed to aggregate market pairs").await?;
+
+        let total = collection.aggregate(count_pipeline).await
+            .map_err(db_error("Failed to count market pairs"))?
+            .try_next().await
+            .map_err(db_error("Failed to get count result"))?
+            .and_then(|doc| doc.get_i32("total").map(i64::from).or_else(|_| doc.get_i64("total")).ok())
+            .unwrap_or(0) as u64;
+
+        Ok(Paginated::new(market_pairs, total, page))
+    }
+
+    async fn find_market_pair_ids_referencing(&self, exchange: Option<ObjectId>, assets: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        let mut conditions = vec![
+            doc! { "_base_asset": { "$in": assets } },
+            doc! { "_quote_asset": { "$in": assets } },
+        ];
+        if let Some(exchange) = exchange {
+            conditions.push(doc! { "_exchange": exchange });
+        }
+        ids_matching(&self.market_pairs(), doc! { "$or": conditions }, "Failed to fetch dependent market pairs").await
+    }
+}
+
+#[async_trait]
+impl StrategyRepository for MongoDbContext {
+    async fn insert_strategy(&self, strategy: ArbitrageStrategy) -> Result<ArbitrageStrategy, AppError> {
+        let insert_result = self.strategies().insert_one(&strategy).await.map_err(db_error("Failed to insert arbitrage strategy"))?;
+        Ok(ArbitrageStrategy { id: insert_result.inserted_id.as_object_id(), ..strategy })
+    }
+
+    async fn find_strategy(&self, id: ObjectId) -> Result<Option<ArbitrageStrategy>, AppError> {
+        self.strategies().find_one(doc! { "_id": id }).await.map_err(db_error("Failed to fetch arbitrage strategy"))
+    }
+
+    async fn find_owned_strategy(&self, id: ObjectId, owner: ObjectId) -> Result<Option<ArbitrageStrategy>, AppError> {
+        self.strategies().find_one(doc! { "_id": id, "_owner": owner }).await.map_err(db_error("Failed to fetch arbitrage strategy"))
+    }
+
+    async fn find_active_strategies(&self) -> Result<Vec<ArbitrageStrategy>, AppError> {
+        self.strategies().find(doc! { "status": true }).await
+            .map_err(db_error("Failed to fetch active arbitrage strategies"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through arbitrage strategies"))
+    }
+
+    async fn update_owned_strategy(&self, id: ObjectId, owner: ObjectId, strategy: ArbitrageStrategy) -> Result<Option<ArbitrageStrategy>, AppError> {
+        let update_doc = doc! {
+            "$set": {
+                "arbitrage_type": bson::to_bson(&strategy.arbitrage_type)?,
+                "legs": bson::to_bson(&strategy.legs)?,
+                "statistical": bson::to_bson(&strategy.statistical)?,
+                "updated_at": strategy.updated_at,
+                "status": strategy.status,
+                "execution_mode": bson::to_bson(&strategy.execution_mode)?,
+            }
+        };
+        let update_result = self.strategies().update_one(doc! { "_id": id, "_owner": owner }, update_doc).await
+            .map_err(db_error("Failed to update arbitrage strategy"))?;
+        if update_result.matched_count == 0 {
+            return Ok(None);
+        }
+        self.find_strategy(id).await
+    }
+
+    async fn delete_owned_strategy(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError> {
+        let delete_result = self.strategies().delete_one(doc! { "_id": id, "_owner": owner }).await
+            .map_err(db_error("Failed to delete arbitrage strategy"))?;
+        Ok(delete_result.deleted_count > 0)
+    }
+
+    async fn list_owned_strategies(&self, owner: ObjectId, arbitrage_type: Option<ArbitrageType>, page: &PageQuery) -> Result<Paginated<ArbitrageStrategy>, AppError> {
+        let mut filter = doc! { "_owner": owner };
+        if let Some(arbitrage_type) = arbitrage_type {
+            filter.insert("arbitrage_type", bson::to_bson(&arbitrage_type)?);
+        }
+        find_page(&self.strategies(), filter, doc! { "created_at": -1 }, page).await.map_err(db_error("Failed to fetch arbitrage strategies"))
+    }
+
+    async fn find_strategy_ids_referencing(&self, market_pairs: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        if market_pairs.is_empty() {
+            return Ok(Vec::new());
+        }
+        ids_matching(&self.strategies(), doc! { "legs.market_pair": { "$in": market_pairs } }, "Failed to fetch dependent arbitrage strategies").await
+    }
+
+    async fn find_unmigrated_strategy_ids(&self) -> Result<Vec<ObjectId>, AppError> {
+        ids_matching(&self.strategies(), legacy_strategy_filter(), "Failed to fetch unmigrated arbitrage strategies").await
+    }
+
+    async fn find_legacy_strategy_documents(&self) -> Result<Vec<Document>, AppError> {
+        self.strategies().clone_with_type::<Document>().find(legacy_strategy_filter()).await
+            .map_err(db_error("Failed to fetch legacy arbitrage strategies"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through legacy arbitrage strategies"))
+    }
+
+    async fn set_migrated_legs(&self, id: ObjectId, arbitrage_type: ArbitrageType, legs: Vec<Leg>, statistical: Option<StatisticalParams>) -> Result<(), AppError> {
+        let update = doc! {
+            "$set": {
+                "arbitrage_type": bson::to_bson(&arbitrage_type)?,
+                "legs": bson::to_bson(&legs)?,
+                "statistical": bson::to_bson(&statistical)?,
+            },
+            "$unset": { "details": "" },
+        };
+        self.strategies().update_one(doc! { "_id": id }, update).await.map_err(db_error("Failed to migrate arbitrage strategy"))?;
+        Ok(())
+    }
+}
+
+fn legacy_strategy_filter() -> Document {
+    doc! { "details": { "$exists": true }, "legs": { "$exists": false } }
+}
+
+#[async_trait]
+impl AssetEquivalenceRepository for MongoDbContext {
+    async fn insert_asset_equivalence(&self, equivalence: AssetEquivalence) -> Result<AssetEquivalence, AppError> {
+        let insert_result = self.asset_equivalences().insert_one(&equivalence).await.map_err(db_error("Failed to insert asset equivalence"))?;
+        Ok(AssetEquivalence { id: insert_result.inserted_id.as_object_id(), ..equivalence })
+    }
+
+    async fn find_asset_equivalence(&self, id: ObjectId) -> Result<Option<AssetEquivalence>, AppError> {
+        self.asset_equivalences().find_one(doc! { "_id": id }).await.map_err(db_error("Failed to fetch asset equivalence"))
+    }
+
+    async fn update_asset_equivalence(&self, id: ObjectId, equivalence: AssetEquivalence) -> Result<Option<AssetEquivalence>, AppError> {
+        let update_doc = doc! {
+            "$set": {
+                "peg": equivalence.peg,
+                "symbols": equivalence.symbols,
+                "priority": equivalence.priority,
+                "updated_at": equivalence.updated_at,
+            }
+        };
+        let update_result = self.asset_equivalences().update_one(doc! { "_id": id }, update_doc).await
+            .map_err(db_error("Failed to update asset equivalence"))?;
+        if update_result.matched_count == 0 {
+            return Ok(None);
+        }
+        self.find_asset_equivalence(id).await
+    }
+
+    async fn delete_asset_equivalence(&self, id: ObjectId) -> Result<bool, AppError> {
+        let delete_result = self.asset_equivalences().delete_one(doc! { "_id": id }).await.map_err(db_error("Failed to delete asset equivalence"))?;
+        Ok(delete_result.deleted_count > 0)
+    }
+
+    async fn find_asset_equivalences(&self) -> Result<Vec<AssetEquivalence>, AppError> {
+        self.asset_equivalences().find(doc! {}).sort(doc! { "priority": 1 }).await
+            .map_err(db_error("Failed to fetch asset equivalences"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through asset equivalences"))
+    }
+
+    async fn list_asset_equivalences(&self, page: &PageQuery) -> Result<Paginated<AssetEquivalence>, AppError> {
+        find_page(&self.asset_equivalences(), doc! {}, doc! { "priority": 1 }, page).await.map_err(db_error("Failed to fetch asset equivalences"))
+    }
+}
+
+#[async_trait]
+impl IntegrityRepository for MongoDbContext {
+    async fn delete_catalog_documents(&self, documents: &CatalogDocuments) -> Result<(), AppError> {
+        let db = self.get_database();
+        let mut session = self.client.start_session().await.map_err(db_error("Failed to start session"))?;
+        session.start_transaction().await.map_err(db_error("Failed to start transaction"))?;
+
+        // Si falla cualquiera de los borrados no se aplica ninguno
+        let deletions = [
+            ("alert_deliveries", "_alert_rule", &documents.alert_rules),
+            ("alert_rules", "_id", &documents.alert_rules),
+            ("backtests", "_id", &documents.backtests),
+            ("paper_trades", "_id", &documents.paper_trades),
+            ("arbitrage_strategies", "_id", &documents.strategies),
+            ("marketpairs", "_id", &documents.market_pairs),
+            ("assets", "_id", &documents.assets),
+            ("exchanges", "_id", &documents.exchanges),
+        ];
+        for (collection, field, ids) in deletions.into_iter().filter(|(_, _, ids)| !ids.is_empty()) {
+            let result = db.collection::<Document>(collection)
+                .delete_many(doc! { field: { "$in": ids.as_slice() } })
+                .session(&mut session)
+                .await;
+            if let Err(e) = result {
+                if let Err(abort_error) = session.abort_transaction().await {
+                    error!("Failed to abort catalog deletion: {}", abort_error);
+                }
+                return Err(db_error("Failed to delete catalog documents")(e));
+            }
+        }
+
+        session.commit_transaction().await.map_err(db_error("Failed to commit catalog deletion"))
+    }
+
+    async fn find_orphans(&self) -> Result<CatalogDocuments, AppError> {
+        let db = self.get_database();
+
+        let assets = ids_from_aggregate(&db.collection("assets"), vec![
+            doc! { "$lookup": { "from": "exchanges", "localField": "_exchange", "foreignField": "_id", "as": "exchange" } },
+            doc! { "$match": { "exchange": { "$size": 0 } } },
+            doc! { "$project": { "_id": 1 } },
+        ], "Failed to find orphaned assets").await?;
+
+        // Sin $unwind: un lookup vacío es justo lo que se busca
+        let mut pipeline: Vec<Document> = populate_market_pair_stages().into_iter().filter(|stage| stage.contains_key("$lookup")).collect();
+        pipeline.push(doc! {
+            "$match": {
+                "$or": [
+                    { "exchange": { "$size": 0 } },
+                    { "base_asset": { "$size": 0 } },
+                    { "quote_asset": { "$size": 0 } },
+                ]
+            }
+        });
+        pipeline.push(doc! { "$project": { "_id": 1 } });
+        let market_pairs = ids_from_aggregate(&db.collection("marketpairs"), pipeline, "Failed to find orphaned market pairs").await?;
+
+        let strategies: Vec<ArbitrageStrategy> = self.strategies().find(doc! {}).await
+            .map_err(db_error("Failed to fetch arbitrage strategies"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through arbitrage strategies"))?;
+        let mut referenced: Vec<ObjectId> = strategies.iter().flat_map(|s| ArbitrageStrategyService::leg_pair_ids(&s.legs)).collect();
+        referenced.sort();
+        referenced.dedup();
+        let existing = ids_matching(&self.market_pairs(), doc! { "_id": { "$in": referenced } }, "Failed to fetch market pairs").await?;
+        let strategies = strategies.iter()
+            .filter(|s| ArbitrageStrategyService::leg_pair_ids(&s.legs).iter().any(|id| !existing.contains(id)))
+            .filter_map(|s| s.id)
+            .collect();
+
+        let alert_rules = ids_without_strategy(&db, "alert_rules", "Failed to find orphaned alert rules").await?;
+        let backtests = ids_without_strategy(&db, "backtests", "Failed to find orphaned backtests").await?;
+        let paper_trades = ids_without_strategy(&db, "paper_trades", "Failed to find orphaned paper trades").await?;
+
+        Ok(CatalogDocuments { exchanges: Vec::new(), assets, market_pairs, strategies, alert_rules, backtests, paper_trades })
+    }
+}
+
+#[async_trait]
+impl UserRepository for MongoDbContext {
+    async fn insert_user(&self, user: User) -> Result<User, AppError> {
+        let insert_result = self.users().insert_one(&user).await.map_err(db_error("Failed to insert user"))?;
+        Ok(User { id: insert_result.inserted_id.as_object_id(), ..user })
+    }
+
+    async fn find_user(&self, id: ObjectId) -> Result<Option<User>, AppError> {
+        self.users().find_one(doc! { "_id": id }).await.map_err(db_error("Failed to fetch user"))
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, AppError> {
+        self.users().find_one(doc! { "email": email }).await.map_err(db_error("Failed to fetch user"))
+    }
+
+    async fn update_profile(&self, id: ObjectId, update: ProfileUpdate) -> Result<Option<User>, AppError> {
+        let mut update_doc = doc! {};
+        if let Some(name) = update.name {
+            update_doc.insert("name", name);
+        }
+        if let Some(email) = update.email {
+            update_doc.insert("email", email);
+        }
+        if let Some(password_hash) = update.password_hash {
+            update_doc.insert("password", password_hash);
+        }
+        if let Some(default_market_pair) = update.default_market_pair {
+            update_doc.insert("_default_market_pair", default_market_pair);
+        }
+
+        // Solo realiza la actualización si hay campos para actualizar
+        if !update_doc.is_empty() {
+            self.users().update_one(doc! { "_id": id }, doc! { "$set": update_doc }).await.map_err(db_error("Failed to update user"))?;
+        }
+        self.find_user(id).await
+    }
+
+    async fn set_role(&self, id: ObjectId, role: Role) -> Result<Option<User>, AppError> {
+        let update_result = self.users().update_one(doc! { "_id": id }, doc! { "$set": { "role": bson::to_bson(&role)? } }).await
+            .map_err(db_error("Failed to update user role"))?;
+        if update_result.matched_count == 0 {
+            return Ok(None);
+        }
+        self.find_user(id).await
+    }
+
+    async fn set_password_reset(&self, id: ObjectId, token_hash: &str, expires: NaiveDateTime) -> Result<(), AppError> {
+        self.users()
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "password_reset_token": token_hash, "password_reset_expires": bson::to_bson(&expires)? } },
+            )
+            .await
+            .map_err(db_error("Failed to store password reset token"))?;
+        Ok(())
+    }
+
+    async fn find_user_by_reset_token(&self, token_hash: &str) -> Result<Option<User>, AppError> {
+        self.users().find_one(doc! { "password_reset_token": token_hash }).await.map_err(db_error("Failed to fetch user"))
+    }
+
+    async fn consume_password_reset(&self, id: ObjectId, token_hash: &str, password_hash: &str) -> Result<bool, AppError> {
+        let update_result = self.users()
+            .update_one(
+                doc! { "_id": id, "password_reset_token": token_hash },
+                doc! { "$set": { "password": password_hash, "password_reset_token": "", "tokens": [] } },
+            )
+            .await
+            .map_err(db_error("Failed to reset password"))?;
+        Ok(update_result.matched_count > 0)
+    }
+
+    async fn add_session(&self, user_id: ObjectId, session: Session, now: f64) -> Result<(), AppError> {
+        let users = self.users();
+        users
+            .update_one(doc! { "_id": user_id }, doc! { "$pull": { "tokens": { "expires_at": { "$lte": now } } } })
+            .await
+            .map_err(db_error("Failed to prune sessions"))?;
+        users
+            .update_one(doc! { "_id": user_id }, doc! { "$push": { "tokens": bson::to_bson(&session)? } })
+            .await
+            .map_err(db_error("Failed to create session"))?;
+        Ok(())
+    }
+
+    async fn find_user_by_session(&self, session_id: &str) -> Result<Option<User>, AppError> {
+        self.users().find_one(doc! { "tokens.id": session_id }).await.map_err(db_error("Failed to fetch user"))
+    }
+
+    async fn rotate_session(&self, user_id: ObjectId, session_id: &str, current_hash: &str, new_hash: &str, user_agent: &str, now: f64) -> Result<bool, AppError> {
+        // La condición sobre el hash evita que dos refresh simultáneos roten la misma sesión
+        let update_result = self.users()
+            .update_one(
+                doc! { "_id": user_id, "tokens": { "$elemMatch": { "id": session_id, "refresh_token_hash": current_hash } } },
+                doc! { "$set": {
+                    "tokens.$.refresh_token_hash": new_hash,
+                    "tokens.$.user_agent": user_agent,
+                    "tokens.$.last_used_at": now,
+                } },
+            )
+            .await
+            .map_err(db_error("Failed to rotate session"))?;
+        Ok(update_result.matched_count > 0)
+    }
+
+    async fn touch_session(&self, user_id: ObjectId, session_id: &str, now: f64) -> Result<bool, AppError> {
+        let update_result = self.users()
+            .update_one(
+                doc! { "_id": user_id, "tokens": { "$elemMatch": { "id": session_id, "expires_at": { "$gt": now } } } },
+                doc! { "$set": { "tokens.$.last_used_at": now } },
+            )
+            .await
+            .map_err(db_error("Failed to check session"))?;
+        Ok(update_result.matched_count > 0)
+    }
+
+    async fn remove_session(&self, user_id: ObjectId, session_id: &str) -> Result<(), AppError> {
+        self.users()
+            .update_one(doc! { "_id": user_id }, doc! { "$pull": { "tokens": { "id": session_id } } })
+            .await
+            .map_err(db_error("Failed to revoke session"))?;
+        Ok(())
+    }
+
+    async fn clear_sessions(&self, user_id: ObjectId) -> Result<(), AppError> {
+        self.users()
+            .update_one(doc! { "_id": user_id }, doc! { "$set": { "tokens": [] } })
+            .await
+            .map_err(db_error("Failed to revoke sessions"))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for MongoDbContext {
+    async fn insert_api_key(&self, api_key: ApiKey) -> Result<ApiKey, AppError> {
+        // secret_hash no se serializa para que no salga en las respuestas; se añade al documento a mano
+        let mut document = bson::to_document(&api_key)?;
+        document.insert("secret_hash", &api_key.secret_hash);
+        let insert_result = self.api_keys().clone_with_type::<Document>().insert_one(document).await
+            .map_err(db_error("Failed to insert API key"))?;
+        Ok(ApiKey { id: insert_result.inserted_id.as_object_id(), ..api_key })
+    }
+
+    async fn list_owned_api_keys(&self, owner: ObjectId, page: &PageQuery) -> Result<Paginated<ApiKey>, AppError> {
+        find_page(&self.api_keys(), doc! { "_user": owner }, doc! { "created_at": -1 }, page).await.map_err(db_error("Failed to fetch API keys"))
+    }
+
+    async fn delete_owned_api_key(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError> {
+        let delete_result = self.api_keys().delete_one(doc! { "_id": id, "_user": owner }).await
+            .map_err(db_error("Failed to delete API key"))?;
+        Ok(delete_result.deleted_count > 0)
+    }
+
+    async fn use_api_key(&self, id: ObjectId, secret_hash: &str, now: f64) -> Result<Option<ApiKey>, AppError> {
+        let filter = doc! {
+            "_id": id,
+            "secret_hash": secret_hash,
+            "$or": [{ "expires_at": null }, { "expires_at": { "$gt": now } }],
+        };
+        self.api_keys().find_one_and_update(filter, doc! { "$set": { "last_used_at": now } }).await
+            .map_err(db_error("Failed to check API key"))
+    }
+}
+
+#[async_trait]
+impl MarketDataRepository for MongoDbContext {
+    async fn store_quote(&self, quote: MarketQuote) -> Result<(), AppError> {
+        self.market_quotes().replace_one(doc! { "_market_pair": quote._market_pair }, &quote).upsert(true).await
+            .map_err(db_error("Failed to store market quote"))?;
+        self.market_quote_history().insert_one(&quote).await
+            .map_err(db_error("Failed to store market quote history"))?;
+        Ok(())
+    }
+
+    async fn find_quote(&self, market_pair: ObjectId) -> Result<Option<MarketQuote>, AppError> {
+        self.market_quotes().find_one(doc! { "_market_pair": market_pair }).await.map_err(db_error("Failed to fetch market quote"))
+    }
+
+    async fn find_quotes(&self, market_pairs: &[ObjectId]) -> Result<Vec<MarketQuote>, AppError> {
+        self.market_quotes().find(doc! { "_market_pair": { "$in": market_pairs } }).await
+            .map_err(db_error("Failed to fetch market quotes"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through market quotes"))
+    }
+
+    async fn find_quote_history(&self, market_pairs: &[ObjectId], from: f64, to: f64) -> Result<Vec<MarketQuote>, AppError> {
+        self.market_quote_history()
+            .find(doc! { "_market_pair": { "$in": market_pairs }, "timestamp": { "$gte": from, "$lte": to } })
+            .sort(doc! { "timestamp": 1 })
+            .await
+            .map_err(db_error("Failed to fetch market quote history"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through market quote history"))
+    }
+
+    async fn store_order_book(&self, order_book: OrderBook) -> Result<(), AppError> {
+        self.order_books().replace_one(doc! { "_market_pair": order_book._market_pair }, &order_book).upsert(true).await
+            .map_err(db_error("Failed to store order book"))?;
+        Ok(())
+    }
+
+    async fn find_order_book(&self, market_pair: ObjectId) -> Result<Option<OrderBook>, AppError> {
+        self.order_books().find_one(doc! { "_market_pair": market_pair }).await.map_err(db_error("Failed to fetch order book"))
+    }
+
+    async fn find_order_books(&self, market_pairs: &[ObjectId]) -> Result<Vec<OrderBook>, AppError> {
+        self.order_books().find(doc! { "_market_pair": { "$in": market_pairs } }).await
+            .map_err(db_error("Failed to fetch order books"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through order books"))
+    }
+}
+
+#[async_trait]
+impl PaperTradingRepository for MongoDbContext {
+    async fn find_paper_balances(&self, user: ObjectId) -> Result<Vec<PaperBalance>, AppError> {
+        self.paper_balances().find(doc! { "_user": user }).sort(doc! { "_exchange": 1, "asset": 1 }).await
+            .map_err(db_error("Failed to fetch paper balances"))?
+            .try_collect().await
+            .map_err(db_error("Failed to iterate through paper balances"))
+    }
+
+    // Los negativos solo se aplican si el saldo guardado sigue cubriéndolos, así dos ejecuciones
+    // simultáneas no pueden dejarlo en negativo
+    async fn apply_paper_deltas(&self, user: ObjectId, deltas: &Balances, now: f64) -> Result<(), AppError> {
+        let collection = self.paper_balances();
+        let mut session = self.client.start_session().await.map_err(db_error("Failed to start session"))?;
+        session.start_transaction().await.map_err(db_error("Failed to start transaction"))?;
+
+        for ((exchange_id, asset), delta) in deltas.iter() {
+            let mut filter = doc! { "_user": user, "_exchange": exchange_id, "asset": asset };
+            if *delta < 0.0 {
+                filter.insert("amount", doc! { "$gte": -delta - BALANCE_EPSILON });
+            }
+            let result = collection
+                .update_one(filter, doc! { "$inc": { "amount": delta }, "$set": { "updated_at": now } })
+                .upsert(*delta > 0.0)
+                .session(&mut session)
+                .await;
+
+            let error = match result {
+                Ok(result) if *delta < 0.0 && result.matched_count == 0 => {
+                    AppError::Conflict(format!("Insufficient paper balance of {} on exchange {}", asset, exchange_id))
+                },
+                Ok(_) => continue,
+                Err(e) => db_error("Failed to update paper balance")(e),
+            };
+            if let Err(abort_error) = session.abort_transaction().await {
+                error!("Failed to abort paper balance update: {}", abort_error);
+            }
+            return Err(error);
+        }
+
+        session.commit_transaction().await.map_err(db_error("Failed to commit paper balance update"))
+    }
+
+    async fn insert_paper_trade(&self, trade: PaperTrade) -> Result<PaperTrade, AppError> {
+        let insert_result = self.paper_trades().insert_one(&trade).await.map_err(db_error("Failed to insert paper trade"))?;
+        Ok(PaperTrade { id: insert_result.inserted_id.as_object_id(), ..trade })
+    }
+
+    async fn list_paper_trades(&self, user: ObjectId, page: &PageQuery) -> Result<Paginated<PaperTrade>, AppError> {
+        find_page(&self.paper_trades(), doc! { "_user": user }, doc! { "created_at": -1 }, page).await.map_err(db_error("Failed to fetch paper trades"))
+    }
+
+    async fn find_paper_trade_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        ids_matching(&self.paper_trades(), doc! { "_arbitrage_strategy": { "$in": strategies } }, "Failed to fetch paper trades").await
+    }
+}
+
+#[async_trait]
+impl BacktestRepository for MongoDbContext {
+    async fn insert_backtest(&self, job: BacktestJob) -> Result<BacktestJob, AppError> {
+        let insert_result = self.backtests().insert_one(&job).await.map_err(db_error("Failed to insert backtest job"))?;
+        Ok(BacktestJob { id: insert_result.inserted_id.as_object_id(), ..job })
+    }
+
+    async fn find_backtest(&self, id: ObjectId) -> Result<Option<BacktestJob>, AppError> {
+        self.backtests().find_one(doc! { "_id": id }).await.map_err(db_error("Failed to fetch backtest job"))
+    }
+
+    async fn set_backtest_status(&self, id: ObjectId, status: BacktestStatus, report: Option<BacktestReport>, error: Option<String>, now: f64) -> Result<(), AppError> {
+        let update_doc = doc! {
+            "$set": {
+                "status": bson::to_bson(&status)?,
+                "report": bson::to_bson(&report)?,
+                "error": error,
+                "updated_at": now,
+            }
+        };
+        self.backtests().update_one(doc! { "_id": id }, update_doc).await.map_err(db_error("Failed to update backtest job"))?;
+        Ok(())
+    }
+
+    async fn list_strategy_backtests(&self, strategy: ObjectId, page: &PageQuery) -> Result<Paginated<BacktestJob>, AppError> {
+        find_page(&self.backtests(), doc! { "_arbitrage_strategy": strategy }, doc! { "created_at": -1 }, page).await
+            .map_err(db_error("Failed to fetch backtest jobs"))
+    }
+
+    async fn find_backtest_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        ids_matching(&self.backtests(), doc! { "_arbitrage_strategy": { "$in": strategies } }, "Failed to fetch backtest jobs").await
+    }
+}
+
+#[async_trait]
+impl AlertRepository for MongoDbContext {
+    async fn insert_alert_rule(&self, rule: AlertRule) -> Result<AlertRule, AppError> {
+        let insert_result = self.alert_rules().insert_one(&rule).await.map_err(db_error("Failed to insert alert rule"))?;
+        Ok(AlertRule { id: insert_result.inserted_id.as_object_id(), ..rule })
+    }
+
+    async fn find_owned_alert_rule(&self, id: ObjectId, owner: ObjectId) -> Result<Option<AlertRule>, AppError> {
+        self.alert_rules().find_one(doc! { "_id": id, "_user": owner }).await.map_err(db_error("Failed to fetch alert rule"))
+    }
+
+    async fn update_owned_alert_rule(&self, id: ObjectId, owner: ObjectId, rule: AlertRule) -> Result<Option<AlertRule>, AppError> {
+        let update_doc = doc! {
+            "$set": {
+                "min_profit": rule.min_profit,
+                "min_duration": rule.min_duration,
+                "webhook_url": rule.webhook_url,
+                "secret": rule.secret,
+                "status": rule.status,
+                "above_since": bson::Bson::Null,
+                "fired": false,
+                "updated_at": rule.updated_at,
+            }
+        };
+        let update_result = self.alert_rules().update_one(doc! { "_id": id, "_user": owner }, update_doc).await
+            .map_err(db_error("Failed to update alert rule"))?;
+        if update_result.matched_count == 0 {
+            return Ok(None);
+        }
+        self.find_owned_alert_rule(id, owner).await
+    }
+
+    async fn delete_owned_alert_rule(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError> {
+        let delete_result = self.alert_rules().delete_one(doc! { "_id": id, "_user": owner }).await
+            .map_err(db_error("Failed to delete alert rule"))?;
+        Ok(delete_result.deleted_count > 0)
+    }
+
+    async fn list_owned_alert_rules(&self, owner: ObjectId, page: &PageQuery) -> Result<Paginated<AlertRule>, AppError> {
+        find_page(&self.alert_rules(), doc! { "_user": owner }, doc! { "created_at": -1 }, page).await.map_err(db_error("Failed to fetch alert rules"))
+    }
+
+    async fn find_active_alert_rules(&self, strategy: ObjectId) -> Result<Vec<AlertRule>, AppError> {
+        self.alert_rules().find(doc! { "_arbitrage_strategy": strategy, "status": true }).await
+            .map_err(db_error("Failed to fetch alert rules"))?
+            .try_collect().await
+            .map_err(db_error("Failed to read alert rules"))
+    }
+
+    async fn set_alert_rule_state(&self, id: ObjectId, above_since: Option<f64>, fired: bool) -> Result<(), AppError> {
+        self.alert_rules().update_one(doc! { "_id": id }, doc! { "$set": { "above_since": above_since, "fired": fired } }).await
+            .map_err(db_error("Failed to update alert rule state"))?;
+        Ok(())
+    }
+
+    async fn insert_alert_delivery(&self, delivery: AlertDelivery) -> Result<(), AppError> {
+        self.alert_deliveries().insert_one(&delivery).await.map_err(db_error("Failed to insert alert delivery"))?;
+        Ok(())
+    }
+
+    async fn list_alert_deliveries(&self, rule: ObjectId, page: &PageQuery) -> Result<Paginated<AlertDelivery>, AppError> {
+        find_page(&self.alert_deliveries(), doc! { "_alert_rule": rule }, doc! { "created_at": -1 }, page).await
+            .map_err(db_error("Failed to fetch alert deliveries"))
+    }
+
+    async fn find_alert_rule_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError> {
+        ids_matching(&self.alert_rules(), doc! { "_arbitrage_strategy": { "$in": strategies } }, "Failed to fetch alert rules").await
+    }
+}
@@ -0,0 +1,216 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use mongodb::bson::{oid::ObjectId, Document};
+use crate::helpers::app_error::AppError;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::modules::alert::alert_schema::{AlertDelivery, AlertRule};
+use crate::modules::api_key::api_key_schema::ApiKey;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, Leg, StatisticalParams};
+use crate::modules::asset::asset_schema::Asset;
+use crate::modules::asset_equivalence::asset_equivalence_schema::AssetEquivalence;
+use crate::modules::backtest::backtest_schema::{BacktestJob, BacktestReport, BacktestStatus};
+use crate::modules::exchange::exchange_schema::Exchange;
+use crate::modules::integrity::integrity_schema::CatalogDocuments;
+use crate::modules::market_data::market_data_schema::{MarketQuote, OrderBook};
+use crate::modules::market_pair::market_pair_schema::MarketPair;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use crate::modules::paper_trading::paper_trading_schema::{PaperBalance, PaperTrade};
+use crate::modules::paper_trading::simulated_exchange::Balances;
+use crate::modules::user::user_schema::{Role, Session, User};
+
+// Acceso a datos de los recursos principales. MongoDbContext es la implementación real;
+// los tests usan InMemoryStorage. Los update devuelven None y los delete false si el id no existe.
+
+#[async_trait]
+pub trait ExchangeRepository: Send + Sync {
+    async fn insert_exchange(&self, exchange: Exchange) -> Result<Exchange, AppError>;
+    async fn find_exchange(&self, id: ObjectId) -> Result<Option<Exchange>, AppError>;
+    async fn update_exchange(&self, id: ObjectId, exchange: Exchange) -> Result<Option<Exchange>, AppError>;
+    async fn delete_exchange(&self, id: ObjectId) -> Result<bool, AppError>;
+    async fn list_exchanges(&self, page: &PageQuery) -> Result<Paginated<Exchange>, AppError>;
+}
+
+#[async_trait]
+pub trait AssetRepository: Send + Sync {
+    async fn insert_asset(&self, asset: Asset) -> Result<Asset, AppError>;
+    async fn find_asset(&self, id: ObjectId) -> Result<Option<Asset>, AppError>;
+    async fn update_asset(&self, id: ObjectId, asset: Asset) -> Result<Option<Asset>, AppError>;
+    async fn delete_asset(&self, id: ObjectId) -> Result<bool, AppError>;
+    // Documentos crudos: con include_exchange cada activo lleva su exchange en "exchange"
+    async fn list_assets(&self, page: &PageQuery, search: Option<&str>, include_exchange: bool) -> Result<Paginated<Document>, AppError>;
+    async fn find_asset_ids_by_exchange(&self, exchange: ObjectId) -> Result<Vec<ObjectId>, AppError>;
+}
+
+// Selección de pares para las consultas que devuelven pares poblados
+#[derive(Debug, Clone)]
+pub enum MarketPairFilter {
+    Ids(Vec<ObjectId>),
+    Exchange(ObjectId),
+    // Pares activos de esos exchanges; vacío significa todo el catálogo
+    Active(Vec<ObjectId>),
+    // Pares entre los dos activos, en cualquier sentido
+    Linking(ObjectId, ObjectId),
+    // Igual que Linking pero por símbolo, con una lista de variantes a cada lado
+    LinkingSymbols(Vec<String>, Vec<String>),
+}
+
+#[async_trait]
+pub trait MarketPairRepository: Send + Sync {
+    async fn insert_market_pair(&self, market_pair: MarketPair) -> Result<MarketPair, AppError>;
+    async fn find_market_pair(&self, id: ObjectId) -> Result<Option<MarketPair>, AppError>;
+    async fn update_market_pair(&self, id: ObjectId, market_pair: MarketPair) -> Result<Option<MarketPair>, AppError>;
+    async fn delete_market_pair(&self, id: ObjectId) -> Result<bool, AppError>;
+    // (exchange, base, quote) es único; insertar o actualizar a una combinación existente da Conflict
+    async fn find_market_pair_by_assets(&self, exchange: ObjectId, base_asset: ObjectId, quote_asset: ObjectId) -> Result<Option<MarketPair>, AppError>;
+    // Los pares cuyo exchange o activos no existen se omiten
+    async fn find_populated_market_pairs(&self, filter: MarketPairFilter) -> Result<Vec<PopulatedMarketPair>, AppError>;
+    // search filtra por el símbolo del activo base o cotizado
+    async fn list_populated_market_pairs(&self, page: &PageQuery, exchange: Option<ObjectId>, search: Option<&str>) -> Result<Paginated<PopulatedMarketPair>, AppError>;
+    // Pares del exchange indicado o cuyo activo base o cotizado está en `assets`
+    async fn find_market_pair_ids_referencing(&self, exchange: Option<ObjectId>, assets: &[ObjectId]) -> Result<Vec<ObjectId>, AppError>;
+}
+
+#[async_trait]
+pub trait StrategyRepository: Send + Sync {
+    async fn insert_strategy(&self, strategy: ArbitrageStrategy) -> Result<ArbitrageStrategy, AppError>;
+    async fn find_strategy(&self, id: ObjectId) -> Result<Option<ArbitrageStrategy>, AppError>;
+    async fn find_owned_strategy(&self, id: ObjectId, owner: ObjectId) -> Result<Option<ArbitrageStrategy>, AppError>;
+    async fn find_active_strategies(&self) -> Result<Vec<ArbitrageStrategy>, AppError>;
+    async fn update_owned_strategy(&self, id: ObjectId, owner: ObjectId, strategy: ArbitrageStrategy) -> Result<Option<ArbitrageStrategy>, AppError>;
+    async fn delete_owned_strategy(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError>;
+    async fn list_owned_strategies(&self, owner: ObjectId, arbitrage_type: Option<ArbitrageType>, page: &PageQuery) -> Result<Paginated<ArbitrageStrategy>, AppError>;
+    // Estrategias de cualquier usuario que usan alguno de los pares
+    async fn find_strategy_ids_referencing(&self, market_pairs: &[ObjectId]) -> Result<Vec<ObjectId>, AppError>;
+    // Estrategias que siguen con el formato de details anterior (sin patas)
+    async fn find_unmigrated_strategy_ids(&self) -> Result<Vec<ObjectId>, AppError>;
+    // Los mismos documentos en crudo, para convertirlos
+    async fn find_legacy_strategy_documents(&self) -> Result<Vec<Document>, AppError>;
+    // Guarda las patas de una estrategia migrada y quita su details
+    async fn set_migrated_legs(&self, id: ObjectId, arbitrage_type: ArbitrageType, legs: Vec<Leg>, statistical: Option<StatisticalParams>) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait AssetEquivalenceRepository: Send + Sync {
+    async fn insert_asset_equivalence(&self, equivalence: AssetEquivalence) -> Result<AssetEquivalence, AppError>;
+    async fn find_asset_equivalence(&self, id: ObjectId) -> Result<Option<AssetEquivalence>, AppError>;
+    async fn update_asset_equivalence(&self, id: ObjectId, equivalence: AssetEquivalence) -> Result<Option<AssetEquivalence>, AppError>;
+    async fn delete_asset_equivalence(&self, id: ObjectId) -> Result<bool, AppError>;
+    // Todos los grupos, de menor a mayor prioridad
+    async fn find_asset_equivalences(&self) -> Result<Vec<AssetEquivalence>, AppError>;
+    async fn list_asset_equivalences(&self, page: &PageQuery) -> Result<Paginated<AssetEquivalence>, AppError>;
+}
+
+// Cotizaciones y libros de órdenes; de cada par se guarda el último y, de las cotizaciones, también el historial
+#[async_trait]
+pub trait MarketDataRepository: Send + Sync {
+    async fn store_quote(&self, quote: MarketQuote) -> Result<(), AppError>;
+    async fn find_quote(&self, market_pair: ObjectId) -> Result<Option<MarketQuote>, AppError>;
+    async fn find_quotes(&self, market_pairs: &[ObjectId]) -> Result<Vec<MarketQuote>, AppError>;
+    // Historial entre `from` y `to` (inclusivos), en orden cronológico
+    async fn find_quote_history(&self, market_pairs: &[ObjectId], from: f64, to: f64) -> Result<Vec<MarketQuote>, AppError>;
+    async fn store_order_book(&self, order_book: OrderBook) -> Result<(), AppError>;
+    async fn find_order_book(&self, market_pair: ObjectId) -> Result<Option<OrderBook>, AppError>;
+    async fn find_order_books(&self, market_pairs: &[ObjectId]) -> Result<Vec<OrderBook>, AppError>;
+}
+
+#[async_trait]
+pub trait PaperTradingRepository: Send + Sync {
+    // Ordenados por exchange y activo
+    async fn find_paper_balances(&self, user: ObjectId) -> Result<Vec<PaperBalance>, AppError>;
+    // Aplica todos los cambios o ninguno; da Conflict si algún negativo deja el saldo por debajo de cero
+    async fn apply_paper_deltas(&self, user: ObjectId, deltas: &Balances, now: f64) -> Result<(), AppError>;
+    async fn insert_paper_trade(&self, trade: PaperTrade) -> Result<PaperTrade, AppError>;
+    async fn list_paper_trades(&self, user: ObjectId, page: &PageQuery) -> Result<Paginated<PaperTrade>, AppError>;
+    // Operaciones de cualquier usuario sobre alguna de las estrategias
+    async fn find_paper_trade_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError>;
+}
+
+#[async_trait]
+pub trait BacktestRepository: Send + Sync {
+    async fn insert_backtest(&self, job: BacktestJob) -> Result<BacktestJob, AppError>;
+    async fn find_backtest(&self, id: ObjectId) -> Result<Option<BacktestJob>, AppError>;
+    async fn set_backtest_status(&self, id: ObjectId, status: BacktestStatus, report: Option<BacktestReport>, error: Option<String>, now: f64) -> Result<(), AppError>;
+    async fn list_strategy_backtests(&self, strategy: ObjectId, page: &PageQuery) -> Result<Paginated<BacktestJob>, AppError>;
+    async fn find_backtest_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError>;
+}
+
+#[async_trait]
+pub trait AlertRepository: Send + Sync {
+    async fn insert_alert_rule(&self, rule: AlertRule) -> Result<AlertRule, AppError>;
+    async fn find_owned_alert_rule(&self, id: ObjectId, owner: ObjectId) -> Result<Option<AlertRule>, AppError>;
+    // Sustituye la configuración de la regla y la rearma
+    async fn update_owned_alert_rule(&self, id: ObjectId, owner: ObjectId, rule: AlertRule) -> Result<Option<AlertRule>, AppError>;
+    async fn delete_owned_alert_rule(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError>;
+    async fn list_owned_alert_rules(&self, owner: ObjectId, page: &PageQuery) -> Result<Paginated<AlertRule>, AppError>;
+    // Reglas activas de la estrategia, de cualquier usuario
+    async fn find_active_alert_rules(&self, strategy: ObjectId) -> Result<Vec<AlertRule>, AppError>;
+    async fn set_alert_rule_state(&self, id: ObjectId, above_since: Option<f64>, fired: bool) -> Result<(), AppError>;
+    async fn insert_alert_delivery(&self, delivery: AlertDelivery) -> Result<(), AppError>;
+    async fn list_alert_deliveries(&self, rule: ObjectId, page: &PageQuery) -> Result<Paginated<AlertDelivery>, AppError>;
+    // Reglas de cualquier usuario sobre alguna de las estrategias
+    async fn find_alert_rule_ids_referencing(&self, strategies: &[ObjectId]) -> Result<Vec<ObjectId>, AppError>;
+}
+
+#[async_trait]
+pub trait IntegrityRepository: Send + Sync {
+    // Borra todos los documentos indicados de forma atómica, junto con los envíos de las reglas borradas
+    async fn delete_catalog_documents(&self, documents: &CatalogDocuments) -> Result<(), AppError>;
+    // Activos sin exchange, pares sin exchange o activos, estrategias con algún par inexistente y
+    // reglas, backtests u operaciones paper de estrategias que ya no existen
+    async fn find_orphans(&self) -> Result<CatalogDocuments, AppError>;
+}
+
+// Campos de perfil que el usuario puede cambiar; None deja el valor actual
+#[derive(Debug, Clone, Default)]
+pub struct ProfileUpdate {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub password_hash: Option<String>,
+    pub default_market_pair: Option<ObjectId>,
+}
+
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn insert_user(&self, user: User) -> Result<User, AppError>;
+    async fn find_user(&self, id: ObjectId) -> Result<Option<User>, AppError>;
+    async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, AppError>;
+    async fn update_profile(&self, id: ObjectId, update: ProfileUpdate) -> Result<Option<User>, AppError>;
+    async fn set_role(&self, id: ObjectId, role: Role) -> Result<Option<User>, AppError>;
+
+    async fn set_password_reset(&self, id: ObjectId, token_hash: &str, expires: NaiveDateTime) -> Result<(), AppError>;
+    async fn find_user_by_reset_token(&self, token_hash: &str) -> Result<Option<User>, AppError>;
+    // Cambia la contraseña solo si el token sigue vigente; lo invalida y cierra todas las sesiones
+    async fn consume_password_reset(&self, id: ObjectId, token_hash: &str, password_hash: &str) -> Result<bool, AppError>;
+
+    // Añade la sesión y descarta las caducadas a fecha `now`
+    async fn add_session(&self, user_id: ObjectId, session: Session, now: f64) -> Result<(), AppError>;
+    async fn find_user_by_session(&self, session_id: &str) -> Result<Option<User>, AppError>;
+    // Sustituye el hash del refresh token solo si sigue siendo `current_hash`
+    async fn rotate_session(&self, user_id: ObjectId, session_id: &str, current_hash: &str, new_hash: &str, user_agent: &str, now: f64) -> Result<bool, AppError>;
+    // Actualiza el último uso si la sesión existe y no ha caducado
+    async fn touch_session(&self, user_id: ObjectId, session_id: &str, now: f64) -> Result<bool, AppError>;
+    async fn remove_session(&self, user_id: ObjectId, session_id: &str) -> Result<(), AppError>;
+    async fn clear_sessions(&self, user_id: ObjectId) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn insert_api_key(&self, api_key: ApiKey) -> Result<ApiKey, AppError>;
+    async fn list_owned_api_keys(&self, owner: ObjectId, page: &PageQuery) -> Result<Paginated<ApiKey>, AppError>;
+    async fn delete_owned_api_key(&self, id: ObjectId, owner: ObjectId) -> Result<bool, AppError>;
+    // Devuelve la clave si el hash coincide y no ha caducado a fecha `now`, registrando su último uso
+    async fn use_api_key(&self, id: ObjectId, secret_hash: &str, now: f64) -> Result<Option<ApiKey>, AppError>;
+}
+
+// Todo el almacenamiento; los controladores lo reciben como web::Data<dyn Storage>
+pub trait Storage:
+    ExchangeRepository + AssetRepository + MarketPairRepository + StrategyRepository + AssetEquivalenceRepository + UserRepository + ApiKeyRepository
+    + IntegrityRepository + MarketDataRepository + PaperTradingRepository + BacktestRepository + AlertRepository
+{
+}
+
+impl<T> Storage for T where
+    T: ExchangeRepository + AssetRepository + MarketPairRepository + StrategyRepository + AssetEquivalenceRepository + UserRepository + ApiKeyRepository
+    + IntegrityRepository + MarketDataRepository + PaperTradingRepository + BacktestRepository + AlertRepository
+{
+}
@@ -0,0 +1,169 @@
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use mongodb::bson::oid::ObjectId;
+use mongodb::error::{ErrorKind, WriteFailure};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use tracing::{error, warn};
+use utoipa::ToSchema;
+use crate::modules::auth::auth_response::ApiResponse;
+
+// Error de validación de un campo concreto del cuerpo de la petición
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        FieldError { field: field.to_string(), message: message.to_string() }
+    }
+}
+
+// Error común de servicios y controladores. Cada variante tiene un código HTTP y un
+// `code` estable que el cliente puede comprobar sin interpretar el mensaje.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    NotFound(String),
+    Validation(String),
+    // Como Validation, pero la respuesta lista los errores de cada campo en `errors`
+    InvalidFields(String, Vec<FieldError>),
+    Conflict(String),
+    Unauthorized(String),
+    Forbidden(String),
+    Database(String),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn invalid_fields(errors: Vec<FieldError>) -> Self {
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        AppError::InvalidFields(format!("Invalid fields: {}", fields.join(", ")), errors)
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Validation(_) | AppError::InvalidFields(..) => "validation_error",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Database(_) => "database_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(msg)
+            | AppError::Validation(msg)
+            | AppError::InvalidFields(msg, _)
+            | AppError::Conflict(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::Database(msg)
+            | AppError::Internal(msg) => msg,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Validation(_) | AppError::InvalidFields(..) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Los detalles de los errores internos se registran pero no se envían al cliente
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let message = if status.is_server_error() {
+            error!("{}: {}", self.code(), self.message());
+            "Internal server error"
+        } else {
+            warn!("{}: {}", self.code(), self.message());
+            self.message()
+        };
+        let mut body = ApiResponse::<()>::failure(self.code(), message);
+        if let AppError::InvalidFields(_, errors) = self {
+            body.errors = Some(errors.clone());
+        }
+        HttpResponse::build(status).json(body)
+    }
+}
+
+impl From<mongodb::error::Error> for AppError {
+    fn from(e: mongodb::error::Error) -> Self {
+        match e.kind.as_ref() {
+            ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == 11000 => {
+                AppError::Conflict("A record with the same unique fields already exists".to_string())
+            },
+            _ => AppError::Database(e.to_string()),
+        }
+    }
+}
+
+impl From<mongodb::bson::de::Error> for AppError {
+    fn from(e: mongodb::bson::de::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for AppError {
+    fn from(e: mongodb::bson::ser::Error) -> Self {
+        AppError::Internal(e.to_string())
+    }
+}
+
+// Registra el error de MongoDB con su contexto antes de convertirlo
+pub fn db_error(context: &'static str) -> impl Fn(mongodb::error::Error) -> AppError {
+    move |e| {
+        error!("{}: {}", context, e);
+        AppError::from(e)
+    }
+}
+
+pub fn parse_object_id(id: &str, what: &str) -> Result<ObjectId, AppError> {
+    ObjectId::parse_str(id).map_err(|_| AppError::Validation(format!("Invalid {} ID", what)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+
+    #[actix_web::test]
+    async fn maps_to_status_and_code() {
+        let response = AppError::NotFound("Asset not found".to_string()).error_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "not_found");
+        assert_eq!(json["message"], "Asset not found");
+
+        let response = AppError::Database("connection reset".to_string()).error_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        assert!(!String::from_utf8_lossy(&body).contains("connection reset"));
+
+        let response = AppError::invalid_fields(vec![FieldError::new("_quote_asset", "Must differ from _base_asset")]).error_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "validation_error");
+        assert_eq!(json["errors"][0]["field"], "_quote_asset");
+
+        assert_eq!(parse_object_id("nope", "asset"), Err(AppError::Validation("Invalid asset ID".to_string())));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod app_error;
+pub mod pagination;
+#[cfg(test)]
+pub mod test_fixtures;
@@ -0,0 +1,103 @@
+use futures::stream::TryStreamExt;
+use mongodb::bson::Document;
+use mongodb::Collection;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_PER_PAGE: u64 = 20;
+const MAX_PER_PAGE: u64 = 100;
+// Tope de página para que skip, page + 1 y el $skip de Mongo (i64) no desborden
+const MAX_PAGE: u64 = i64::MAX as u64 / MAX_PER_PAGE;
+
+// Parámetros comunes de los listados: ?page=&per_page= o ?cursor= (el next_cursor de la página anterior)
+#[derive(Deserialize, Debug, Clone, Default, IntoParams)]
+pub struct PageQuery {
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+    pub cursor: Option<String>,
+}
+
+impl PageQuery {
+    pub fn page(&self) -> u64 {
+        self.cursor.as_deref()
+            .and_then(|cursor| cursor.parse().ok())
+            .or(self.page)
+            .unwrap_or(1)
+            .clamp(1, MAX_PAGE)
+    }
+
+    pub fn per_page(&self) -> u64 {
+        self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)
+    }
+
+    pub fn skip(&self) -> u64 {
+        (self.page() - 1) * self.per_page()
+    }
+}
+
+// Envoltorio de los listados dentro de ApiResponse::data. Los resultados acotados que se calculan
+// en cada petición (/arbitrage-strategies/suggested, /arbitrage-strategies/cycles, /sessions y
+// /paper_trading/balances) no se paginan y devuelven el array directamente.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, total: u64, query: &PageQuery) -> Self {
+        let page = query.page();
+        let per_page = query.per_page();
+        Paginated {
+            items,
+            total,
+            page,
+            per_page,
+            next_cursor: (page * per_page < total).then(|| (page + 1).to_string()),
+        }
+    }
+}
+
+// Página de un find simple con su total
+pub async fn find_page<T>(collection: &Collection<T>, filter: Document, sort: Document, query: &PageQuery) -> Result<Paginated<T>, mongodb::error::Error>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    let total = collection.count_documents(filter.clone()).await?;
+    let items = collection.find(filter)
+        .sort(sort)
+        .skip(query.skip())
+        .limit(query.per_page() as i64)
+        .await?
+        .try_collect()
+        .await?;
+    Ok(Paginated::new(items, total, query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_page_bounds_and_next_cursor() {
+        let query = PageQuery { page: Some(2), per_page: Some(10), cursor: None };
+        assert_eq!(query.skip(), 10);
+        assert_eq!(Paginated::new(vec![0; 10], 25, &query).next_cursor.as_deref(), Some("3"));
+        assert_eq!(Paginated::new(vec![0; 5], 25, &PageQuery { cursor: Some("3".to_string()), ..query.clone() }).next_cursor, None);
+
+        let query = PageQuery { page: Some(0), per_page: Some(1000), cursor: None };
+        assert_eq!((query.page(), query.per_page(), query.skip()), (1, MAX_PER_PAGE, 0));
+    }
+
+    #[test]
+    fn huge_pages_do_not_overflow() {
+        let query = PageQuery { page: None, per_page: Some(MAX_PER_PAGE), cursor: Some(u64::MAX.to_string()) };
+        assert_eq!(query.page(), MAX_PAGE);
+        assert!(query.skip() <= i64::MAX as u64);
+        assert_eq!(Paginated::new(Vec::<u8>::new(), 10, &query).next_cursor, None);
+    }
+}
@@ -0,0 +1,96 @@
+use mongodb::bson::oid::ObjectId;
+use std::path::PathBuf;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::TradeSide;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::PopulatedLeg;
+use crate::modules::asset::asset_schema::Asset;
+use crate::modules::exchange::exchange_schema::Exchange;
+use crate::modules::market_data::market_data_schema::MarketQuote;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+
+pub fn exchange(short_name: &str) -> Exchange {
+    exchange_with_fee(short_name, 0.0)
+}
+
+pub fn exchange_with_fee(short_name: &str, taker_fee: f64) -> Exchange {
+    Exchange {
+        id: Some(ObjectId::new()),
+        name: short_name.to_string(),
+        short_name: short_name.to_string(),
+        url: String::new(),
+        maker_fee: taker_fee,
+        taker_fee,
+        created_at: 0.0,
+        updated_at: 0.0,
+    }
+}
+
+pub fn asset(exchange: &Exchange, short_name: &str) -> Asset {
+    Asset {
+        id: Some(ObjectId::new()),
+        _exchange: exchange.id.unwrap(),
+        name: short_name.to_string(),
+        short_name: short_name.to_string(),
+        withdrawal_fee: 0.0,
+        created_at: 0.0,
+        updated_at: 0.0,
+        status: true,
+    }
+}
+
+pub fn pair(exchange: &Exchange, base: &str, quote: &str) -> PopulatedMarketPair {
+    PopulatedMarketPair {
+        id: Some(ObjectId::new()),
+        exchange: exchange.clone(),
+        base_asset: asset(exchange, base),
+        quote_asset: asset(exchange, quote),
+        maker_fee: None,
+        taker_fee: None,
+        created_at: 0.0,
+        updated_at: 0.0,
+        status: true,
+    }
+}
+
+pub fn leg(market_pair: PopulatedMarketPair, side: TradeSide) -> PopulatedLeg {
+    PopulatedLeg { market_pair, side }
+}
+
+// Cotización sin spread: bid y ask iguales al precio dado
+pub fn quote(pair: &PopulatedMarketPair, price: f64) -> (ObjectId, MarketQuote) {
+    let id = pair.id.unwrap();
+    (id, MarketQuote {
+        id: None,
+        _market_pair: id,
+        _exchange: pair.exchange.id.unwrap(),
+        bid: price,
+        ask: price,
+        bid_size: 0.0,
+        ask_size: 0.0,
+        timestamp: 0.0,
+    })
+}
+
+pub fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
+
+// Archivo de MARKET_DATA_DIR con BTC/USDT listado (ticker y libro) y ETH/USDT sin listar
+const MARKET_DATA_FIXTURE: &str = r#"{
+    "markets": [{ "symbol": "BTC/USDT", "base": "BTC", "quote": "USDT" }],
+    "tickers": [{ "symbol": "BTC/USDT", "bid": 29990.0, "ask": 30010.0, "timestamp": 1700000000.0 }],
+    "order_books": [{
+        "symbol": "BTC/USDT",
+        "bids": [{ "price": 29990.0, "amount": 1.5 }],
+        "asks": [{ "price": 30010.0, "amount": 2.0 }],
+        "timestamp": 1700000000.0
+    }]
+}"#;
+
+// Escribe el archivo de mercado de `short_name` en un directorio temporal nuevo y lo devuelve
+pub fn market_data_dir(short_name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("arbi-market-{}", ObjectId::new().to_hex()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("{}.json", short_name.to_lowercase())), MARKET_DATA_FIXTURE).unwrap();
+    dir
+}
@@ -3,20 +3,27 @@ mod modules;
 mod helpers;
 mod middleware;
 mod router; // Importa el archivo router.rs
+mod openapi;
 
 use actix_web::{web, App, HttpServer};
 use dotenv::dotenv;
 use crate::db::mongodb::{get_mongodb_client, MongoDbContext};
-use tracing::{error, info};
-use tracing_subscriber;
+use crate::db::repositories::Storage;
+use std::sync::Arc;
 use crate::middleware::auth_middleware::Auth;
+use crate::modules::account::account_service::AccountService;
+use crate::modules::asset_equivalence::asset_equivalence_service::AssetEquivalenceService;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_migration_service::ArbitrageStrategyMigrationService;
+use crate::modules::opportunity_stream::opportunity_hub::OpportunityHub;
+use crate::modules::alert::alert_service::AlertService;
+use tracing::{error, info};
 
 // Erro not found
-use crate::modules::auth::auth_response::ApiResponse;
-use actix_web::{HttpResponse, Error};
+use crate::helpers::app_error::AppError;
+use actix_web::HttpResponse;
 
-async fn not_found() -> Result<HttpResponse, Error> {
-    Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Ruta no encontrada")))
+async fn not_found() -> Result<HttpResponse, AppError> {
+    Err(AppError::NotFound("Ruta no encontrada".to_string()))
 }
 
 #[actix_web::main]
@@ -36,18 +43,50 @@ async fn main() -> std::io::Result<()> {
         },
         Err(e) => {
             error!("Failed to connect to MongoDb: {}", e);
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to connect to MongoDB: {}", e)));
+            return Err(std::io::Error::other(format!("Failed to connect to MongoDB: {}", e)));
         }
     };
 
     // Crear el contexto de MongoDbContext
     let mongo_context = MongoDbContext::new(client);
 
+    if let Err(e) = mongo_context.ensure_indexes().await {
+        error!("Failed to create indexes: {}", e);
+    }
+
+    // Sembrar los grupos de equivalencia de stablecoins si la colección está vacía
+    if let Err(e) = AssetEquivalenceService::ensure_defaults(&mongo_context).await {
+        error!("Failed to seed asset equivalences: {}", e);
+    }
+
+    // Convertir a patas las estrategias guardadas con el formato de detalles anterior
+    if let Err(e) = ArbitrageStrategyMigrationService::migrate_legacy_strategies(&mongo_context).await {
+        error!("Failed to migrate arbitrage strategies: {}", e);
+    }
+
+    // Promover a admin un usuario existente; el registro nunca da ese rol
+    if let Some(email) = std::env::var("PROMOTE_ADMIN_EMAIL").ok().map(|email| email.trim().to_string()).filter(|email| !email.is_empty()) {
+        match AccountService::promote_admin(&email, &mongo_context).await {
+            Ok(_) => info!("User {} promoted to admin", email),
+            Err(e) => error!("Failed to promote {} to admin: {}", email, e),
+        }
+    }
+
+    // Canal compartido por todos los workers para difundir oportunidades por WebSocket
+    let opportunity_hub = web::Data::new(OpportunityHub::default());
+
+    // Servicios y controladores acceden a los datos solo a través de los repositorios
+    let storage: web::Data<dyn Storage> = web::Data::from(Arc::new(mongo_context) as Arc<dyn Storage>);
+
+    // Motor de alertas: evalúa las reglas con cada oportunidad publicada
+    actix_web::rt::spawn(AlertService::run(opportunity_hub.subscribe(), storage.clone()));
+
     // Iniciar el servidor HTTP de Actix Web
     HttpServer::new(move || {
         App::new()
-            //.wrap(Auth) // Añadir el middleware de autenticación
-            .app_data(web::Data::new(mongo_context.clone())) // Pasar el contexto de MongoDbContext al contexto de Actix Web
+            .wrap(Auth) // Rutas protegidas salvo las de PUBLIC_ROUTES
+            .app_data(storage.clone())
+            .app_data(opportunity_hub.clone())
             .configure(router::configure) // Configurar las rutas usando router.rs
             .default_service(web::route().to(not_found))
     })
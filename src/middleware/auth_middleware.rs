@@ -1,20 +1,55 @@
-use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error, HttpMessage, HttpResponse};
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, web, Error, HttpMessage, ResponseError};
 use actix_web::dev::{Transform, Service};
-use actix_web::http::StatusCode;
 use actix_web::body::EitherBody;
 use futures::future::{ok, Ready as FuturesReady};
 use std::task::{Context, Poll};
 use std::rc::Rc;
-use std::cell::RefCell;
 use std::pin::Pin;
 use std::future::Future;
 use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Serialize, Deserialize};
+use crate::helpers::app_error::AppError;
+use crate::modules::auth::session_service::SessionService;
+use crate::modules::api_key::api_key_service::{required_scope, ApiKeyService};
+use crate::db::repositories::Storage;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    exp: usize,
+// Cabecera con la que se envía una API key personal en lugar del JWT
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+// Rutas sin autenticación si no se define PUBLIC_ROUTES. El WebSocket valida su propio token.
+const DEFAULT_PUBLIC_ROUTES: &str = "/register,/login,/refresh,/password/*,/ws/*,/openapi.json,/docs";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    // Sesión a la que pertenece el token; solo las API keys autentican sin sesión
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+}
+
+// Valida un JWT firmado con SECRET_KEY. Lo comparten el middleware HTTP y el WebSocket.
+pub fn decode_claims(token: &str) -> Option<Claims> {
+    let secret_key = std::env::var("SECRET_KEY").expect("SECRET_KEY must be set");
+    decode::<Claims>(token, &DecodingKey::from_secret(secret_key.as_ref()), &Validation::default())
+        .ok()
+        .map(|decoded| decoded.claims)
+}
+
+// Lista separada por comas; una entrada terminada en * es un prefijo
+fn is_public_route(path: &str, public_routes: &str) -> bool {
+    public_routes.split(',')
+        .map(str::trim)
+        .filter(|route| !route.is_empty())
+        .any(|route| match route.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == route,
+        })
+}
+
+pub fn reject<B>(req: ServiceRequest, error: AppError) -> ServiceResponse<EitherBody<B>> {
+    let (req, _) = req.into_parts();
+    ServiceResponse::new(req, error.error_response().map_into_right_body())
 }
 
 pub struct Auth;
@@ -32,12 +67,12 @@ where
     type Future = FuturesReady<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ok(AuthMiddleware { service: Rc::new(RefCell::new(service)) })
+        ok(AuthMiddleware { service: Rc::new(service) })
     }
 }
 
 pub struct AuthMiddleware<S> {
-    service: Rc<RefCell<S>>,
+    service: Rc<S>,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
@@ -51,38 +86,70 @@ where
     type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
 
     fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.service.borrow_mut().poll_ready(ctx)
+        self.service.poll_ready(ctx)
     }
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let svc = self.service.clone();
 
         Box::pin(async move {
-            // Permitir acceso sin autenticación a /register y /login
-            let path = req.path();
-            if path == "/register" || path == "/login" {
-                return Ok(svc.borrow_mut().call(req).await?.map_into_left_body());
+            // Permitir acceso sin autenticación a las rutas públicas configuradas
+            let public_routes = std::env::var("PUBLIC_ROUTES").unwrap_or_else(|_| DEFAULT_PUBLIC_ROUTES.to_string());
+            if is_public_route(req.path(), &public_routes) {
+                return Ok(svc.call(req).await?.map_into_left_body());
+            }
+
+            let storage = req.app_data::<web::Data<dyn Storage>>().cloned();
+
+            // API key personal: alternativa al JWT limitada a los scopes de la clave
+            if let (Some(key), Some(storage)) = (req.headers().get(API_KEY_HEADER).and_then(|h| h.to_str().ok()), &storage) {
+                let Some(api_key) = ApiKeyService::authenticate(key, storage.get_ref()).await else {
+                    return Ok(reject(req, AppError::Unauthorized("Invalid or expired API key".to_string())));
+                };
+                if !required_scope(req.method(), req.path()).is_some_and(|scope| api_key.scopes.contains(&scope)) {
+                    return Ok(reject(req, AppError::Forbidden("API key scope does not allow this route".to_string())));
+                }
+                req.extensions_mut().insert(Claims {
+                    sub: api_key._user.to_hex(),
+                    exp: api_key.expires_at.unwrap_or(0.0) as usize,
+                    sid: None,
+                });
+                return Ok(svc.call(req).await?.map_into_left_body());
             }
 
             // Verificar autenticación para otras rutas
             if let Some(authen_header) = req.headers().get("Authorization") {
                 if let Ok(authen_str) = authen_header.to_str() {
                     if authen_str.starts_with("Bearer ") {
-                        let token = authen_str.trim_start_matches("Bearer ").to_string();
-                        let secret_key = std::env::var("SECRET_KEY").expect("SECRET_KEY must be set");
-                        if let Ok(decoded) = decode::<Claims>(&token, &DecodingKey::from_secret(secret_key.as_ref()), &Validation::default()) {
-                            req.extensions_mut().insert(decoded.claims);
-                            return Ok(svc.borrow_mut().call(req).await?.map_into_left_body());
+                        let token = authen_str.trim_start_matches("Bearer ");
+                        if let Some(claims) = decode_claims(token) {
+                            // El token debe pertenecer a una sesión no revocada
+                            if let Some(storage) = &storage {
+                                if SessionService::touch(&claims, storage.get_ref()).await {
+                                    req.extensions_mut().insert(claims);
+                                    return Ok(svc.call(req).await?.map_into_left_body());
+                                }
+                            }
                         }
                     }
                 }
             }
 
-            let (req, _) = req.into_parts();
-            Ok(ServiceResponse::new(
-                req,
-                HttpResponse::new(StatusCode::UNAUTHORIZED).map_into_right_body()
-            ))
+            Ok(reject(req, AppError::Unauthorized("Invalid or missing token".to_string())))
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_exact_and_prefix_routes() {
+        let routes = "/register, /login,/ws/*";
+        assert!(is_public_route("/login", routes));
+        assert!(is_public_route("/ws/opportunities", routes));
+        assert!(!is_public_route("/login/other", routes));
+        assert!(!is_public_route("/arbitrage-strategies", routes));
+    }
+}
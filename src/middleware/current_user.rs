@@ -0,0 +1,27 @@
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use futures::future::{ready, Ready};
+use mongodb::bson::oid::ObjectId;
+use crate::middleware::auth_middleware::Claims;
+use crate::helpers::app_error::AppError;
+
+// Usuario autenticado a partir de los Claims que inserta el middleware Auth
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub id: ObjectId,
+    pub session_id: Option<String>, // None si la petición se autenticó con API key
+}
+
+impl FromRequest for CurrentUser {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let user = req.extensions().get::<Claims>().cloned()
+            .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))
+            .and_then(|claims| {
+                let id = ObjectId::parse_str(&claims.sub).map_err(|_| AppError::Unauthorized("Invalid token subject".to_string()))?;
+                Ok(CurrentUser { id, session_id: claims.sid })
+            });
+        ready(user)
+    }
+}
@@ -1 +1,3 @@
-pub mod auth_middleware;
\ No newline at end of file
+pub mod auth_middleware;
+pub mod current_user;
+pub mod permission_middleware;
@@ -0,0 +1,85 @@
+use actix_web::{dev::ServiceRequest, dev::ServiceResponse, web, Error, HttpMessage};
+use actix_web::dev::{Transform, Service};
+use actix_web::body::EitherBody;
+use futures::future::{ok, Ready as FuturesReady};
+use std::task::{Context, Poll};
+use std::rc::Rc;
+use std::pin::Pin;
+use std::future::Future;
+use mongodb::bson::oid::ObjectId;
+use crate::db::repositories::Storage;
+use crate::middleware::auth_middleware::{reject, Claims};
+use crate::modules::account::account_service::AccountService;
+use crate::helpers::app_error::AppError;
+use crate::modules::user::user_schema::Permission;
+use tracing::warn;
+
+// Guard por ruta: #[post("/exchanges", wrap = "RequirePermission(Permission::ManageCatalog)")]
+// El rol se lee de la base de datos para que los cambios de rol apliquen sin volver a iniciar sesión.
+pub struct RequirePermission(pub Permission);
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequirePermissionMiddleware<S>;
+    type InitError = ();
+    type Future = FuturesReady<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequirePermissionMiddleware { service: Rc::new(service), permission: self.0 })
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: Permission,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let svc = self.service.clone();
+        let permission = self.permission;
+
+        Box::pin(async move {
+            let user_id = req.extensions().get::<Claims>()
+                .and_then(|claims| ObjectId::parse_str(&claims.sub).ok());
+            let storage = req.app_data::<web::Data<dyn Storage>>().cloned();
+
+            let error = match (user_id, storage) {
+                (Some(user_id), Some(storage)) => match AccountService::get_user(user_id, storage.get_ref()).await {
+                    Ok(user) if user.role.allows(permission) => {
+                        return Ok(svc.call(req).await?.map_into_left_body());
+                    },
+                    Ok(user) => {
+                        warn!("User {} with role {:?} lacks permission {:?}", user_id, user.role, permission);
+                        AppError::Forbidden("Insufficient permissions".to_string())
+                    },
+                    // Un token de un usuario borrado no autentica; los fallos de la base de datos se propagan
+                    Err(AppError::NotFound(_)) => AppError::Unauthorized("Invalid or missing token".to_string()),
+                    Err(err) => err,
+                },
+                _ => AppError::Unauthorized("Invalid or missing token".to_string()),
+            };
+
+            Ok(reject(req, error))
+        })
+    }
+}
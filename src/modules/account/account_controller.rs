@@ -1,52 +1,76 @@
-use actix_web::{get, put, web, HttpResponse, Responder};
-use crate::modules::account::account_service::{AccountService, UpdateUserRequest};
-use crate::db::mongodb::MongoDbContext;
+use actix_web::{get, put, web, HttpResponse};
+use crate::modules::account::account_service::{AccountService, UpdateRoleRequest, UpdateUserRequest, UserResponse};
+use crate::db::repositories::Storage;
 use crate::modules::auth::auth_response::ApiResponse;
-use mongodb::bson::oid::ObjectId;
-use tracing::{info, error};
+use crate::middleware::current_user::CurrentUser;
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::openapi::ErrorResponse;
+use tracing::info;
 
+#[utoipa::path(
+    tag = "account",
+    params(("id" = String, Path, description = "User ID; must be the current user")),
+    responses(
+        (status = 200, description = "Account of the current user", body = ApiResponse<UserResponse>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Another user's account", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
 #[get("/account/{id}")]
-pub async fn get_user(path: web::Path<String>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let user_id = path.into_inner();
-    let user_id = match ObjectId::parse_str(&user_id) {
-        Ok(id) => id,
-        Err(_) => {
-            error!("Invalid user ID: {}", user_id);
-            return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Invalid user ID"));
-        },
-    };
-
-    match AccountService::get_user(user_id, &db_context).await {
-        Ok(user) => {
-            info!("User retrieved successfully: {}", user_id);
-            HttpResponse::Ok().json(ApiResponse::success("User retrieved successfully", user))
-        },
-        Err(err) => {
-            error!("Failed to retrieve user: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<()>::error(&err))
-        },
+pub async fn get_user(user: CurrentUser, path: web::Path<String>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let user_id = parse_object_id(&path.into_inner(), "user")?;
+    // Cada usuario solo puede consultar y modificar su propia cuenta
+    if user_id != user.id {
+        return Err(AppError::Forbidden("Access denied".to_string()));
     }
+
+    let user = AccountService::get_user(user_id, storage.get_ref()).await?;
+    info!("User retrieved successfully: {}", user_id);
+    Ok(HttpResponse::Ok().json(ApiResponse::success("User retrieved successfully", UserResponse::from(user))))
 }
 
+#[utoipa::path(
+    tag = "account",
+    params(("id" = String, Path, description = "User ID; must be the current user")),
+    request_body = UpdateUserRequest,
+    responses(
+        (status = 200, description = "Account updated", body = ApiResponse<UserResponse>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Another user's account", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
 #[put("/account/{id}")]
-pub async fn update_user(path: web::Path<String>, data: web::Json<UpdateUserRequest>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let user_id = path.into_inner();
-    let user_id = match ObjectId::parse_str(&user_id) {
-        Ok(id) => id,
-        Err(_) => {
-            error!("Invalid user ID: {}", user_id);
-            return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Invalid user ID"));
-        },
-    };
-
-    match AccountService::update_user(user_id, data.into_inner(), &db_context).await {
-        Ok(user) => {
-            info!("User updated successfully: {}", user_id);
-            HttpResponse::Ok().json(ApiResponse::success("User updated successfully", user))
-        },
-        Err(err) => {
-            error!("Failed to update user: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<()>::error(&err))
-        },
+pub async fn update_user(user: CurrentUser, path: web::Path<String>, data: web::Json<UpdateUserRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let user_id = parse_object_id(&path.into_inner(), "user")?;
+    // Cada usuario solo puede consultar y modificar su propia cuenta
+    if user_id != user.id {
+        return Err(AppError::Forbidden("Access denied".to_string()));
     }
+
+    let user = AccountService::update_user(user_id, data.into_inner(), storage.get_ref()).await?;
+    info!("User updated successfully: {}", user_id);
+    Ok(HttpResponse::Ok().json(ApiResponse::success("User updated successfully", UserResponse::from(user))))
+}
+
+#[utoipa::path(
+    tag = "account",
+    params(("id" = String, Path, description = "User ID")),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 200, description = "Role changed", body = ApiResponse<UserResponse>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[put("/admin/users/{id}/role", wrap = "RequirePermission(Permission::ManageUsers)")]
+pub async fn update_user_role(path: web::Path<String>, data: web::Json<UpdateRoleRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let user_id = parse_object_id(&path.into_inner(), "user")?;
+    let user = AccountService::update_role(user_id, data.role, storage.get_ref()).await?;
+    info!("Role of user {} changed to {:?}", user_id, user.role);
+    Ok(HttpResponse::Ok().json(ApiResponse::success("User role updated successfully", UserResponse::from(user))))
 }
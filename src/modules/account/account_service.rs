@@ -1,10 +1,12 @@
-use crate::modules::user::user_schema::User;
-use mongodb::bson::{doc, oid::ObjectId};
-use crate::db::mongodb::MongoDbContext;
-use tracing::{ error};
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::modules::user::user_schema::{Role, User};
+use mongodb::bson::oid::ObjectId;
+use crate::db::repositories::{ProfileUpdate, UserRepository};
+use tracing::error;
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
     pub name: Option<String>,
     pub email: Option<String>,
@@ -13,63 +15,97 @@ pub struct UpdateUserRequest {
     pub _default_market_pair: Option<String>, // Mantén este campo como String para recibirlo desde el frontend
 }
 
-pub struct AccountService;
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UpdateRoleRequest {
+    pub role: Role,
+}
 
-impl AccountService {
-    pub async fn get_user(user_id: ObjectId, db_context: &MongoDbContext) -> Result<User, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<User>("users");
-
-        let user = collection
-            .find_one(doc! { "_id": user_id })
-            .await
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| {
-                error!("User not found: {}", user_id);
-                "User not found".to_string()
-            })?;
-
-        Ok(user)
-    }
+// Datos públicos de la cuenta: nunca incluye el hash de la contraseña, las sesiones ni el token de recuperación
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UserResponse {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub role: Role,
+    pub _default_asset: Option<String>, // Enviar como String en la respuesta JSON
+    pub _default_market_pair: Option<String>, // Enviar como String en la respuesta JSON
+}
 
-    pub async fn update_user(user_id: ObjectId, update_data: UpdateUserRequest, db_context: &MongoDbContext) -> Result<User, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<User>("users");
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        UserResponse {
+            id: user.id.map(|id| id.to_hex()).unwrap_or_default(),
+            name: user.name,
+            email: user.email,
+            role: user.role,
+            _default_asset: user._default_asset.map(|id| id.to_hex()),
+            _default_market_pair: user._default_market_pair.map(|id| id.to_hex()),
+        }
+    }
+}
 
-        let mut update_doc = doc! {};
+pub struct AccountService;
 
+fn not_found() -> AppError {
+    AppError::NotFound("User not found".to_string())
+}
 
-        if let Some(name) = update_data.name {
-            update_doc.insert("name", name);
-        }
+impl AccountService {
+    pub async fn get_user(user_id: ObjectId, repo: &dyn UserRepository) -> Result<User, AppError> {
+        repo.find_user(user_id).await?.ok_or_else(|| {
+            error!("User not found: {}", user_id);
+            not_found()
+        })
+    }
 
-        if let Some(email) = update_data.email {
-            update_doc.insert("email", email);
-        }
+    pub async fn update_user(user_id: ObjectId, update_data: UpdateUserRequest, repo: &dyn UserRepository) -> Result<User, AppError> {
+        let mut update = ProfileUpdate {
+            name: update_data.name,
+            email: update_data.email,
+            ..ProfileUpdate::default()
+        };
 
         // Solo actualiza la contraseña si se proporciona y no está vacía
-        if let Some(password) = update_data.password {
-            if !password.is_empty() {
-                let hashed_password = bcrypt::hash(password, bcrypt::DEFAULT_COST).map_err(|e| e.to_string())?;
-                update_doc.insert("password", hashed_password);
-            }
+        if let Some(password) = update_data.password.filter(|password| !password.is_empty()) {
+            update.password_hash = Some(bcrypt::hash(password, bcrypt::DEFAULT_COST).map_err(|e| AppError::Internal(e.to_string()))?);
         }
 
-        println!("{:?}", update_data._default_market_pair);
-        if let Some(default_asset) = update_data._default_market_pair {
-            // Convertir el default_asset de String a ObjectId
-            let default_asset_id = ObjectId::parse_str(&default_asset).map_err(|e| e.to_string())?;
-            update_doc.insert("_default_market_pair", default_asset_id);
+        if let Some(default_market_pair) = update_data._default_market_pair {
+            // Convertir el default_market_pair de String a ObjectId
+            update.default_market_pair = Some(parse_object_id(&default_market_pair, "market pair")?);
         }
 
-        // Solo realiza la actualización si hay campos para actualizar
-        if !update_doc.is_empty() {
-            collection
-                .update_one(doc! { "_id": user_id }, doc! { "$set": update_doc })
-                .await
-                .map_err(|e| e.to_string())?;
-        }
+        repo.update_profile(user_id, update).await?.ok_or_else(not_found)
+    }
+
+    pub async fn update_role(user_id: ObjectId, role: Role, repo: &dyn UserRepository) -> Result<User, AppError> {
+        repo.set_role(user_id, role).await?.ok_or_else(not_found)
+    }
+
+    // Promoción fuera de banda del primer admin: la hace quien controla el despliegue, no el registro
+    pub async fn promote_admin(email: &str, repo: &dyn UserRepository) -> Result<User, AppError> {
+        let user = repo.find_user_by_email(email).await?.ok_or_else(not_found)?;
+        let user_id = user.id.ok_or_else(|| AppError::Internal("User without id".to_string()))?;
+        Self::update_role(user_id, Role::Admin, repo).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::InMemoryStorage;
+    use crate::modules::auth::auth_service::AuthService;
+
+    #[actix_web::test]
+    async fn registration_never_grants_admin() {
+        std::env::set_var("SECRET_KEY", "test-secret");
+        let storage = InMemoryStorage::default();
+        let registered = AuthService::register("Ada", "ada@example.com", "correct horse", "test", &storage).await.unwrap();
+        let user_id = parse_object_id(&registered.id, "user").unwrap();
+        assert_eq!(AccountService::get_user(user_id, &storage).await.unwrap().role, Role::Viewer);
 
-        Self::get_user(user_id, db_context).await
+        let promoted = AccountService::promote_admin("ada@example.com", &storage).await.unwrap();
+        assert_eq!(promoted.role, Role::Admin);
+        assert!(matches!(AccountService::promote_admin("nobody@example.com", &storage).await, Err(AppError::NotFound(_))));
     }
 }
@@ -6,4 +6,5 @@ use actix_web::web;
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(account_controller::get_user);
     cfg.service(account_controller::update_user);
+    cfg.service(account_controller::update_user_role);
 }
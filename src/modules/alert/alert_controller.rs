@@ -0,0 +1,113 @@
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use crate::modules::alert::alert_service::AlertService;
+use crate::modules::alert::alert_schema::{AlertDelivery, AlertRule};
+use crate::db::repositories::Storage;
+use serde::{Deserialize};
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::openapi::ErrorResponse;
+use crate::middleware::current_user::CurrentUser;
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
+
+#[derive(Deserialize)]
+struct ObjectIdPath {
+    id: String,
+}
+
+#[utoipa::path(
+    tag = "alert_rules",
+    request_body = AlertRule,
+    responses(
+        (status = 200, description = "Alert rule created for the current user", body = ApiResponse<AlertRule>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[post("/alert_rules", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn create_alert_rule(user: CurrentUser, rule: web::Json<AlertRule>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let rule = AlertService::create_alert_rule(rule.into_inner(), user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Alert rule created successfully", rule)))
+}
+
+#[utoipa::path(
+    tag = "alert_rules",
+    params(("id" = String, Path, description = "Alert rule ID")),
+    responses(
+        (status = 200, description = "Alert rule", body = ApiResponse<AlertRule>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/alert_rules/{id}")]
+pub async fn get_alert_rule(user: CurrentUser, path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "alert rule")?;
+    let rule = AlertService::get_alert_rule(id, user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Alert rule retrieved successfully", rule)))
+}
+
+#[utoipa::path(
+    tag = "alert_rules",
+    params(("id" = String, Path, description = "Alert rule ID")),
+    request_body = AlertRule,
+    responses(
+        (status = 200, description = "Alert rule updated", body = ApiResponse<AlertRule>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[put("/alert_rules/{id}", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn update_alert_rule(user: CurrentUser, path: web::Path<ObjectIdPath>, rule: web::Json<AlertRule>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "alert rule")?;
+    let rule = AlertService::update_alert_rule(id, user.id, rule.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Alert rule updated successfully", rule)))
+}
+
+#[utoipa::path(
+    tag = "alert_rules",
+    params(("id" = String, Path, description = "Alert rule ID")),
+    responses(
+        (status = 200, description = "Alert rule deleted"),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/alert_rules/{id}", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn delete_alert_rule(user: CurrentUser, path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "alert rule")?;
+    AlertService::delete_alert_rule(id, user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Alert rule deleted successfully", ())))
+}
+
+#[utoipa::path(
+    tag = "alert_rules",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Page of alert rules of the current user", body = ApiResponse<Paginated<AlertRule>>),
+    ),
+)]
+#[get("/alert_rules")]
+pub async fn get_alert_rules(user: CurrentUser, page: web::Query<PageQuery>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let rules = AlertService::get_alert_rules(user.id, &page, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Alert rules retrieved successfully", rules)))
+}
+
+#[utoipa::path(
+    tag = "alert_rules",
+    params(("id" = String, Path, description = "Alert rule ID"), PageQuery),
+    responses(
+        (status = 200, description = "Page of webhook deliveries of the rule", body = ApiResponse<Paginated<AlertDelivery>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/alert_rules/{id}/deliveries")]
+pub async fn get_alert_deliveries(user: CurrentUser, path: web::Path<ObjectIdPath>, page: web::Query<PageQuery>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "alert rule")?;
+    let deliveries = AlertService::get_deliveries(id, user.id, &page, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Alert deliveries retrieved successfully", deliveries)))
+}
@@ -0,0 +1,78 @@
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use mongodb::bson::oid::ObjectId;
+
+// Regla de alerta sobre una estrategia: dispara cuando el beneficio neto supera min_profit
+// durante al menos min_duration segundos (colección "alert_rules")
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AlertRule {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    // Se toma siempre del usuario autenticado
+    #[serde(default)]
+    #[schema(value_type = ObjectIdSchema)]
+    pub _user: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _arbitrage_strategy: ObjectId,
+    pub min_profit: f64,
+    #[serde(default)]
+    pub min_duration: f64,
+    pub webhook_url: String,
+    // Clave para firmar el payload con HMAC-SHA256; se genera si llega vacía
+    #[serde(default)]
+    pub secret: String,
+    pub status: bool,
+    // Desde cuándo el beneficio está por encima del umbral y si ya se disparó en este episodio
+    #[serde(default)]
+    pub above_since: Option<f64>,
+    #[serde(default)]
+    pub fired: bool,
+    #[serde(default)]
+    pub created_at: f64,
+    #[serde(default)]
+    pub updated_at: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AlertPayload {
+    #[schema(value_type = ObjectIdSchema)]
+    pub alert_rule: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub arbitrage_strategy: ObjectId,
+    pub profit_percentage: f64,
+    pub gross_profit_percentage: f64,
+    pub min_profit: f64,
+    pub above_since: f64,
+    pub fired_at: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum DeliveryStatus {
+    Delivered,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DeliveryAttempt {
+    pub attempt: u32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub timestamp: f64,
+}
+
+// Registro de cada envío de webhook con todos sus intentos (colección "alert_deliveries")
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AlertDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _alert_rule: ObjectId,
+    pub webhook_url: String,
+    pub payload: AlertPayload,
+    pub status: DeliveryStatus,
+    pub attempts: Vec<DeliveryAttempt>,
+    pub created_at: f64,
+}
@@ -0,0 +1,219 @@
+use actix_web::web;
+use crate::db::repositories::{AlertRepository, Storage};
+use crate::helpers::app_error::AppError;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use mongodb::bson::oid::ObjectId;
+use crate::modules::alert::alert_schema::{AlertDelivery, AlertPayload, AlertRule};
+use crate::modules::alert::webhook_client::{RetryPolicy, WebhookClient};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::opportunity_stream::opportunity_stream_schema::OpportunityUpdate;
+use chrono::Utc;
+use rand::Rng;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, info, warn};
+
+// Cambio de estado de una regla tras observar un nuevo beneficio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleTransition {
+    pub above_since: Option<f64>,
+    pub fired: bool,
+    // true solo en la observación que dispara la alerta
+    pub fire: bool,
+}
+
+// Una regla dispara una vez por episodio: cuando el beneficio lleva min_duration segundos por
+// encima del umbral. Al caer por debajo se rearma.
+pub fn observe(rule: &AlertRule, profit_percentage: f64, timestamp: f64) -> RuleTransition {
+    if profit_percentage <= rule.min_profit {
+        return RuleTransition { above_since: None, fired: false, fire: false };
+    }
+
+    let above_since = rule.above_since.unwrap_or(timestamp);
+    let fire = !rule.fired && timestamp - above_since >= rule.min_duration;
+    RuleTransition { above_since: Some(above_since), fired: rule.fired || fire, fire }
+}
+
+fn generate_secret() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub struct AlertService;
+
+impl AlertService {
+    fn validate(rule: &AlertRule) -> Result<(), AppError> {
+        if !rule.min_profit.is_finite() {
+            return Err(AppError::Validation("min_profit must be a number".to_string()));
+        }
+        if !rule.min_duration.is_finite() || rule.min_duration < 0.0 {
+            return Err(AppError::Validation("min_duration cannot be negative".to_string()));
+        }
+        if !(rule.webhook_url.starts_with("http://") || rule.webhook_url.starts_with("https://")) {
+            return Err(AppError::Validation("webhook_url must be an http(s) URL".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn create_alert_rule(rule: AlertRule, owner: ObjectId, storage: &dyn Storage) -> Result<AlertRule, AppError> {
+        Self::validate(&rule)?;
+        ArbitrageStrategyService::get_owned_arbitrage_strategy(rule._arbitrage_strategy, owner, storage).await?;
+
+        let now = Utc::now().timestamp() as f64;
+        let secret = if rule.secret.trim().is_empty() { generate_secret() } else { rule.secret.clone() };
+        let new_rule = AlertRule {
+            id: None,
+            _user: owner,
+            secret,
+            above_since: None,
+            fired: false,
+            created_at: now,
+            updated_at: now,
+            ..rule
+        };
+
+        storage.insert_alert_rule(new_rule).await
+    }
+
+    pub async fn get_alert_rule(id: ObjectId, owner: ObjectId, repo: &dyn AlertRepository) -> Result<AlertRule, AppError> {
+        repo.find_owned_alert_rule(id, owner).await?
+            .ok_or_else(|| AppError::NotFound("Alert rule not found".to_string()))
+    }
+
+    pub async fn update_alert_rule(id: ObjectId, owner: ObjectId, updated: AlertRule, repo: &dyn AlertRepository) -> Result<AlertRule, AppError> {
+        Self::validate(&updated)?;
+        let current = Self::get_alert_rule(id, owner, repo).await?;
+
+        // Cambiar la regla la rearma
+        let secret = if updated.secret.trim().is_empty() { current.secret } else { updated.secret };
+        let updated = AlertRule {
+            secret,
+            updated_at: Utc::now().timestamp() as f64,
+            ..updated
+        };
+
+        repo.update_owned_alert_rule(id, owner, updated).await?
+            .ok_or_else(|| AppError::NotFound("Alert rule not found".to_string()))
+    }
+
+    pub async fn delete_alert_rule(id: ObjectId, owner: ObjectId, repo: &dyn AlertRepository) -> Result<(), AppError> {
+        if !repo.delete_owned_alert_rule(id, owner).await? {
+            return Err(AppError::NotFound("Alert rule not found".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn get_alert_rules(owner: ObjectId, page: &PageQuery, repo: &dyn AlertRepository) -> Result<Paginated<AlertRule>, AppError> {
+        repo.list_owned_alert_rules(owner, page).await
+    }
+
+    pub async fn get_deliveries(rule_id: ObjectId, owner: ObjectId, page: &PageQuery, repo: &dyn AlertRepository) -> Result<Paginated<AlertDelivery>, AppError> {
+        Self::get_alert_rule(rule_id, owner, repo).await?;
+        repo.list_alert_deliveries(rule_id, page).await
+    }
+
+    // Escucha las oportunidades publicadas tras cada ingesta y evalúa las reglas de cada estrategia
+    pub async fn run(mut updates: broadcast::Receiver<OpportunityUpdate>, storage: web::Data<dyn Storage>) {
+        let client = Arc::new(WebhookClient::new(RetryPolicy::from_env()));
+        info!("Alert engine started");
+
+        loop {
+            match updates.recv().await {
+                Ok(update) => {
+                    if let Err(e) = Self::handle_update(&update, &client, &storage).await {
+                        error!("Failed to evaluate alert rules: {}", e);
+                    }
+                },
+                Err(RecvError::Lagged(skipped)) => warn!("Alert engine skipped {} opportunity updates", skipped),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+
+    async fn handle_update(update: &OpportunityUpdate, client: &Arc<WebhookClient>, storage: &web::Data<dyn Storage>) -> Result<(), AppError> {
+        for rule in storage.find_active_alert_rules(update.strategy_id).await? {
+            let Some(rule_id) = rule.id else { continue };
+            let transition = observe(&rule, update.profit_percentage, update.timestamp);
+            if transition.above_since != rule.above_since || transition.fired != rule.fired {
+                storage.set_alert_rule_state(rule_id, transition.above_since, transition.fired).await?;
+            }
+
+            if transition.fire {
+                let payload = AlertPayload {
+                    alert_rule: rule_id,
+                    arbitrage_strategy: update.strategy_id,
+                    profit_percentage: update.profit_percentage,
+                    gross_profit_percentage: update.gross_profit_percentage,
+                    min_profit: rule.min_profit,
+                    above_since: transition.above_since.unwrap_or(update.timestamp),
+                    fired_at: update.timestamp,
+                };
+                let client = client.clone();
+                let storage = storage.clone();
+                actix_web::rt::spawn(async move {
+                    if let Err(e) = Self::deliver(rule, payload, &client, storage.get_ref()).await {
+                        error!("Failed to record alert delivery: {}", e);
+                    }
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn deliver(rule: AlertRule, payload: AlertPayload, client: &WebhookClient, repo: &dyn AlertRepository) -> Result<(), AppError> {
+        let body = serde_json::to_string(&payload).map_err(|e| AppError::Internal(e.to_string()))?;
+        let (status, attempts) = client.deliver(&rule.webhook_url, &rule.secret, &body).await;
+        info!("Alert {} delivery finished as {:?} after {} attempts", payload.alert_rule, status, attempts.len());
+
+        repo.insert_alert_delivery(AlertDelivery {
+            id: None,
+            _alert_rule: payload.alert_rule,
+            webhook_url: rule.webhook_url,
+            payload,
+            status,
+            attempts,
+            created_at: Utc::now().timestamp() as f64,
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(min_profit: f64, min_duration: f64) -> AlertRule {
+        AlertRule {
+            id: Some(ObjectId::new()),
+            _user: ObjectId::new(),
+            _arbitrage_strategy: ObjectId::new(),
+            min_profit,
+            min_duration,
+            webhook_url: "http://localhost/hook".to_string(),
+            secret: String::new(),
+            status: true,
+            above_since: None,
+            fired: false,
+            created_at: 0.0,
+            updated_at: 0.0,
+        }
+    }
+
+    #[test]
+    fn fires_once_after_profit_holds_for_the_duration() {
+        // "Avisar cuando el beneficio neto supere 0.8% durante más de 5 segundos"
+        let mut rule = rule(0.8, 5.0);
+        let mut fired_at = Vec::new();
+
+        for (timestamp, profit) in [(0.0, 0.9), (3.0, 1.0), (6.0, 0.85), (8.0, 0.9), (9.0, 0.5), (10.0, 0.9), (16.0, 0.9)] {
+            let transition = observe(&rule, profit, timestamp);
+            if transition.fire {
+                fired_at.push(timestamp);
+            }
+            rule.above_since = transition.above_since;
+            rule.fired = transition.fired;
+        }
+
+        assert_eq!(fired_at, vec![6.0, 16.0]);
+    }
+}
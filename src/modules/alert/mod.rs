@@ -0,0 +1,15 @@
+pub mod alert_schema;
+pub mod webhook_client;
+pub mod alert_service;
+pub mod alert_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(alert_controller::create_alert_rule);
+    cfg.service(alert_controller::get_alert_rule);
+    cfg.service(alert_controller::update_alert_rule);
+    cfg.service(alert_controller::delete_alert_rule);
+    cfg.service(alert_controller::get_alert_rules);
+    cfg.service(alert_controller::get_alert_deliveries);
+}
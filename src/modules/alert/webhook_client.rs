@@ -0,0 +1,143 @@
+use crate::modules::alert::alert_schema::{DeliveryAttempt, DeliveryStatus};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+use std::time::Duration;
+use tracing::warn;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNATURE_HEADER: &str = "X-Arbi-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Arbi-Timestamp";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Firma hex de "{timestamp}.{body}". El receptor la recalcula con el secreto de la regla.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    // ALERT_MAX_ATTEMPTS (5) y ALERT_RETRY_BASE_MS (1000); la espera se duplica en cada reintento
+    pub fn from_env() -> Self {
+        let max_attempts = env::var("ALERT_MAX_ATTEMPTS").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+        let base_ms = env::var("ALERT_RETRY_BASE_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(1000);
+        Self { max_attempts, base_delay: Duration::from_millis(base_ms) }
+    }
+
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay * 2u32.saturating_pow(attempt.saturating_sub(1))
+    }
+}
+
+pub struct WebhookClient {
+    client: reqwest::Client,
+    policy: RetryPolicy,
+}
+
+impl WebhookClient {
+    pub fn new(policy: RetryPolicy) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { client, policy }
+    }
+
+    // Reintenta errores de red, 5xx y 429. Cualquier otra respuesta termina el envío.
+    pub async fn deliver(&self, url: &str, secret: &str, body: &str) -> (DeliveryStatus, Vec<DeliveryAttempt>) {
+        let mut attempts = Vec::new();
+
+        for attempt in 1..=self.policy.max_attempts.max(1) {
+            let timestamp = Utc::now().timestamp();
+            let result = self.client.post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, timestamp, body)))
+                .body(body.to_string())
+                .send()
+                .await;
+
+            let (status_code, error) = match result {
+                Ok(response) => (Some(response.status().as_u16()), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            attempts.push(DeliveryAttempt { attempt, status_code, error, timestamp: timestamp as f64 });
+
+            match status_code {
+                Some(code) if (200..300).contains(&code) => return (DeliveryStatus::Delivered, attempts),
+                Some(code) if code != 429 && code < 500 => break,
+                _ => {},
+            }
+
+            if attempt < self.policy.max_attempts {
+                let delay = self.policy.delay(attempt);
+                warn!("Webhook delivery to {} failed (attempt {}), retrying in {:?}", url, attempt, delay);
+                actix_web::rt::time::sleep(delay).await;
+            }
+        }
+
+        (DeliveryStatus::Failed, attempts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const SECRET: &str = "webhook-secret";
+
+    // Receptor local: falla la primera petición y acepta las siguientes si la firma es válida
+    async fn receiver(req: HttpRequest, body: String, calls: web::Data<Arc<AtomicUsize>>) -> HttpResponse {
+        if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            return HttpResponse::ServiceUnavailable().finish();
+        }
+        let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+        let timestamp: i64 = header(TIMESTAMP_HEADER).parse().unwrap_or_default();
+        if header(SIGNATURE_HEADER) == format!("sha256={}", sign(SECRET, timestamp, &body)) {
+            HttpResponse::Ok().finish()
+        } else {
+            HttpResponse::Unauthorized().finish()
+        }
+    }
+
+    #[actix_web::test]
+    async fn retries_until_the_signed_payload_is_accepted() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let data = web::Data::new(calls.clone());
+        let server = HttpServer::new(move || App::new().app_data(data.clone()).route("/hook", web::post().to(receiver)))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let address = server.addrs()[0];
+        let handle = server.run();
+        let server_handle = handle.handle();
+        actix_web::rt::spawn(handle);
+
+        let client = WebhookClient::new(RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(10) });
+        let (status, attempts) = client.deliver(&format!("http://{}/hook", address), SECRET, r#"{"profit_percentage":0.9}"#).await;
+
+        assert_eq!(status, DeliveryStatus::Delivered);
+        assert_eq!(attempts.iter().map(|a| a.status_code).collect::<Vec<_>>(), vec![Some(503), Some(200)]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        server_handle.stop(true).await;
+    }
+
+    #[test]
+    fn backoff_doubles_each_attempt() {
+        let policy = RetryPolicy { max_attempts: 4, base_delay: Duration::from_millis(100) };
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(3), Duration::from_millis(400));
+    }
+}
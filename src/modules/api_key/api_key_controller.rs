@@ -0,0 +1,58 @@
+use actix_web::{get, post, delete, web, HttpResponse};
+use crate::modules::api_key::api_key_service::{ApiKeyService, CreateApiKeyRequest, CreatedApiKey};
+use crate::modules::api_key::api_key_schema::ApiKey;
+use crate::db::repositories::Storage;
+use serde::{Deserialize};
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::openapi::ErrorResponse;
+use crate::middleware::current_user::CurrentUser;
+
+#[derive(Deserialize)]
+struct ObjectIdPath {
+    id: String,
+}
+
+#[utoipa::path(
+    tag = "api_keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "API key created; the full key is only returned here", body = ApiResponse<CreatedApiKey>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+    ),
+)]
+#[post("/api_keys")]
+pub async fn create_api_key(user: CurrentUser, request: web::Json<CreateApiKeyRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let api_key = ApiKeyService::create_api_key(user.id, request.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("API key created successfully", api_key)))
+}
+
+#[utoipa::path(
+    tag = "api_keys",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Page of API keys of the current user", body = ApiResponse<Paginated<ApiKey>>),
+    ),
+)]
+#[get("/api_keys")]
+pub async fn get_api_keys(user: CurrentUser, page: web::Query<PageQuery>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let api_keys = ApiKeyService::get_api_keys(user.id, &page, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("API keys retrieved successfully", api_keys)))
+}
+
+#[utoipa::path(
+    tag = "api_keys",
+    params(("id" = String, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "API key revoked"),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/api_keys/{id}")]
+pub async fn delete_api_key(user: CurrentUser, path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "API key")?;
+    ApiKeyService::delete_api_key(id, user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("API key deleted successfully", ())))
+}
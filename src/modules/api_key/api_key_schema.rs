@@ -0,0 +1,35 @@
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use mongodb::bson::oid::ObjectId;
+
+// Permisos que puede tener una API key; el rol del usuario sigue aplicándose encima
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    ReadCatalog,      // Lectura de exchanges, assets, pares, equivalencias y datos de mercado
+    ManageStrategies, // Estrategias, backtests, paper trading y alertas
+    Evaluate,         // Evaluación, z-score, profundidad, ciclos y sugerencias
+}
+
+// API key personal (colección "api_keys"). La clave completa es "arbi_{id}_{secreto}"
+// y solo se muestra al crearla; se guarda el hash del secreto.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ApiKey {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _user: ObjectId,
+    pub name: String,
+    // No sale en las respuestas; el repositorio lo guarda aparte
+    #[serde(skip_serializing)]
+    #[schema(ignore)]
+    pub secret_hash: String,
+    pub scopes: Vec<ApiKeyScope>,
+    #[serde(default)]
+    pub expires_at: Option<f64>,
+    #[serde(default)]
+    pub last_used_at: Option<f64>,
+    pub created_at: f64,
+}
@@ -0,0 +1,150 @@
+use crate::db::repositories::ApiKeyRepository;
+use crate::helpers::app_error::AppError;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use mongodb::bson::oid::ObjectId;
+use crate::modules::api_key::api_key_schema::{ApiKey, ApiKeyScope};
+use crate::modules::auth::session_service::{generate_secret, hash_secret};
+use actix_web::http::Method;
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use tracing::info;
+
+const KEY_PREFIX: &str = "arbi_";
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    // Clave completa; no se puede recuperar después
+    pub key: String,
+}
+
+fn split_key(key: &str) -> Option<(ObjectId, &str)> {
+    let (id, secret) = key.strip_prefix(KEY_PREFIX)?.split_once('_')?;
+    let id = ObjectId::parse_str(id).ok()?;
+    (!secret.is_empty()).then_some((id, secret))
+}
+
+// Scope necesario para cada ruta cuando se accede con API key. Las rutas sin scope
+// (cuenta, sesiones, administración, gestión de API keys) solo admiten JWT.
+pub fn required_scope(method: &Method, path: &str) -> Option<ApiKeyScope> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let read = method == Method::GET;
+
+    match segments.as_slice() {
+        ["arbitrage-strategies", "cycles" | "suggested"] if read => Some(ApiKeyScope::Evaluate),
+        ["arbitrage-strategies", _, "evaluate" | "zscore" | "depth"] if read => Some(ApiKeyScope::Evaluate),
+        ["arbitrage-strategies", ..] | ["backtests", ..] | ["paper_trading", ..] | ["alert_rules", ..] => Some(ApiKeyScope::ManageStrategies),
+        ["exchanges" | "assets" | "market_pairs" | "asset_equivalences" | "conversion_pairs", ..] if read => Some(ApiKeyScope::ReadCatalog),
+        ["market_data", "quotes" | "order_books", ..] if read => Some(ApiKeyScope::ReadCatalog),
+        _ => None,
+    }
+}
+
+pub struct ApiKeyService;
+
+impl ApiKeyService {
+    pub async fn create_api_key(owner: ObjectId, request: CreateApiKeyRequest, repo: &dyn ApiKeyRepository) -> Result<CreatedApiKey, AppError> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation("name is required".to_string()));
+        }
+        if request.scopes.is_empty() {
+            return Err(AppError::Validation("At least one scope is required".to_string()));
+        }
+        let now = Utc::now().timestamp() as f64;
+        if request.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(AppError::Validation("expires_at must be in the future".to_string()));
+        }
+
+        let id = ObjectId::new();
+        let secret = generate_secret();
+        // dedup solo quita repetidos consecutivos
+        let mut scopes = request.scopes;
+        scopes.sort();
+        scopes.dedup();
+        let api_key = repo.insert_api_key(ApiKey {
+            id: Some(id),
+            _user: owner,
+            name: name.to_string(),
+            secret_hash: hash_secret(&secret),
+            scopes,
+            expires_at: request.expires_at,
+            last_used_at: None,
+            created_at: now,
+        }).await?;
+
+        info!("API key {} created for user {}", id, owner);
+        Ok(CreatedApiKey { api_key, key: format!("{}{}_{}", KEY_PREFIX, id.to_hex(), secret) })
+    }
+
+    pub async fn get_api_keys(owner: ObjectId, page: &PageQuery, repo: &dyn ApiKeyRepository) -> Result<Paginated<ApiKey>, AppError> {
+        repo.list_owned_api_keys(owner, page).await
+    }
+
+    pub async fn delete_api_key(id: ObjectId, owner: ObjectId, repo: &dyn ApiKeyRepository) -> Result<(), AppError> {
+        if !repo.delete_owned_api_key(id, owner).await? {
+            return Err(AppError::NotFound("API key not found".to_string()));
+        }
+        Ok(())
+    }
+
+    // Valida la clave y registra su último uso en la misma operación
+    pub async fn authenticate(key: &str, repo: &dyn ApiKeyRepository) -> Option<ApiKey> {
+        let (id, secret) = split_key(key)?;
+        let now = Utc::now().timestamp() as f64;
+        repo.use_api_key(id, &hash_secret(secret), now).await.ok().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::InMemoryStorage;
+
+    #[test]
+    fn scopes_follow_the_route() {
+        assert_eq!(required_scope(&Method::GET, "/exchanges"), Some(ApiKeyScope::ReadCatalog));
+        assert_eq!(required_scope(&Method::POST, "/exchanges"), None);
+        assert_eq!(required_scope(&Method::GET, "/arbitrage-strategies/66f0/evaluate"), Some(ApiKeyScope::Evaluate));
+        assert_eq!(required_scope(&Method::PUT, "/arbitrage-strategies/66f0"), Some(ApiKeyScope::ManageStrategies));
+        assert_eq!(required_scope(&Method::GET, "/api_keys"), None);
+        assert_eq!(required_scope(&Method::POST, "/sessions/revoke-all"), None);
+    }
+
+    #[test]
+    fn parses_generated_keys() {
+        let id = ObjectId::new();
+        let key = format!("{}{}_{}", KEY_PREFIX, id.to_hex(), "secret");
+        assert_eq!(split_key(&key), Some((id, "secret")));
+        assert_eq!(split_key("arbi_nothex_secret"), None);
+        assert_eq!(split_key(&format!("{}{}_", KEY_PREFIX, id.to_hex())), None);
+    }
+
+    #[actix_web::test]
+    async fn created_keys_authenticate_without_exposing_the_secret_hash() {
+        let storage = InMemoryStorage::default();
+        let owner = ObjectId::new();
+        let request = CreateApiKeyRequest {
+            name: "bot".to_string(),
+            scopes: vec![ApiKeyScope::Evaluate, ApiKeyScope::ReadCatalog, ApiKeyScope::Evaluate],
+            expires_at: None,
+        };
+
+        let created = ApiKeyService::create_api_key(owner, request, &storage).await.unwrap();
+        assert_eq!(created.api_key.scopes, vec![ApiKeyScope::ReadCatalog, ApiKeyScope::Evaluate]);
+        assert!(serde_json::to_value(&created).unwrap().get("secret_hash").is_none());
+
+        let api_key = ApiKeyService::authenticate(&created.key, &storage).await.unwrap();
+        assert_eq!(api_key._user, owner);
+        assert!(ApiKeyService::authenticate(&format!("{}x", created.key), &storage).await.is_none());
+    }
+}
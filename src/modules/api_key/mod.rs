@@ -0,0 +1,11 @@
+pub mod api_key_schema;
+pub mod api_key_service;
+pub mod api_key_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_key_controller::create_api_key);
+    cfg.service(api_key_controller::get_api_keys);
+    cfg.service(api_key_controller::delete_api_key);
+}
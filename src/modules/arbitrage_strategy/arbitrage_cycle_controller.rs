@@ -0,0 +1,45 @@
+use actix_web::{get, web, HttpResponse};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use crate::modules::arbitrage_strategy::arbitrage_cycle_service::{ArbitrageCycleService, DetectedCycle};
+use crate::openapi::ErrorResponse;
+use mongodb::bson::oid::ObjectId;
+
+#[derive(Deserialize, IntoParams)]
+struct CycleQuery {
+    exchanges: Option<String>, // Ids separados por comas
+    fee: Option<f64>, // Sustituye la comisión taker de cada par
+    min_profit: Option<f64>,
+}
+
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(CycleQuery),
+    responses(
+        (status = 200, description = "Profitable cycles across the listed exchanges (not paginated)", body = ApiResponse<Vec<DetectedCycle>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+    ),
+)]
+#[get("/arbitrage-strategies/cycles")]
+pub async fn detect_arbitrage_cycles(
+    storage: web::Data<dyn Storage>,
+    query: web::Query<CycleQuery>,
+) -> Result<HttpResponse, AppError> {
+    let exchange_ids = query.exchanges.iter()
+        .flat_map(|s| s.split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|id| ObjectId::parse_str(id).map_err(|_| AppError::Validation(format!("Invalid exchange ID: {}", id))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let cycles = ArbitrageCycleService::detect_cycles(
+        &exchange_ids,
+        query.fee,
+        query.min_profit.unwrap_or(0.0),
+        storage.get_ref(),
+    ).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Arbitrage cycles detected successfully", cycles)))
+}
@@ -0,0 +1,268 @@
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, Leg, TradeSide};
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::profit_percentage;
+use crate::modules::arbitrage_strategy::suggested_arbitrage_strategy_service::SuggestedArbitrageStrategyService;
+use crate::modules::market_data::market_data_service::MarketDataService;
+use crate::modules::market_data::exchange_connector::market_symbol;
+use crate::modules::market_pair::market_pair_service::MarketPairService;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+const EPSILON: f64 = 1e-12;
+
+// Arista del grafo de activos con peso -ln(tasa efectiva)
+#[derive(Debug, Clone)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub weight: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct CycleLeg {
+    #[schema(value_type = ObjectIdSchema)]
+    pub market_pair: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub exchange: ObjectId,
+    pub exchange_name: String,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub from_asset: String,
+    pub to_asset: String,
+    pub rate: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DetectedCycle {
+    pub legs: Vec<CycleLeg>,
+    pub profit_percentage: f64,
+    // Estrategia con las mismas patas; None si el ciclo no llega a tener dos
+    pub proposed_strategy: Option<ArbitrageStrategy>,
+}
+
+// Bellman-Ford desde una fuente virtual conectada a todos los nodos. Devuelve cada ciclo
+// negativo una sola vez, como índices de aristas en orden de recorrido.
+pub fn find_negative_cycles(node_count: usize, edges: &[GraphEdge]) -> Vec<Vec<usize>> {
+    let mut dist = vec![0.0; node_count];
+    let mut pred: Vec<Option<usize>> = vec![None; node_count];
+
+    for _ in 0..node_count.saturating_sub(1) {
+        let mut changed = false;
+        for (index, edge) in edges.iter().enumerate() {
+            if dist[edge.from] + edge.weight < dist[edge.to] - EPSILON {
+                dist[edge.to] = dist[edge.from] + edge.weight;
+                pred[edge.to] = Some(index);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut cycles = Vec::new();
+    for (index, edge) in edges.iter().enumerate() {
+        if dist[edge.from] + edge.weight >= dist[edge.to] - EPSILON {
+            continue;
+        }
+        dist[edge.to] = dist[edge.from] + edge.weight;
+        pred[edge.to] = Some(index);
+
+        // Retroceder node_count pasos garantiza caer dentro del ciclo
+        let mut start = edge.to;
+        for _ in 0..node_count {
+            match pred[start] {
+                Some(p) => start = edges[p].from,
+                None => break,
+            }
+        }
+
+        let mut cycle = Vec::new();
+        let mut node = start;
+        loop {
+            let Some(p) = pred[node] else {
+                cycle.clear();
+                break;
+            };
+            cycle.push(p);
+            node = edges[p].from;
+            if node == start || cycle.len() > node_count {
+                break;
+            }
+        }
+        if cycle.is_empty() || node != start {
+            continue;
+        }
+        cycle.reverse();
+
+        let total: f64 = cycle.iter().map(|&e| edges[e].weight).sum();
+        if total >= -EPSILON {
+            continue;
+        }
+
+        let min_position = cycle.iter().enumerate().min_by_key(|(_, &e)| e).map(|(i, _)| i).unwrap_or(0);
+        cycle.rotate_left(min_position);
+        if seen.insert(cycle.clone()) {
+            cycles.push(cycle);
+        }
+    }
+
+    cycles
+}
+
+pub struct ArbitrageCycleService;
+
+impl ArbitrageCycleService {
+    pub async fn detect_cycles(
+        exchange_ids: &[ObjectId],
+        fee_override: Option<f64>,
+        min_profit: f64,
+        storage: &dyn Storage
+    ) -> Result<Vec<DetectedCycle>, AppError> {
+        let pairs = MarketPairService::get_active_market_pairs(storage, exchange_ids).await?;
+        let pair_ids: Vec<ObjectId> = pairs.iter().filter_map(|p| p.id).collect();
+        let quotes = MarketDataService::get_quotes(&pair_ids, storage).await?;
+
+        let mut nodes: HashMap<String, usize> = HashMap::new();
+        let mut edges = Vec::new();
+        let mut legs = Vec::new();
+
+        for pair in pairs.iter() {
+            let (Some(pair_id), Some(exchange_id)) = (pair.id, pair.exchange.id) else { continue };
+            let Some(quote) = quotes.get(&pair_id) else { continue };
+            if quote.bid <= 0.0 || quote.ask <= 0.0 {
+                continue;
+            }
+
+            let next_index = nodes.len();
+            let base = *nodes.entry(pair.base_asset.short_name.clone()).or_insert(next_index);
+            let next_index = nodes.len();
+            let quote_node = *nodes.entry(pair.quote_asset.short_name.clone()).or_insert(next_index);
+            let symbol = market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name);
+            let fee = fee_override.unwrap_or_else(|| pair.effective_taker_fee());
+
+            for (side, from, to, rate) in [
+                (TradeSide::Sell, base, quote_node, quote.bid * (1.0 - fee)),
+                (TradeSide::Buy, quote_node, base, (1.0 - fee) / quote.ask),
+            ] {
+                edges.push(GraphEdge { from, to, weight: -rate.ln() });
+                let (from_asset, to_asset) = match side {
+                    TradeSide::Sell => (&pair.base_asset.short_name, &pair.quote_asset.short_name),
+                    TradeSide::Buy => (&pair.quote_asset.short_name, &pair.base_asset.short_name),
+                };
+                legs.push(CycleLeg {
+                    market_pair: pair_id,
+                    exchange: exchange_id,
+                    exchange_name: pair.exchange.short_name.clone(),
+                    symbol: symbol.clone(),
+                    side,
+                    from_asset: from_asset.clone(),
+                    to_asset: to_asset.clone(),
+                    rate,
+                });
+            }
+        }
+
+        let mut detected = Vec::new();
+        for cycle in find_negative_cycles(nodes.len(), &edges) {
+            let cycle_legs: Vec<CycleLeg> = cycle.iter().map(|&e| legs[e].clone()).collect();
+
+            let mut used_pairs = HashSet::new();
+            if !cycle_legs.iter().all(|leg| used_pairs.insert(leg.market_pair)) {
+                continue;
+            }
+
+            let growth: f64 = cycle_legs.iter().map(|leg| leg.rate).product();
+            let profit = profit_percentage(1.0, growth);
+            if profit < min_profit {
+                continue;
+            }
+
+            detected.push(DetectedCycle {
+                proposed_strategy: Self::propose_strategy(&cycle_legs),
+                legs: cycle_legs,
+                profit_percentage: profit,
+            });
+        }
+
+        detected.sort_by(|a, b| b.profit_percentage.total_cmp(&a.profit_percentage));
+        info!("Detected {} profitable cycles over {} market pairs", detected.len(), pairs.len());
+        Ok(detected)
+    }
+
+    // Etiqueta el ciclo con la primera rotación que encaja en Exchange, Triangular o Geographic;
+    // cualquier otro ciclo de tres o más patas queda como TradingPair con las patas tal cual
+    pub fn propose_strategy(legs: &[CycleLeg]) -> Option<ArbitrageStrategy> {
+        let single_exchange = legs.iter().all(|leg| leg.exchange == legs[0].exchange);
+        let to_legs = |cycle: &[&CycleLeg]| -> Vec<Leg> {
+            cycle.iter().map(|leg| Leg { market_pair: leg.market_pair, side: leg.side, exchange: leg.exchange }).collect()
+        };
+
+        for offset in 0..legs.len() {
+            let rotated: Vec<&CycleLeg> = legs.iter().cycle().skip(offset).take(legs.len()).collect();
+
+            let arbitrage_type = match rotated.as_slice() {
+                [buy, sell] if !single_exchange && buy.side == TradeSide::Buy && buy.symbol == sell.symbol => Some(ArbitrageType::Exchange),
+                [_, _, _] if single_exchange => Some(ArbitrageType::Triangular),
+                [buy, sell, _]
+                    if buy.side == TradeSide::Buy
+                        && sell.side == TradeSide::Sell
+                        && buy.exchange != sell.exchange
+                        && buy.to_asset == sell.from_asset => Some(ArbitrageType::Geographic),
+                _ => None,
+            };
+
+            if let Some(arbitrage_type) = arbitrage_type {
+                return Some(SuggestedArbitrageStrategyService::suggestion(arbitrage_type, to_legs(&rotated)));
+            }
+        }
+
+        if legs.len() < 3 {
+            return None;
+        }
+        let cycle: Vec<&CycleLeg> = legs.iter().collect();
+        Some(SuggestedArbitrageStrategyService::suggestion(ArbitrageType::TradingPair, to_legs(&cycle)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(from: usize, to: usize, rate: f64) -> GraphEdge {
+        GraphEdge { from, to, weight: -f64::ln(rate) }
+    }
+
+    #[test]
+    fn detects_profitable_triangle() {
+        // USDT(0) -> ETH(1) -> BTC(2) -> USDT(0) con el ejemplo triangular del README
+        let edges = vec![
+            edge(0, 1, 1.0 / 1850.0),
+            edge(1, 2, 0.062338),
+            edge(2, 0, 30000.0),
+            edge(1, 0, 1850.0 * 0.99),
+        ];
+
+        let cycles = find_negative_cycles(3, &edges);
+
+        assert_eq!(cycles, vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn no_cycles_without_arbitrage() {
+        let edges = vec![
+            edge(0, 1, 1.0 / 1850.0),
+            edge(1, 0, 1849.0),
+            edge(1, 2, 0.06),
+            edge(2, 1, 1.0 / 0.0601),
+        ];
+
+        assert!(find_negative_cycles(3, &edges).is_empty());
+    }
+}
@@ -0,0 +1,465 @@
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageType, TradeSide};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::{ArbitrageStrategyService, PopulatedLeg};
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::{profit_percentage, ArbitrageEvaluationService, CycleDirection, RouteSelection};
+use crate::modules::arbitrage_strategy::statistical_arbitrage_service::StatisticalArbitrageService;
+use crate::modules::market_data::market_data_schema::{OrderBook, OrderBookLevel};
+use crate::modules::market_data::market_data_service::MarketDataService;
+use crate::modules::market_data::exchange_connector::market_symbol;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use std::collections::HashMap;
+
+// Iteraciones de la búsqueda binaria, fracción de la capacidad usada como tamaño mínimo y
+// número de tamaños, en progresión geométrica hasta la capacidad, que se prueban antes de bisecar
+const SEARCH_ITERATIONS: usize = 60;
+const PROBE_FRACTION: f64 = 1e-6;
+const SCAN_STEPS: usize = 60;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DepthLegResult {
+    #[schema(value_type = ObjectIdSchema)]
+    pub market_pair: ObjectId,
+    pub exchange: String,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub asset_in: String,
+    pub amount_in: f64,
+    pub asset_out: String,
+    pub amount_out: f64,
+    // Precio del mejor nivel y precio medio ponderado por volumen al recorrer el libro
+    pub best_price: f64,
+    pub vwap: f64,
+    pub slippage_percentage: f64,
+    pub levels_consumed: usize,
+    pub fee: f64,
+    pub withdrawal_fee: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DepthEvaluation {
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub strategy_id: Option<ObjectId>,
+    pub arbitrage_type: ArbitrageType,
+    pub direction: Option<CycleDirection>,
+    pub min_profit: f64,
+    pub start_asset: String,
+    // Importes mínimo y máximo en start_asset que ejecutan todas las patas con beneficio >= min_profit
+    // (0 si no hay). Con comisiones de retiro fijas los importes pequeños tampoco son rentables.
+    pub min_amount: f64,
+    pub max_amount: f64,
+    // Máximo importe que los libros pueden absorber en la primera pata
+    pub capacity: f64,
+    pub final_amount: f64,
+    pub profit_percentage: f64,
+    // En Statistical, precio medio al que se espera cerrar la posición
+    pub exit_price: Option<f64>,
+    // Patas ejecutadas con max_amount, o con el tamaño más rentable probado si ninguno alcanza min_profit
+    pub legs: Vec<DepthLegResult>,
+}
+
+// Patas, importe final y beneficio de un tamaño rentable
+type Profitable = (Vec<DepthLegResult>, f64, f64);
+
+// Resultado de la búsqueda para un recorrido
+struct SizedRoute {
+    min_amount: f64,
+    max_amount: f64,
+    legs: Vec<DepthLegResult>,
+    final_amount: f64,
+    profit_percentage: f64,
+}
+
+// Recorre los niveles del lado correspondiente gastando `amount` del activo que se entrega.
+// Devuelve (cantidad recibida, niveles consumidos) o None si el libro no tiene profundidad suficiente.
+pub fn walk_book(levels: &[OrderBookLevel], side: TradeSide, amount: f64) -> Option<(f64, usize)> {
+    let mut remaining = amount;
+    let mut received = 0.0;
+    let mut consumed = 0;
+
+    for level in levels.iter().filter(|l| l.price > 0.0 && l.amount > 0.0) {
+        if remaining <= 0.0 {
+            break;
+        }
+        consumed += 1;
+        match side {
+            // Se gasta quote comprando el base disponible en cada ask
+            TradeSide::Buy => {
+                let cost = level.price * level.amount;
+                if remaining <= cost {
+                    received += remaining / level.price;
+                    remaining = 0.0;
+                } else {
+                    received += level.amount;
+                    remaining -= cost;
+                }
+            },
+            // Se vende base contra cada bid
+            TradeSide::Sell => {
+                let filled = remaining.min(level.amount);
+                received += filled * level.price;
+                remaining -= filled;
+            },
+        }
+    }
+
+    if remaining > amount * 1e-12 {
+        None
+    } else {
+        Some((received, consumed))
+    }
+}
+
+// Importe máximo, en el activo entregado, que admite el lado del libro
+pub fn book_capacity(levels: &[OrderBookLevel], side: TradeSide) -> f64 {
+    levels.iter()
+        .map(|l| match side {
+            TradeSide::Buy => l.price * l.amount,
+            TradeSide::Sell => l.amount,
+        })
+        .sum()
+}
+
+// Asks de menor a mayor precio y bids de mayor a menor, como se consumen
+fn sorted_levels(book: &OrderBook, side: TradeSide) -> Vec<OrderBookLevel> {
+    let mut levels = match side {
+        TradeSide::Buy => book.asks.clone(),
+        TradeSide::Sell => book.bids.clone(),
+    };
+    match side {
+        TradeSide::Buy => levels.sort_by(|a, b| a.price.total_cmp(&b.price)),
+        TradeSide::Sell => levels.sort_by(|a, b| b.price.total_cmp(&a.price)),
+    }
+    levels
+}
+
+pub struct ArbitrageDepthService;
+
+impl ArbitrageDepthService {
+    pub async fn evaluate_depth(id: ObjectId, min_profit: f64, storage: &dyn Storage) -> Result<DepthEvaluation, AppError> {
+        let strategy = ArbitrageStrategyService::get_arbitrage_strategy(id, storage).await?;
+        let legs = ArbitrageStrategyService::populate_legs(&strategy.legs, storage).await?;
+        let pair_ids = ArbitrageStrategyService::leg_pair_ids(&strategy.legs);
+        let books = MarketDataService::get_order_books(&pair_ids, storage).await?;
+
+        let mut result = match strategy.arbitrage_type {
+            ArbitrageType::Statistical => {
+                // El beneficio se mide como la vuelta del precio a la media de la ventana
+                let z_score = StatisticalArbitrageService::get_z_score(id, storage).await?;
+                let side = z_score.side.unwrap_or(if z_score.z_score < 0.0 { TradeSide::Buy } else { TradeSide::Sell });
+                Self::size_reversion(&strategy.arbitrage_type, &legs[0].market_pair, side, z_score.mean, min_profit, &books)?
+            },
+            _ => {
+                let equivalences = EquivalenceRegistry::load(storage).await?;
+                Self::size_cycle(&strategy.arbitrage_type, &legs, min_profit, &books, &equivalences)?
+            },
+        };
+        result.strategy_id = strategy.id;
+        Ok(result)
+    }
+
+    // Para cada recorrido del ciclo busca el tamaño máximo rentable y devuelve el mayor
+    pub fn size_cycle(
+        arbitrage_type: &ArbitrageType,
+        legs: &[PopulatedLeg],
+        min_profit: f64,
+        books: &HashMap<ObjectId, OrderBook>,
+        equivalences: &EquivalenceRegistry
+    ) -> Result<DepthEvaluation, AppError> {
+        let mut best: Option<DepthEvaluation> = None;
+        let mut last_error = None;
+
+        for route in ArbitrageEvaluationService::cycle_routes(arbitrage_type, legs, RouteSelection::Best)? {
+            let sized = ArbitrageEvaluationService::check_route(&route, equivalences)
+                .and_then(|_| {
+                    let (first_pair, first_side) = route.legs.first().ok_or_else(|| AppError::Internal("Cycle has no legs".to_string()))?;
+                    let capacity = book_capacity(&Self::levels_for(first_pair, *first_side, books)?, *first_side);
+                    Self::search(capacity, min_profit, |amount| Self::walk_legs(amount, &route.legs, books))
+                        .map(|sized| DepthEvaluation {
+                            strategy_id: None,
+                            arbitrage_type: arbitrage_type.clone(),
+                            direction: Some(route.direction),
+                            min_profit,
+                            start_asset: route.start_asset.clone(),
+                            min_amount: sized.min_amount,
+                            max_amount: sized.max_amount,
+                            capacity,
+                            final_amount: sized.final_amount,
+                            profit_percentage: sized.profit_percentage,
+                            exit_price: None,
+                            legs: sized.legs,
+                        })
+                });
+
+            match sized {
+                Ok(result) => {
+                    let better = best.as_ref().is_none_or(|b| {
+                        result.max_amount > b.max_amount
+                            || (result.max_amount == b.max_amount && result.profit_percentage > b.profit_percentage)
+                    });
+                    if better {
+                        best = Some(result);
+                    }
+                },
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        best.ok_or_else(|| last_error.unwrap_or_else(|| AppError::Validation("No evaluable route for strategy".to_string())))
+    }
+
+    // Entrada en un solo par recorriendo el libro y salida al precio medio, ambas con comisión taker
+    pub fn size_reversion(
+        arbitrage_type: &ArbitrageType,
+        pair: &PopulatedMarketPair,
+        side: TradeSide,
+        mean: f64,
+        min_profit: f64,
+        books: &HashMap<ObjectId, OrderBook>
+    ) -> Result<DepthEvaluation, AppError> {
+        if mean <= 0.0 {
+            return Err(AppError::Validation("Invalid mean price".to_string()));
+        }
+        let fee = pair.effective_taker_fee();
+        let capacity = book_capacity(&Self::levels_for(pair, side, books)?, side);
+        let legs = [(pair, side)];
+
+        let sized = Self::search(capacity, min_profit, |amount| {
+            let (legs, received) = Self::walk_legs(amount, &legs, books)?;
+            let exit = match side {
+                TradeSide::Buy => received * mean,
+                TradeSide::Sell => received / mean,
+            };
+            Ok((legs, exit * (1.0 - fee)))
+        })?;
+
+        let start_asset = match side {
+            TradeSide::Buy => &pair.quote_asset.short_name,
+            TradeSide::Sell => &pair.base_asset.short_name,
+        };
+
+        Ok(DepthEvaluation {
+            strategy_id: None,
+            arbitrage_type: arbitrage_type.clone(),
+            direction: None,
+            min_profit,
+            start_asset: start_asset.clone(),
+            min_amount: sized.min_amount,
+            max_amount: sized.max_amount,
+            capacity,
+            final_amount: sized.final_amount,
+            profit_percentage: sized.profit_percentage,
+            exit_price: Some(mean),
+            legs: sized.legs,
+        })
+    }
+
+    fn levels_for(pair: &PopulatedMarketPair, side: TradeSide, books: &HashMap<ObjectId, OrderBook>) -> Result<Vec<OrderBookLevel>, AppError> {
+        let pair_id = pair.id.ok_or_else(|| AppError::Internal("Market pair without id".to_string()))?;
+        let book = books.get(&pair_id).ok_or_else(|| {
+            AppError::Validation(format!(
+                "No order book for {} on {}",
+                market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name),
+                pair.exchange.short_name
+            ))
+        })?;
+        Ok(sorted_levels(book, side))
+    }
+
+    // Intervalo de importes con beneficio >= min_profit. El beneficio no es monótono: una comisión de
+    // retiro fija hace perder dinero a los importes pequeños y al crecer se consumen niveles peores del
+    // libro. Se prueban tamaños en progresión geométrica hasta la capacidad y se ajustan por bisección
+    // los dos extremos del tramo rentable.
+    fn search<F>(capacity: f64, min_profit: f64, simulate: F) -> Result<SizedRoute, AppError>
+    where
+        F: Fn(f64) -> Result<(Vec<DepthLegResult>, f64), AppError>,
+    {
+        if capacity <= 0.0 {
+            return Err(AppError::Validation("Order book has no depth".to_string()));
+        }
+
+        let profitable = |amount: f64| -> Option<Profitable> {
+            let (legs, final_amount) = simulate(amount).ok()?;
+            let profit = profit_percentage(amount, final_amount);
+            (profit >= min_profit).then_some((legs, final_amount, profit))
+        };
+
+        let probe = capacity * PROBE_FRACTION;
+        let sizes: Vec<f64> = (0..=SCAN_STEPS)
+            .map(|step| if step == SCAN_STEPS { capacity } else { probe * (capacity / probe).powf(step as f64 / SCAN_STEPS as f64) })
+            .collect();
+        let found: Vec<(usize, Profitable)> = sizes.iter().enumerate()
+            .filter_map(|(step, &amount)| profitable(amount).map(|result| (step, result)))
+            .collect();
+
+        let (Some((first, lowest)), Some((last, highest))) = (found.first().cloned(), found.last().cloned()) else {
+            // Ningún tamaño es rentable: se devuelven las patas del más rentable como referencia
+            let reference = sizes.iter()
+                .filter_map(|&amount| simulate(amount).ok().map(|(legs, final_amount)| (amount, legs, final_amount)))
+                .max_by(|a, b| profit_percentage(a.0, a.2).total_cmp(&profit_percentage(b.0, b.2)));
+            let (amount, legs, final_amount) = match reference {
+                Some(reference) => reference,
+                None => {
+                    let (legs, final_amount) = simulate(probe)?;
+                    (probe, legs, final_amount)
+                },
+            };
+            return Ok(SizedRoute { min_amount: 0.0, max_amount: 0.0, legs, final_amount, profit_percentage: profit_percentage(amount, final_amount) });
+        };
+
+        // Acerca el extremo rentable `good` al no rentable `bad` conservando el resultado del último rentable
+        let edge = |mut good: f64, mut result: Profitable, mut bad: f64| {
+            for _ in 0..SEARCH_ITERATIONS {
+                let mid = (good + bad) / 2.0;
+                match profitable(mid) {
+                    Some(found) => {
+                        good = mid;
+                        result = found;
+                    },
+                    None => bad = mid,
+                }
+            }
+            (good, result)
+        };
+
+        let min_amount = if first == 0 { sizes[0] } else { edge(sizes[first], lowest, sizes[first - 1]).0 };
+        let (max_amount, (legs, final_amount, profit)) = if last == SCAN_STEPS {
+            (capacity, highest)
+        } else {
+            edge(sizes[last], highest, sizes[last + 1])
+        };
+
+        Ok(SizedRoute { min_amount, max_amount, legs, final_amount, profit_percentage: profit })
+    }
+
+    // Igual que execute_cycle pero consumiendo niveles del libro en lugar del mejor precio
+    pub fn walk_legs(
+        start_amount: f64,
+        legs: &[(&PopulatedMarketPair, TradeSide)],
+        books: &HashMap<ObjectId, OrderBook>
+    ) -> Result<(Vec<DepthLegResult>, f64), AppError> {
+        let mut amount = start_amount;
+        let mut results = Vec::with_capacity(legs.len());
+
+        for (index, (pair, side)) in legs.iter().enumerate() {
+            let pair_id = pair.id.ok_or_else(|| AppError::Internal("Market pair without id".to_string()))?;
+            let symbol = market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name);
+            let levels = Self::levels_for(pair, *side, books)?;
+            let best_price = levels.first().map(|l| l.price)
+                .ok_or_else(|| AppError::Validation(format!("Empty order book for {} on {}", symbol, pair.exchange.short_name)))?;
+
+            let (received, levels_consumed) = walk_book(&levels, *side, amount)
+                .ok_or_else(|| AppError::Validation(format!("Not enough depth for {} on {}", symbol, pair.exchange.short_name)))?;
+            let (asset_in, asset_out, vwap) = match side {
+                TradeSide::Buy => (&pair.quote_asset, &pair.base_asset, amount / received),
+                TradeSide::Sell => (&pair.base_asset, &pair.quote_asset, received / amount),
+            };
+            let slippage_percentage = match side {
+                TradeSide::Buy => (vwap - best_price) / best_price * 100.0,
+                TradeSide::Sell => (best_price - vwap) / best_price * 100.0,
+            };
+
+            let fee = received * pair.effective_taker_fee();
+            let withdrawal_fee = match legs.get(index + 1) {
+                Some((next, _)) if next.exchange.id != pair.exchange.id => asset_out.withdrawal_fee,
+                _ => 0.0,
+            };
+            let amount_out = (received - fee - withdrawal_fee).max(0.0);
+
+            results.push(DepthLegResult {
+                market_pair: pair_id,
+                exchange: pair.exchange.short_name.clone(),
+                symbol,
+                side: *side,
+                asset_in: asset_in.short_name.clone(),
+                amount_in: amount,
+                asset_out: asset_out.short_name.clone(),
+                amount_out,
+                best_price,
+                vwap,
+                slippage_percentage,
+                levels_consumed,
+                fee,
+                withdrawal_fee,
+            });
+            amount = amount_out;
+        }
+
+        Ok((results, amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::{exchange, leg, pair, round};
+
+    fn level(price: f64, amount: f64) -> OrderBookLevel {
+        OrderBookLevel { price, amount }
+    }
+
+    fn book(pair: &PopulatedMarketPair, bids: Vec<OrderBookLevel>, asks: Vec<OrderBookLevel>) -> (ObjectId, OrderBook) {
+        let id = pair.id.unwrap();
+        (id, OrderBook { id: None, _market_pair: id, _exchange: pair.exchange.id.unwrap(), bids, asks, timestamp: 0.0 })
+    }
+
+    #[test]
+    fn walks_levels_and_reports_vwap() {
+        let asks = vec![level(100.0, 1.0), level(110.0, 1.0)];
+
+        // 210 USDT compran 1 unidad a 100 y otra a 110
+        assert_eq!(walk_book(&asks, TradeSide::Buy, 210.0), Some((2.0, 2)));
+        assert_eq!(walk_book(&asks, TradeSide::Buy, 211.0), None);
+        assert_eq!(walk_book(&[level(100.0, 2.0)], TradeSide::Sell, 1.5), Some((150.0, 1)));
+    }
+
+    #[test]
+    fn max_size_stops_where_depth_erodes_the_spread() {
+        // Exchange: compra en A a 100 (1 unidad) y 102 (10), venta en B a 101.5 (10).
+        // Con 100 USDT el beneficio es 1.5%; al entrar en el nivel de 102 cae por debajo del 0.5%.
+        let (a, b) = (exchange("A"), exchange("B"));
+        let (pair1, pair2) = (pair(&a, "ETH", "USDT"), pair(&b, "ETH", "USDT"));
+        let books: HashMap<_, _> = [
+            book(&pair1, vec![level(99.0, 10.0)], vec![level(100.0, 1.0), level(102.0, 10.0)]),
+            book(&pair2, vec![level(101.5, 10.0)], vec![level(103.0, 10.0)]),
+        ].into_iter().collect();
+        let legs = vec![leg(pair1, TradeSide::Buy), leg(pair2, TradeSide::Sell)];
+
+        let result = ArbitrageDepthService::size_cycle(&ArbitrageType::Exchange, &legs, 0.5, &books, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(result.direction, Some(CycleDirection::Forward));
+        assert!(result.max_amount > 100.0 && result.max_amount < 1120.0);
+        assert!(result.profit_percentage >= 0.5);
+        // La compra pasa al segundo nivel, así que su precio medio supera el mejor ask
+        assert_eq!(result.legs[0].levels_consumed, 2);
+        assert!(result.legs[0].vwap > 100.0);
+        assert_eq!(round(result.legs[1].vwap, 6), 101.5);
+    }
+
+    #[test]
+    fn fixed_withdrawal_fee_sets_a_minimum_size() {
+        // Mismos libros, pero retirar el ETH de A cuesta 0.01 ETH: por debajo de ~78 USDT la comisión
+        // se come el spread y por encima de ~141 USDT lo hace el segundo nivel de asks
+        let (a, b) = (exchange("A"), exchange("B"));
+        let (mut pair1, pair2) = (pair(&a, "ETH", "USDT"), pair(&b, "ETH", "USDT"));
+        pair1.base_asset.withdrawal_fee = 0.01;
+        let books: HashMap<_, _> = [
+            book(&pair1, vec![level(99.0, 10.0)], vec![level(100.0, 1.0), level(102.0, 10.0)]),
+            book(&pair2, vec![level(101.5, 10.0)], vec![level(103.0, 10.0)]),
+        ].into_iter().collect();
+        let legs = vec![leg(pair1, TradeSide::Buy), leg(pair2, TradeSide::Sell)];
+
+        let result = ArbitrageDepthService::size_cycle(&ArbitrageType::Exchange, &legs, 0.2, &books, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(result.direction, Some(CycleDirection::Forward));
+        assert_eq!(round(result.min_amount, 2), 78.08);
+        assert_eq!(round(result.max_amount, 2), 141.29);
+        assert!(result.profit_percentage >= 0.2);
+        assert_eq!(result.legs[0].withdrawal_fee, 0.01);
+    }
+}
@@ -0,0 +1,453 @@
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageType, Leg, TradeSide};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::{ArbitrageStrategyService, PopulatedLeg};
+use crate::modules::market_data::market_data_schema::MarketQuote;
+use crate::modules::market_data::market_data_service::MarketDataService;
+use crate::modules::market_data::exchange_connector::market_symbol;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum CycleDirection {
+    Forward,
+    Reverse,
+}
+
+// Recorridos que se consideran: por defecto solo el guardado; el inverso hay que pedirlo
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteSelection {
+    #[default]
+    Stored,
+    Best,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LegResult {
+    #[schema(value_type = ObjectIdSchema)]
+    pub market_pair: ObjectId,
+    pub exchange: String,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub price: f64,
+    pub asset_in: String,
+    pub amount_in: f64,
+    pub asset_out: String,
+    pub amount_out: f64,
+    // Comisión taker cobrada en asset_out
+    pub fee: f64,
+    // Comisión por retirar asset_out cuando la siguiente pata está en otro exchange
+    pub withdrawal_fee: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct EvaluationResult {
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub strategy_id: Option<ObjectId>,
+    pub arbitrage_type: ArbitrageType,
+    pub direction: CycleDirection,
+    pub start_asset: String,
+    pub start_amount: f64,
+    pub legs: Vec<LegResult>,
+    pub final_asset: String,
+    pub final_amount: f64,
+    pub profit: f64,
+    pub profit_percentage: f64,
+    // Resultado sin comisiones, para comparar el spread bruto con el neto
+    pub gross_final_amount: f64,
+    pub gross_profit_percentage: f64,
+}
+
+// Recorrido candidato de un ciclo: activo inicial y patas en orden de ejecución
+pub struct CycleRoute<'a> {
+    pub direction: CycleDirection,
+    pub start_asset: String,
+    pub legs: Vec<(&'a PopulatedMarketPair, TradeSide)>,
+}
+
+pub fn profit_percentage(start_amount: f64, final_amount: f64) -> f64 {
+    (final_amount - start_amount) / start_amount * 100.0
+}
+
+// Activo que entrega una pata: el quote al comprar y el base al vender
+fn spent_asset(pair: &PopulatedMarketPair, side: TradeSide) -> String {
+    match side {
+        TradeSide::Buy => pair.quote_asset.short_name.clone(),
+        TradeSide::Sell => pair.base_asset.short_name.clone(),
+    }
+}
+
+pub struct ArbitrageEvaluationService;
+
+impl ArbitrageEvaluationService {
+    pub async fn evaluate_strategy(id: ObjectId, amount: f64, selection: RouteSelection, storage: &dyn Storage) -> Result<EvaluationResult, AppError> {
+        let strategy = ArbitrageStrategyService::get_arbitrage_strategy(id, storage).await?;
+        let legs = ArbitrageStrategyService::populate_legs(&strategy.legs, storage).await?;
+        let pair_ids = ArbitrageStrategyService::leg_pair_ids(&strategy.legs);
+        let quotes = MarketDataService::get_quotes(&pair_ids, storage).await?;
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+
+        let mut result = Self::evaluate(&strategy.arbitrage_type, &legs, amount, selection, &quotes, &equivalences)?;
+        result.strategy_id = strategy.id;
+        Ok(result)
+    }
+
+    // Evalúa los recorridos seleccionados del ciclo y devuelve el más rentable
+    pub fn evaluate(
+        arbitrage_type: &ArbitrageType,
+        legs: &[PopulatedLeg],
+        start_amount: f64,
+        selection: RouteSelection,
+        quotes: &HashMap<ObjectId, MarketQuote>,
+        equivalences: &EquivalenceRegistry
+    ) -> Result<EvaluationResult, AppError> {
+        if start_amount <= 0.0 {
+            return Err(AppError::Validation("Amount must be greater than zero".to_string()));
+        }
+
+        let mut best: Option<EvaluationResult> = None;
+        let mut last_error = None;
+
+        for route in Self::cycle_routes(arbitrage_type, legs, selection)? {
+            let evaluated = Self::check_route(&route, equivalences)
+                .and_then(|_| Self::execute_cycle(start_amount, &route.legs, quotes));
+
+            match evaluated {
+                Ok((legs, gross_final_amount)) => {
+                    let final_leg = legs.last().ok_or_else(|| AppError::Internal("Cycle has no legs".to_string()))?;
+                    let final_amount = final_leg.amount_out;
+                    let result = EvaluationResult {
+                        strategy_id: None,
+                        arbitrage_type: arbitrage_type.clone(),
+                        direction: route.direction,
+                        start_asset: route.start_asset.clone(),
+                        start_amount,
+                        final_asset: final_leg.asset_out.clone(),
+                        final_amount,
+                        profit: final_amount - start_amount,
+                        profit_percentage: profit_percentage(start_amount, final_amount),
+                        gross_final_amount,
+                        gross_profit_percentage: profit_percentage(start_amount, gross_final_amount),
+                        legs,
+                    };
+                    if best.as_ref().is_none_or(|b| result.profit_percentage > b.profit_percentage) {
+                        best = Some(result);
+                    }
+                },
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        best.ok_or_else(|| last_error.unwrap_or_else(|| AppError::Validation("No evaluable route for strategy".to_string())))
+    }
+
+    // El recorrido guardado y, con RouteSelection::Best, el inverso: mismas patas en orden contrario y con el lado cambiado
+    pub fn cycle_routes<'a>(arbitrage_type: &ArbitrageType, legs: &'a [PopulatedLeg], selection: RouteSelection) -> Result<Vec<CycleRoute<'a>>, AppError> {
+        if *arbitrage_type == ArbitrageType::Statistical {
+            return Err(AppError::Validation("Statistical strategies are not cycles; use the z-score endpoint".to_string()));
+        }
+        if legs.is_empty() {
+            return Err(AppError::Validation("Strategy has no legs".to_string()));
+        }
+
+        let forward: Vec<_> = legs.iter().map(|leg| (&leg.market_pair, leg.side)).collect();
+        let mut routes = vec![(CycleDirection::Forward, forward)];
+        if selection == RouteSelection::Best {
+            routes.push((CycleDirection::Reverse, legs.iter().rev().map(|leg| (&leg.market_pair, leg.side.opposite())).collect()));
+        }
+
+        Ok(routes
+            .into_iter()
+            .map(|(direction, legs)| CycleRoute { direction, start_asset: spent_asset(legs[0].0, legs[0].1), legs })
+            .collect())
+    }
+
+    // Comprueba que cada pata gasta el activo que deja la anterior
+    pub fn check_route(route: &CycleRoute, equivalences: &EquivalenceRegistry) -> Result<(), AppError> {
+        let mut holding = route.start_asset.clone();
+
+        for (index, (pair, side)) in route.legs.iter().enumerate() {
+            if !equivalences.same_asset(&holding, &spent_asset(pair, *side)) {
+                return Err(AppError::Validation(format!(
+                    "Leg {} ({}) does not trade {}",
+                    index + 1,
+                    market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name),
+                    holding
+                )));
+            }
+            holding = match side {
+                TradeSide::Buy => pair.base_asset.short_name.clone(),
+                TradeSide::Sell => pair.quote_asset.short_name.clone(),
+            };
+        }
+
+        Ok(())
+    }
+
+    // Patas de una estrategia a partir de sus pares en orden: el lado de cada una sale de recorrer
+    // el ciclo desde el quote del primer par (Geographic, Exchange) o desde el activo que comparten
+    // el primero y el último (Triangular, TradingPair)
+    pub fn plan_strategy_legs(arbitrage_type: &ArbitrageType, pairs: &[&PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Result<Vec<Leg>, AppError> {
+        let (first, last) = match (pairs.first(), pairs.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(AppError::Validation("Strategy has no pairs".to_string())),
+        };
+        let start_asset = match arbitrage_type {
+            ArbitrageType::Geographic | ArbitrageType::Exchange => first.quote_asset.short_name.clone(),
+            ArbitrageType::Triangular | ArbitrageType::TradingPair => [&first.base_asset.short_name, &first.quote_asset.short_name]
+                .into_iter()
+                .find(|asset| equivalences.same_asset(asset, &last.base_asset.short_name) || equivalences.same_asset(asset, &last.quote_asset.short_name))
+                .cloned()
+                .ok_or_else(|| AppError::Validation("First and last pairs do not share an asset".to_string()))?,
+            ArbitrageType::Statistical => return Err(AppError::Validation("Statistical strategies are not cycles".to_string())),
+        };
+
+        Self::plan_cycle(&start_asset, pairs, equivalences)?
+            .into_iter()
+            .map(|(pair, side)| Ok(Leg {
+                market_pair: pair.id.ok_or_else(|| AppError::Internal("Market pair without id".to_string()))?,
+                side,
+                exchange: pair.exchange.id.ok_or_else(|| AppError::Internal("Exchange without id".to_string()))?,
+            }))
+            .collect()
+    }
+
+    // Decide el lado de cada pata según el activo que se tiene en mano
+    pub fn plan_cycle<'a>(start_asset: &str, pairs: &[&'a PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Result<Vec<(&'a PopulatedMarketPair, TradeSide)>, AppError> {
+        let mut holding = start_asset.to_string();
+        let mut legs = Vec::with_capacity(pairs.len());
+
+        for (index, pair) in pairs.iter().enumerate() {
+            if equivalences.same_asset(&holding, &pair.base_asset.short_name) {
+                legs.push((*pair, TradeSide::Sell));
+                holding = pair.quote_asset.short_name.clone();
+            } else if equivalences.same_asset(&holding, &pair.quote_asset.short_name) {
+                legs.push((*pair, TradeSide::Buy));
+                holding = pair.base_asset.short_name.clone();
+            } else {
+                return Err(AppError::Validation(format!(
+                    "Leg {} ({}) does not trade {}",
+                    index + 1,
+                    market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name),
+                    holding
+                )));
+            }
+        }
+
+        Ok(legs)
+    }
+
+    // Ejecuta las patas sobre las cotizaciones aplicando la comisión taker de cada par y la de
+    // retiro al pasar a otro exchange. Devuelve las patas netas y el importe final sin comisiones.
+    pub fn execute_cycle(
+        start_amount: f64,
+        legs: &[(&PopulatedMarketPair, TradeSide)],
+        quotes: &HashMap<ObjectId, MarketQuote>
+    ) -> Result<(Vec<LegResult>, f64), AppError> {
+        let mut amount = start_amount;
+        let mut gross_amount = start_amount;
+        let mut results = Vec::with_capacity(legs.len());
+
+        for (index, (pair, side)) in legs.iter().enumerate() {
+            let pair_id = pair.id.ok_or_else(|| AppError::Internal("Market pair without id".to_string()))?;
+            let symbol = market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name);
+            let quote = quotes.get(&pair_id)
+                .ok_or_else(|| AppError::Validation(format!("No quote for {} on {}", symbol, pair.exchange.short_name)))?;
+
+            let (price, asset_in, asset_out) = match side {
+                TradeSide::Buy => (quote.ask, &pair.quote_asset, &pair.base_asset),
+                TradeSide::Sell => (quote.bid, &pair.base_asset, &pair.quote_asset),
+            };
+            if price <= 0.0 {
+                return Err(AppError::Validation(format!("Invalid price for {} on {}", symbol, pair.exchange.short_name)));
+            }
+            let convert = |value: f64| match side {
+                TradeSide::Buy => value / price,
+                TradeSide::Sell => value * price,
+            };
+
+            let traded = convert(amount);
+            let fee = traded * pair.effective_taker_fee();
+            let withdrawal_fee = match legs.get(index + 1) {
+                Some((next, _)) if next.exchange.id != pair.exchange.id => asset_out.withdrawal_fee,
+                _ => 0.0,
+            };
+            let amount_out = (traded - fee - withdrawal_fee).max(0.0);
+
+            results.push(LegResult {
+                market_pair: pair_id,
+                exchange: pair.exchange.short_name.clone(),
+                symbol,
+                side: *side,
+                price,
+                asset_in: asset_in.short_name.clone(),
+                amount_in: amount,
+                asset_out: asset_out.short_name.clone(),
+                amount_out,
+                fee,
+                withdrawal_fee,
+            });
+            amount = amount_out;
+            gross_amount = convert(gross_amount);
+        }
+
+        Ok((results, gross_amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::{exchange, exchange_with_fee, leg, pair, quote, round};
+
+    #[test]
+    fn readme_triangular_cycle() {
+        // Ejemplo del README: 1850 USDT -> 1 ETH -> 0.062338 BTC -> 1870.14 USDT (1.09%)
+        let ex = exchange("X");
+        let eth_usdt = pair(&ex, "ETH", "USDT");
+        let eth_btc = pair(&ex, "ETH", "BTC");
+        let btc_usdt = pair(&ex, "BTC", "USDT");
+        let quotes: HashMap<_, _> = [
+            quote(&eth_usdt, 1850.0),
+            quote(&eth_btc, 0.062338),
+            quote(&btc_usdt, 30000.0),
+        ].into_iter().collect();
+        let legs = vec![leg(eth_usdt, TradeSide::Buy), leg(eth_btc, TradeSide::Sell), leg(btc_usdt, TradeSide::Sell)];
+
+        let result = ArbitrageEvaluationService::evaluate(&ArbitrageType::Triangular, &legs, 1850.0, RouteSelection::Stored, &quotes, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(result.direction, CycleDirection::Forward);
+        assert_eq!(result.start_asset, "USDT");
+        assert_eq!(result.final_asset, "USDT");
+        assert_eq!(round(result.legs[0].amount_out, 4), 1.0);
+        assert_eq!(result.legs[0].side, TradeSide::Buy);
+        assert_eq!(result.legs[1].side, TradeSide::Sell);
+        assert_eq!(result.legs[2].side, TradeSide::Sell);
+        assert_eq!(round(result.legs[1].amount_out, 6), 0.062338);
+        assert_eq!(round(result.final_amount, 2), 1870.14);
+        assert_eq!(round(result.profit_percentage, 2), 1.09);
+    }
+
+    #[test]
+    fn readme_trading_pair_discrepancy() {
+        let ex = exchange("Y");
+        let ltc_eth = pair(&ex, "LTC", "ETH");
+        let eth_btc = pair(&ex, "ETH", "BTC");
+        let ltc_btc = pair(&ex, "LTC", "BTC");
+
+        let quotes: HashMap<_, _> = [
+            quote(&ltc_eth, 0.068),
+            quote(&eth_btc, 0.06),
+            quote(&ltc_btc, 0.004),
+        ].into_iter().collect();
+        let legs = vec![leg(ltc_eth, TradeSide::Sell), leg(eth_btc, TradeSide::Sell), leg(ltc_btc, TradeSide::Buy)];
+
+        // Ejemplo del README: vender LTC por ETH, ETH por BTC y recomprar LTC: 1 LTC -> 1.02 LTC (2%)
+        let result = ArbitrageEvaluationService::evaluate(&ArbitrageType::TradingPair, &legs, 1.0, RouteSelection::Stored, &quotes, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(result.start_asset, "LTC");
+        assert_eq!(result.direction, CycleDirection::Forward);
+        assert_eq!(round(result.legs[0].amount_out, 6), 0.068);
+        assert_eq!(round(result.legs[1].amount_out, 6), 0.00408);
+        assert_eq!(round(result.final_amount, 4), 1.02);
+        assert_eq!(round(result.profit_percentage, 2), 2.0);
+    }
+
+    #[test]
+    fn missing_quote_is_reported() {
+        let ex = exchange("X");
+        let p1 = pair(&ex, "BTC", "USDT");
+        let p2 = pair(&ex, "BTC", "USDC");
+        let quotes: HashMap<_, _> = [quote(&p1, 30000.0)].into_iter().collect();
+        let legs = vec![leg(p1, TradeSide::Buy), leg(p2, TradeSide::Sell)];
+
+        let err = ArbitrageEvaluationService::evaluate(&ArbitrageType::Exchange, &legs, 100.0, RouteSelection::Stored, &quotes, &EquivalenceRegistry::default()).unwrap_err();
+        assert!(err.message().contains("No quote for BTC/USDC"));
+    }
+
+    #[test]
+    fn raw_spread_is_consumed_by_taker_fees() {
+        // Spread bruto del 0.3% repartido en tres patas taker al 0.1%: el ciclo pierde dinero
+        let ex = exchange_with_fee("X", 0.001);
+        let eth_usdt = pair(&ex, "ETH", "USDT");
+        let eth_btc = pair(&ex, "ETH", "BTC");
+        let btc_usdt = pair(&ex, "BTC", "USDT");
+        let quotes: HashMap<_, _> = [
+            quote(&eth_usdt, 2000.0),
+            quote(&eth_btc, 0.05),
+            quote(&btc_usdt, 40000.0 * 1.003),
+        ].into_iter().collect();
+        let legs = vec![leg(eth_usdt, TradeSide::Buy), leg(eth_btc, TradeSide::Sell), leg(btc_usdt, TradeSide::Sell)];
+
+        let result = ArbitrageEvaluationService::evaluate(&ArbitrageType::Triangular, &legs, 1000.0, RouteSelection::Stored, &quotes, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(round(result.gross_profit_percentage, 2), 0.3);
+        assert!(result.profit_percentage < 0.0);
+        assert!(result.legs.iter().all(|leg| leg.fee > 0.0));
+    }
+
+    #[test]
+    fn reverse_route_flips_every_side() {
+        let ex = exchange("X");
+        let legs = vec![
+            leg(pair(&ex, "ETH", "USDT"), TradeSide::Buy),
+            leg(pair(&ex, "ETH", "BTC"), TradeSide::Sell),
+            leg(pair(&ex, "BTC", "USDT"), TradeSide::Sell),
+        ];
+
+        let routes = ArbitrageEvaluationService::cycle_routes(&ArbitrageType::Triangular, &legs, RouteSelection::Best).unwrap();
+        let reverse = &routes[1];
+
+        assert_eq!(reverse.start_asset, "USDT");
+        assert_eq!(reverse.legs.iter().map(|(_, side)| *side).collect::<Vec<_>>(), vec![TradeSide::Buy, TradeSide::Buy, TradeSide::Sell]);
+        assert!(ArbitrageEvaluationService::check_route(reverse, &EquivalenceRegistry::default()).is_ok());
+    }
+
+    #[test]
+    fn reverse_route_is_only_considered_on_request() {
+        // Comprar en A y vender en B pierde; el recorrido inverso ganaría, pero no es la estrategia guardada
+        let (a, b) = (exchange("A"), exchange("B"));
+        let (eth_a, eth_b) = (pair(&a, "ETH", "USDT"), pair(&b, "ETH", "USDT"));
+        let quotes: HashMap<_, _> = [quote(&eth_a, 2000.0), quote(&eth_b, 1900.0)].into_iter().collect();
+        let legs = vec![leg(eth_a, TradeSide::Buy), leg(eth_b, TradeSide::Sell)];
+        let equivalences = EquivalenceRegistry::default();
+
+        let stored = ArbitrageEvaluationService::evaluate(&ArbitrageType::Exchange, &legs, 2000.0, RouteSelection::Stored, &quotes, &equivalences).unwrap();
+        assert_eq!(stored.direction, CycleDirection::Forward);
+        assert!(stored.profit < 0.0);
+
+        let best = ArbitrageEvaluationService::evaluate(&ArbitrageType::Exchange, &legs, 2000.0, RouteSelection::Best, &quotes, &equivalences).unwrap();
+        assert_eq!(best.direction, CycleDirection::Reverse);
+        assert!(best.profit > 0.0);
+    }
+
+    #[test]
+    fn broken_chain_of_sides_is_rejected() {
+        let ex = exchange("X");
+        let legs = vec![leg(pair(&ex, "BTC", "USDT"), TradeSide::Buy), leg(pair(&ex, "BTC", "USDC"), TradeSide::Buy)];
+
+        let routes = ArbitrageEvaluationService::cycle_routes(&ArbitrageType::Exchange, &legs, RouteSelection::Best).unwrap();
+        let err = ArbitrageEvaluationService::check_route(&routes[0], &EquivalenceRegistry::default()).unwrap_err();
+        assert_eq!(err, AppError::Validation("Leg 2 (BTC/USDC) does not trade BTC".to_string()));
+    }
+
+    #[test]
+    fn strategy_legs_are_planned_from_the_shared_asset() {
+        let ex = exchange("Y");
+        let ltc_eth = pair(&ex, "LTC", "ETH");
+        let eth_btc = pair(&ex, "ETH", "BTC");
+        let ltc_btc = pair(&ex, "LTC", "BTC");
+
+        let legs = ArbitrageEvaluationService::plan_strategy_legs(&ArbitrageType::TradingPair, &[&ltc_eth, &eth_btc, &ltc_btc], &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(legs.iter().map(|leg| leg.side).collect::<Vec<_>>(), vec![TradeSide::Sell, TradeSide::Sell, TradeSide::Buy]);
+        assert_eq!(legs[2].market_pair, ltc_btc.id.unwrap());
+        assert_eq!(legs[2].exchange, ex.id.unwrap());
+    }
+}
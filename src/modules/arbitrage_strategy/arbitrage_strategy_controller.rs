@@ -1,29 +1,56 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
-use crate::db::mongodb::MongoDbContext;
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::{ArbitrageStrategyService, PopulatedArbitrageStrategy};
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::{ArbitrageEvaluationService, EvaluationResult, RouteSelection};
+use crate::modules::arbitrage_strategy::statistical_arbitrage_service::{StatisticalArbitrageService, ZScoreResult};
+use crate::modules::arbitrage_strategy::arbitrage_depth_service::{ArbitrageDepthService, DepthEvaluation};
+use crate::db::repositories::Storage;
 use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize};
+use utoipa::IntoParams;
 use crate::modules::auth::auth_response::ApiResponse;
-use tracing::{info, error};
-use serde_json::json;
+use crate::openapi::ErrorResponse;
+use crate::middleware::current_user::CurrentUser;
+use tracing::info;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
 
 #[derive(Deserialize)]
 struct ObjectIdPath {
     id: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 struct ArbitrageStrategyQuery {
-    page: Option<u64>,
-    per_page: Option<u64>,
     arbitrage_type: Option<ArbitrageType>,
 }
 
+#[derive(Deserialize, IntoParams)]
+struct EvaluateQuery {
+    amount: Option<f64>,
+    // "best" también evalúa el recorrido inverso
+    #[serde(default)]
+    direction: RouteSelection,
+}
+
+#[derive(Deserialize, IntoParams)]
+struct DepthQuery {
+    min_profit: Option<f64>, // Porcentaje mínimo neto de comisiones
+}
+
+// Las operaciones sobre una estrategia solo se permiten a su propietario
+async fn owned_strategy_id(path: &ObjectIdPath, user: &CurrentUser, storage: &dyn Storage) -> Result<ObjectId, AppError> {
+    let id = parse_object_id(&path.id, "arbitrage strategy")?;
+    ArbitrageStrategyService::get_owned_arbitrage_strategy(id, user.id, storage).await?;
+    Ok(id)
+}
+
 // #[post("/arbitrage-strategies")]
-// pub async fn create_arbitrage_strategy(strategy: web::Json<ArbitrageStrategy>, db_context: web::Data<MongoDbContext>) -> impl Responder {
+// pub async fn create_arbitrage_strategy(strategy: web::Json<ArbitrageStrategy>) -> impl Responder {
 //     println!("Creating arbitrage strategy");
-//     match ArbitrageStrategyService::create_arbitrage_strategy(strategy.into_inner(), &db_context).await {
+//     match ArbitrageStrategyService::create_arbitrage_strategy(strategy.into_inner(), storage.get_ref()).await {
 //         Ok(strategy) => HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy created successfully", strategy)),
 //         Err(err) => {
 //             error!("Failed to create arbitrage strategy: {}", err);
@@ -32,84 +59,159 @@ struct ArbitrageStrategyQuery {
 //     }
 // }
 
-#[post("/arbitrage-strategies")]
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    request_body = ArbitrageStrategy,
+    responses(
+        (status = 200, description = "Strategy created for the current user", body = ApiResponse<ArbitrageStrategy>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+    ),
+)]
+#[post("/arbitrage-strategies", wrap = "RequirePermission(Permission::Trade)")]
 pub async fn create_arbitrage_strategy(
+    user: CurrentUser,
     strategy: web::Json<ArbitrageStrategy>,
-    db_context: web::Data<MongoDbContext>
-) -> impl Responder {
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
     info!("Received data: {:?}", strategy);
 
-    match ArbitrageStrategyService::create_arbitrage_strategy(strategy.into_inner(), &db_context).await {
-        Ok(created_strategy) => {
-            info!("Strategy created successfully: {:?}", created_strategy);
-            HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy created successfully", created_strategy))
-        },
-        Err(err) => {
-            error!("Failed to create arbitrage strategy: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&format!("Failed to create strategy: {}", err)))
-        },
-    }
+    let created_strategy = ArbitrageStrategyService::create_arbitrage_strategy(strategy.into_inner(), user.id, storage.get_ref()).await?;
+    info!("Strategy created successfully: {:?}", created_strategy);
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy created successfully", created_strategy)))
 }
 
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(("id" = String, Path, description = "Arbitrage strategy ID")),
+    responses(
+        (status = 200, description = "Strategy", body = ApiResponse<ArbitrageStrategy>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
 #[get("/arbitrage-strategies/{id}")]
-pub async fn get_arbitrage_strategy(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match ArbitrageStrategyService::get_arbitrage_strategy(id, &db_context).await {
-        Ok(strategy) => HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy retrieved successfully", strategy)),
-        Err(err) => {
-            error!("Failed to retrieve arbitrage strategy: {}", err);
-            HttpResponse::NotFound().json(ApiResponse::<String>::error(&err))
-        },
-    }
+pub async fn get_arbitrage_strategy(user: CurrentUser, path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "arbitrage strategy")?;
+    let strategy = ArbitrageStrategyService::get_owned_arbitrage_strategy(id, user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy retrieved successfully", strategy)))
 }
 
-#[put("/arbitrage-strategies/{id}")]
-pub async fn update_arbitrage_strategy(path: web::Path<ObjectIdPath>, strategy: web::Json<ArbitrageStrategy>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match ArbitrageStrategyService::update_arbitrage_strategy(id, strategy.into_inner(), &db_context).await {
-        Ok(strategy) => HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy updated successfully", strategy)),
-        Err(err) => {
-            error!("Failed to update arbitrage strategy: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(("id" = String, Path, description = "Arbitrage strategy ID")),
+    request_body = ArbitrageStrategy,
+    responses(
+        (status = 200, description = "Strategy updated", body = ApiResponse<ArbitrageStrategy>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[put("/arbitrage-strategies/{id}", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn update_arbitrage_strategy(user: CurrentUser, path: web::Path<ObjectIdPath>, strategy: web::Json<ArbitrageStrategy>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "arbitrage strategy")?;
+    let strategy = ArbitrageStrategyService::update_arbitrage_strategy(id, user.id, strategy.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy updated successfully", strategy)))
 }
 
-#[delete("/arbitrage-strategies/{id}")]
-pub async fn delete_arbitrage_strategy(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match ArbitrageStrategyService::delete_arbitrage_strategy(id, &db_context).await {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy deleted successfully", ())),
-        Err(err) => {
-            error!("Failed to delete arbitrage strategy: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(("id" = String, Path, description = "Arbitrage strategy ID")),
+    responses(
+        (status = 200, description = "Strategy deleted"),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/arbitrage-strategies/{id}", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn delete_arbitrage_strategy(user: CurrentUser, path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "arbitrage strategy")?;
+    ArbitrageStrategyService::delete_arbitrage_strategy(id, user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy deleted successfully", ())))
 }
 
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(PageQuery, ArbitrageStrategyQuery),
+    responses(
+        (status = 200, description = "Page of the user strategies", body = ApiResponse<Paginated<PopulatedArbitrageStrategy>>),
+    ),
+)]
 #[get("/arbitrage-strategies")]
 pub async fn get_all_arbitrage_strategies(
-    db_context: web::Data<MongoDbContext>,
+    user: CurrentUser,
+    storage: web::Data<dyn Storage>,
+    page: web::Query<PageQuery>,
     query: web::Query<ArbitrageStrategyQuery>,
-) -> impl Responder {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-    
-    match ArbitrageStrategyService::get_all_arbitrage_strategies(
-        &db_context,
-        page,
-        per_page,
+) -> Result<HttpResponse, AppError> {
+    let strategies = ArbitrageStrategyService::get_all_arbitrage_strategies(
+        storage.get_ref(),
+        user.id,
+        &page,
         query.arbitrage_type.clone(),
-    ).await {
-        Ok((strategies, total)) => HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategies retrieved successfully", json!({
-            "strategies": strategies,
-            "total": total,
-            "page": page,
-            "per_page": per_page
-        }))),
-        Err(err) => {
-            error!("Failed to retrieve arbitrage strategies: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
-}
\ No newline at end of file
+    ).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategies retrieved successfully", strategies)))
+}
+
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(("id" = String, Path, description = "Arbitrage strategy ID"), EvaluateQuery),
+    responses(
+        (status = 200, description = "Quote-based evaluation of the strategy", body = ApiResponse<EvaluationResult>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/arbitrage-strategies/{id}/evaluate")]
+pub async fn evaluate_arbitrage_strategy(
+    user: CurrentUser,
+    path: web::Path<ObjectIdPath>,
+    query: web::Query<EvaluateQuery>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let id = owned_strategy_id(&path, &user, storage.get_ref()).await?;
+
+    let result = ArbitrageEvaluationService::evaluate_strategy(id, query.amount.unwrap_or(1.0), query.direction, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Arbitrage strategy evaluated successfully", result)))
+}
+
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(("id" = String, Path, description = "Arbitrage strategy ID")),
+    responses(
+        (status = 200, description = "Z-score of the statistical strategy", body = ApiResponse<ZScoreResult>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/arbitrage-strategies/{id}/zscore")]
+pub async fn get_arbitrage_strategy_z_score(user: CurrentUser, path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = owned_strategy_id(&path, &user, storage.get_ref()).await?;
+
+    let result = StatisticalArbitrageService::get_z_score(id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Z-score computed successfully", result)))
+}
+
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(("id" = String, Path, description = "Arbitrage strategy ID"), DepthQuery),
+    responses(
+        (status = 200, description = "Order book depth evaluation", body = ApiResponse<DepthEvaluation>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/arbitrage-strategies/{id}/depth")]
+pub async fn get_arbitrage_strategy_depth(
+    user: CurrentUser,
+    path: web::Path<ObjectIdPath>,
+    query: web::Query<DepthQuery>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let id = owned_strategy_id(&path, &user, storage.get_ref()).await?;
+
+    let result = ArbitrageDepthService::evaluate_depth(id, query.min_profit.unwrap_or(0.0), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Order book depth evaluated successfully", result)))
+}
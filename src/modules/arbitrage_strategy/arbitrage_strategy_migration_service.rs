@@ -0,0 +1,193 @@
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use mongodb::bson::{self, oid::ObjectId, Document};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageType, Leg, StatisticalParams, TradeSide};
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::ArbitrageEvaluationService;
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use crate::modules::market_pair::market_pair_service::{MarketPairService, PopulatedMarketPair};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+// Formato anterior de las estrategias: un par por campo y variante, sin lado explícito
+#[derive(Deserialize, Debug, Clone)]
+enum LegacyDetails {
+    Geographic { pair1: ObjectId, pair2: ObjectId, conversion_pair: ObjectId },
+    Exchange { pair1: ObjectId, pair2: ObjectId },
+    Triangular { pair1: ObjectId, pair2: ObjectId, pair3: ObjectId },
+    TradingPair { pair1: ObjectId, pair2: ObjectId, pair3: ObjectId },
+    Statistical { pair: ObjectId, lookback_days: u32, entry_z: f64, exit_z: f64 },
+}
+
+impl LegacyDetails {
+    fn arbitrage_type(&self) -> ArbitrageType {
+        match self {
+            LegacyDetails::Geographic { .. } => ArbitrageType::Geographic,
+            LegacyDetails::Exchange { .. } => ArbitrageType::Exchange,
+            LegacyDetails::Triangular { .. } => ArbitrageType::Triangular,
+            LegacyDetails::TradingPair { .. } => ArbitrageType::TradingPair,
+            LegacyDetails::Statistical { .. } => ArbitrageType::Statistical,
+        }
+    }
+
+    fn pair_ids(&self) -> Vec<ObjectId> {
+        match self {
+            LegacyDetails::Geographic { pair1, pair2, conversion_pair } => vec![*pair1, *pair2, *conversion_pair],
+            LegacyDetails::Exchange { pair1, pair2 } => vec![*pair1, *pair2],
+            LegacyDetails::Triangular { pair1, pair2, pair3 }
+            | LegacyDetails::TradingPair { pair1, pair2, pair3 } => vec![*pair1, *pair2, *pair3],
+            LegacyDetails::Statistical { pair, .. } => vec![*pair],
+        }
+    }
+}
+
+pub struct ArbitrageStrategyMigrationService;
+
+impl ArbitrageStrategyMigrationService {
+    // Pasa a patas las estrategias guardadas con el formato anterior. Las que no se pueden
+    // convertir (pares borrados, ciclos que no cierran) se dejan como están y se avisa.
+    pub async fn migrate_legacy_strategies(storage: &dyn Storage) -> Result<usize, AppError> {
+        let legacy = storage.find_legacy_strategy_documents().await?;
+        if legacy.is_empty() {
+            return Ok(0);
+        }
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+
+        let (total, mut migrated) = (legacy.len(), 0);
+        for document in legacy {
+            let Ok(id) = document.get_object_id("_id") else { continue };
+            let converted = match Self::parse(&document) {
+                Ok(details) => Self::convert_stored(&details, &equivalences, storage).await,
+                Err(e) => Err(e),
+            };
+            let (arbitrage_type, legs, statistical) = match converted {
+                Ok(converted) => converted,
+                Err(e) => {
+                    warn!("Skipping migration of arbitrage strategy {}: {}", id, e);
+                    continue;
+                },
+            };
+
+            storage.set_migrated_legs(id, arbitrage_type, legs, statistical).await?;
+            migrated += 1;
+        }
+
+        info!("Migrated {} arbitrage strategies to legs", migrated);
+        if migrated < total {
+            warn!("{} arbitrage strategies still use the legacy format; they are listed in /integrity/orphans", total - migrated);
+        }
+        Ok(migrated)
+    }
+
+    // El arbitrage_type guardado tiene que coincidir con la variante de details; si no, no se
+    // sabe cuál de los dos es el bueno y la estrategia se deja sin migrar
+    fn parse(document: &Document) -> Result<LegacyDetails, AppError> {
+        let details = document.get("details").cloned().ok_or_else(|| AppError::Validation("Missing details".to_string()))?;
+        let details: LegacyDetails = bson::from_bson(details).map_err(|e| AppError::Validation(format!("Invalid details: {}", e)))?;
+        let stored = document.get("arbitrage_type").cloned().ok_or_else(|| AppError::Validation("Missing arbitrage_type".to_string()))?;
+        let stored: ArbitrageType = bson::from_bson(stored).map_err(|e| AppError::Validation(format!("Invalid arbitrage_type: {}", e)))?;
+        if stored != details.arbitrage_type() {
+            return Err(AppError::Validation(format!("arbitrage_type {:?} does not match {:?} details", stored, details.arbitrage_type())));
+        }
+        Ok(details)
+    }
+
+    async fn convert_stored(
+        details: &LegacyDetails,
+        equivalences: &EquivalenceRegistry,
+        storage: &dyn Storage
+    ) -> Result<(ArbitrageType, Vec<Leg>, Option<StatisticalParams>), AppError> {
+        let pairs = MarketPairService::get_populated_market_pairs(storage, &details.pair_ids()).await?;
+        Self::legs_from_legacy(details, &pairs, equivalences)
+    }
+
+    // Los lados salen de recorrer el ciclo igual que lo hacía la evaluación con el formato anterior
+    fn legs_from_legacy(
+        details: &LegacyDetails,
+        pairs: &[PopulatedMarketPair],
+        equivalences: &EquivalenceRegistry
+    ) -> Result<(ArbitrageType, Vec<Leg>, Option<StatisticalParams>), AppError> {
+        let ordered = details.pair_ids().into_iter()
+            .map(|id| pairs.iter().find(|p| p.id == Some(id)).ok_or_else(|| AppError::NotFound(format!("Market pair {} not found", id))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let arbitrage_type = details.arbitrage_type();
+
+        match details {
+            // El lado de la pata estadística no se usa: la señal compra o vende según el signo del
+            // z-score (ver StatisticalArbitrageService). Se guarda Buy porque el formato anterior no lo tenía.
+            LegacyDetails::Statistical { lookback_days, entry_z, exit_z, .. } => {
+                let pair = ordered[0];
+                let leg = Leg {
+                    market_pair: pair.id.ok_or_else(|| AppError::Internal("Market pair without id".to_string()))?,
+                    side: TradeSide::Buy,
+                    exchange: pair.exchange.id.ok_or_else(|| AppError::Internal("Exchange without id".to_string()))?,
+                };
+                let params = StatisticalParams { lookback_days: *lookback_days, entry_z: *entry_z, exit_z: *exit_z };
+                Ok((arbitrage_type, vec![leg], Some(params)))
+            },
+            _ => {
+                let legs = ArbitrageEvaluationService::plan_strategy_legs(&arbitrage_type, &ordered, equivalences)?;
+                Ok((arbitrage_type, legs, None))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::{exchange, pair};
+    use mongodb::bson::doc;
+
+    #[test]
+    fn legacy_details_are_read_from_stored_documents() {
+        let (pair1, pair2) = (ObjectId::new(), ObjectId::new());
+        let document = doc! { "arbitrage_type": "Exchange", "details": { "Exchange": { "pair1": pair1, "pair2": pair2 } } };
+
+        let details = ArbitrageStrategyMigrationService::parse(&document).unwrap();
+
+        assert_eq!(details.arbitrage_type(), ArbitrageType::Exchange);
+        assert_eq!(details.pair_ids(), vec![pair1, pair2]);
+    }
+
+    #[test]
+    fn legacy_triangular_gets_explicit_sides() {
+        let ex = exchange("X");
+        let pairs = vec![pair(&ex, "ETH", "USDT"), pair(&ex, "ETH", "BTC"), pair(&ex, "BTC", "USDT")];
+        let details = LegacyDetails::Triangular { pair1: pairs[0].id.unwrap(), pair2: pairs[1].id.unwrap(), pair3: pairs[2].id.unwrap() };
+
+        let (arbitrage_type, legs, statistical) = ArbitrageStrategyMigrationService::legs_from_legacy(&details, &pairs, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(arbitrage_type, ArbitrageType::Triangular);
+        assert_eq!(legs.iter().map(|leg| leg.side).collect::<Vec<_>>(), vec![TradeSide::Buy, TradeSide::Sell, TradeSide::Sell]);
+        assert!(legs.iter().all(|leg| leg.exchange == ex.id.unwrap()));
+        assert!(statistical.is_none());
+    }
+
+    #[test]
+    fn legacy_statistical_keeps_its_parameters() {
+        let ex = exchange("X");
+        let pairs = vec![pair(&ex, "ETH", "BTC")];
+        let details = LegacyDetails::Statistical { pair: pairs[0].id.unwrap(), lookback_days: 30, entry_z: 2.0, exit_z: 0.5 };
+
+        let (_, legs, statistical) = ArbitrageStrategyMigrationService::legs_from_legacy(&details, &pairs, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(legs.len(), 1);
+        assert_eq!(statistical, Some(StatisticalParams { lookback_days: 30, entry_z: 2.0, exit_z: 0.5 }));
+    }
+
+    #[test]
+    fn missing_pairs_are_reported() {
+        let details = LegacyDetails::Exchange { pair1: ObjectId::new(), pair2: ObjectId::new() };
+        let err = ArbitrageStrategyMigrationService::legs_from_legacy(&details, &[], &EquivalenceRegistry::default()).unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+    }
+
+    #[test]
+    fn mismatched_arbitrage_type_is_not_migrated() {
+        let document = doc! { "arbitrage_type": "Triangular", "details": { "Exchange": { "pair1": ObjectId::new(), "pair2": ObjectId::new() } } };
+
+        let err = ArbitrageStrategyMigrationService::parse(&document).unwrap_err();
+
+        assert!(matches!(err, AppError::Validation(_)));
+    }
+}
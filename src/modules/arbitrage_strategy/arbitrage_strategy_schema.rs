@@ -1,59 +1,84 @@
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
 use mongodb::bson::oid::ObjectId;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub enum ArbitrageType {
     Geographic,
     Exchange,
     Triangular,
     TradingPair,
+    Statistical,
 }
 
+// Cómo se ejecuta la estrategia cuando se detecta una oportunidad. Disabled solo la evalúa;
+// Paper la "arma" contra el exchange simulado con saldos virtuales.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+pub enum ExecutionMode {
+    #[default]
+    Disabled,
+    Paper,
+}
+
+// Buy: se compra el activo base pagando con el quote al precio ask.
+// Sell: se vende el activo base recibiendo el quote al precio bid.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    pub fn opposite(self) -> Self {
+        match self {
+            TradeSide::Buy => TradeSide::Sell,
+            TradeSide::Sell => TradeSide::Buy,
+        }
+    }
+}
+
+// Una operación del recorrido. El exchange se guarda junto al par para poder filtrar
+// estrategias por exchange sin poblar los pares.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Leg {
+    #[schema(value_type = ObjectIdSchema)]
+    pub market_pair: ObjectId,
+    pub side: TradeSide,
+    #[schema(value_type = ObjectIdSchema)]
+    pub exchange: ObjectId,
+}
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct ArbitrageStrategy {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
+    // Etiqueta de la forma del recorrido; decide qué validaciones se aplican a las patas
     pub arbitrage_type: ArbitrageType,
-    pub details: ArbitrageDetails,
+    // Patas en orden de ejecución. Las estrategias sin migrar del formato antiguo no tienen.
+    #[serde(default)]
+    pub legs: Vec<Leg>,
+    // Solo en las estrategias Statistical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statistical: Option<StatisticalParams>,
     #[serde(default)]
     pub created_at: f64,
     #[serde(default)]
     pub updated_at: f64,
     pub status: bool,
+    #[serde(default)]
+    pub execution_mode: ExecutionMode,
+    // Usuario que creó la estrategia; lo fija el servidor a partir del token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub _owner: Option<ObjectId>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum ArbitrageDetails {
-    Geographic(GeographicArbitrage),
-    Exchange(ExchangeArbitrage),
-    Triangular(TriangularArbitrage),
-    TradingPair(TradingPairArbitrage),
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GeographicArbitrage {
-    pub pair1: ObjectId,
-    pub pair2: ObjectId,
-    pub conversion_pair: ObjectId,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ExchangeArbitrage {
-    pub pair1: ObjectId,
-    pub pair2: ObjectId,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TriangularArbitrage {
-    pub pair1: ObjectId,
-    pub pair2: ObjectId,
-    pub pair3: ObjectId,
+// Reversión a la media del par de la única pata: se entra cuando |z| >= entry_z y se sale cuando |z| <= exit_z
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct StatisticalParams {
+    pub lookback_days: u32,
+    pub entry_z: f64,
+    pub exit_z: f64,
 }
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TradingPairArbitrage {
-    pub pair1: ObjectId,
-    pub pair2: ObjectId,
-    pub pair3: ObjectId,
-}
\ No newline at end of file
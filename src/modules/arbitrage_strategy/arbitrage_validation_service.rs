@@ -0,0 +1,306 @@
+use crate::helpers::app_error::{AppError, FieldError};
+use crate::db::repositories::Storage;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, StatisticalParams, TradeSide};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use crate::modules::market_pair::market_pair_service::{MarketPairService, PopulatedMarketPair};
+
+// Comprobaciones estructurales de una estrategia contra sus pares: que las patas existan, que se
+// encadenen formando un ciclo y que tengan la forma que indica el tipo. Los activos se comparan por
+// símbolo porque cada exchange tiene sus propios documentos de activo, y los símbolos de un mismo
+// grupo de equivalencia (USDT y USDC, por ejemplo) cuentan como el mismo activo, igual que al evaluar.
+pub struct ArbitrageValidationService;
+
+fn leg_field(index: usize, field: Option<&str>) -> String {
+    match field {
+        Some(field) => format!("legs[{}].{}", index, field),
+        None => format!("legs[{}]", index),
+    }
+}
+
+// Activos que entrega y recibe una pata según su lado
+fn leg_assets(pair: &PopulatedMarketPair, side: TradeSide) -> (&str, &str) {
+    match side {
+        TradeSide::Buy => (&pair.quote_asset.short_name, &pair.base_asset.short_name),
+        TradeSide::Sell => (&pair.base_asset.short_name, &pair.quote_asset.short_name),
+    }
+}
+
+fn leg_count_error(arbitrage_type: &ArbitrageType, count: usize) -> Option<String> {
+    let expected = match arbitrage_type {
+        ArbitrageType::Exchange => 2,
+        ArbitrageType::Geographic | ArbitrageType::Triangular => 3,
+        ArbitrageType::Statistical => 1,
+        ArbitrageType::TradingPair if count >= 3 => return None,
+        ArbitrageType::TradingPair => return Some("TradingPair strategies need at least 3 legs".to_string()),
+    };
+    (count != expected).then(|| format!("{:?} strategies need {} leg(s)", arbitrage_type, expected))
+}
+
+fn check_statistical(params: &StatisticalParams) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if params.lookback_days == 0 {
+        errors.push(FieldError::new("statistical.lookback_days", "Must be greater than zero"));
+    }
+    if params.exit_z < 0.0 {
+        errors.push(FieldError::new("statistical.exit_z", "Must not be negative"));
+    } else if params.entry_z <= params.exit_z {
+        errors.push(FieldError::new("statistical.entry_z", "Must be greater than exit_z"));
+    }
+    errors
+}
+
+impl ArbitrageValidationService {
+    pub async fn validate(strategy: &ArbitrageStrategy, storage: &dyn Storage) -> Result<(), AppError> {
+        let ids = ArbitrageStrategyService::leg_pair_ids(&strategy.legs);
+        let pairs = MarketPairService::get_populated_market_pairs(storage, &ids).await?;
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+
+        let errors = Self::check(strategy, &pairs, &equivalences);
+        if !errors.is_empty() {
+            return Err(AppError::invalid_fields(errors));
+        }
+        Ok(())
+    }
+
+    // Errores por campo; `pairs` son los pares poblados que se encontraron
+    pub fn check(strategy: &ArbitrageStrategy, pairs: &[PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        match (&strategy.arbitrage_type, &strategy.statistical) {
+            (ArbitrageType::Statistical, Some(params)) => errors.extend(check_statistical(params)),
+            (ArbitrageType::Statistical, None) => errors.push(FieldError::new("statistical", "Required for Statistical strategies")),
+            (_, Some(_)) => errors.push(FieldError::new("statistical", "Only allowed for Statistical strategies")),
+            (_, None) => {},
+        }
+        if let Some(message) = leg_count_error(&strategy.arbitrage_type, strategy.legs.len()) {
+            errors.push(FieldError::new("legs", &message));
+            return errors;
+        }
+
+        let mut legs = Vec::with_capacity(strategy.legs.len());
+        for (index, leg) in strategy.legs.iter().enumerate() {
+            let Some(pair) = pairs.iter().find(|p| p.id == Some(leg.market_pair)) else {
+                errors.push(FieldError::new(&leg_field(index, Some("market_pair")), "Market pair not found"));
+                continue;
+            };
+            if let Some(previous) = strategy.legs[..index].iter().position(|l| l.market_pair == leg.market_pair) {
+                errors.push(FieldError::new(&leg_field(index, Some("market_pair")), &format!("Same market pair as {}", leg_field(previous, None))));
+            }
+            if pair.exchange.id != Some(leg.exchange) {
+                errors.push(FieldError::new(&leg_field(index, Some("exchange")), &format!("Must be the exchange of the market pair ({})", pair.exchange.short_name)));
+            }
+            legs.push((pair, leg.side));
+        }
+        // Sin todos los pares, o con pares repetidos, la forma de la operación no se puede comprobar
+        if !errors.is_empty() || strategy.arbitrage_type == ArbitrageType::Statistical {
+            return errors;
+        }
+
+        if let Some(error) = Self::check_chain(&legs, equivalences) {
+            errors.push(error);
+            return errors;
+        }
+        let pairs: Vec<&PopulatedMarketPair> = legs.iter().map(|(pair, _)| *pair).collect();
+        let shape_errors = match strategy.arbitrage_type {
+            ArbitrageType::Exchange => Self::check_exchange(&pairs, equivalences),
+            ArbitrageType::Geographic => Self::check_geographic(&pairs, equivalences),
+            ArbitrageType::Triangular => Self::check_single_exchange(&pairs),
+            _ => Vec::new(),
+        };
+        errors.extend(shape_errors.into_iter().map(|(index, message)| FieldError::new(&leg_field(index, None), &message)));
+        errors
+    }
+
+    // Cada pata debe entregar el activo que recibe la anterior y la última debe devolver al activo
+    // con el que empieza la primera. Solo se informa del primer corte.
+    fn check_chain(legs: &[(&PopulatedMarketPair, TradeSide)], equivalences: &EquivalenceRegistry) -> Option<FieldError> {
+        let (start, mut holding) = leg_assets(legs[0].0, legs[0].1);
+
+        for (index, (pair, side)) in legs.iter().enumerate().skip(1) {
+            let (spends, receives) = leg_assets(pair, *side);
+            if !equivalences.same_asset(holding, spends) {
+                return Some(FieldError::new(&leg_field(index, Some("side")), &format!("Spends {} but the previous leg receives {}", spends, holding)));
+            }
+            holding = receives;
+        }
+
+        (!equivalences.same_asset(holding, start)).then(|| FieldError::new(
+            &leg_field(legs.len() - 1, None),
+            &format!("Must close the cycle back to {}, but ends in {}", start, holding),
+        ))
+    }
+
+    // Mismo mercado (base y quote) en dos exchanges distintos
+    fn check_exchange(pairs: &[&PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Vec<(usize, String)> {
+        let (pair1, pair2) = (pairs[0], pairs[1]);
+        let mut errors = Vec::new();
+        if pair1.exchange.id == pair2.exchange.id {
+            errors.push((1, "Must be on a different exchange than legs[0]".to_string()));
+        }
+        if !equivalences.same_asset(&pair1.base_asset.short_name, &pair2.base_asset.short_name)
+            || !equivalences.same_asset(&pair1.quote_asset.short_name, &pair2.quote_asset.short_name) {
+            errors.push((1, format!("Must trade the same market as legs[0] ({}/{})", pair1.base_asset.short_name, pair1.quote_asset.short_name)));
+        }
+        errors
+    }
+
+    // El mismo activo base en dos exchanges y un par de conversión entre sus dos quotes
+    fn check_geographic(pairs: &[&PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Vec<(usize, String)> {
+        let (pair1, pair2, conversion) = (pairs[0], pairs[1], pairs[2]);
+        let mut errors = Vec::new();
+        if pair1.exchange.id == pair2.exchange.id {
+            errors.push((1, "Must be on a different exchange than legs[0]".to_string()));
+        }
+        if !equivalences.same_asset(&pair1.base_asset.short_name, &pair2.base_asset.short_name) {
+            errors.push((1, format!("Must have the same base asset as legs[0] ({})", pair1.base_asset.short_name)));
+        }
+
+        let (quote1, quote2) = (&pair1.quote_asset.short_name, &pair2.quote_asset.short_name);
+        let (base, quote) = (&conversion.base_asset.short_name, &conversion.quote_asset.short_name);
+        let same = |a: &str, b: &str| equivalences.same_asset(a, b);
+        let connects = (same(base, quote1) && same(quote, quote2)) || (same(base, quote2) && same(quote, quote1));
+        if !connects {
+            errors.push((2, format!("Must convert between {} and {}", quote1, quote2)));
+        }
+        errors
+    }
+
+    fn check_single_exchange(pairs: &[&PopulatedMarketPair]) -> Vec<(usize, String)> {
+        let first = pairs[0];
+        pairs.iter().enumerate().skip(1)
+            .filter(|(_, pair)| pair.exchange.id != first.exchange.id)
+            .map(|(index, _)| (index, format!("Must be on the same exchange as legs[0] ({})", first.exchange.short_name)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::{exchange, pair};
+    use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ExecutionMode, Leg};
+    use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::ArbitrageEvaluationService;
+    use crate::modules::arbitrage_strategy::suggested_arbitrage_strategy_service::SuggestedArbitrageStrategyService;
+    use crate::modules::asset_equivalence::asset_equivalence_service::default_groups;
+    use TradeSide::{Buy, Sell};
+
+    fn strategy(arbitrage_type: ArbitrageType, legs: &[(&PopulatedMarketPair, TradeSide)]) -> ArbitrageStrategy {
+        ArbitrageStrategy {
+            id: None,
+            arbitrage_type,
+            legs: legs.iter()
+                .map(|(pair, side)| Leg { market_pair: pair.id.unwrap(), side: *side, exchange: pair.exchange.id.unwrap() })
+                .collect(),
+            statistical: None,
+            created_at: 0.0,
+            updated_at: 0.0,
+            status: true,
+            execution_mode: ExecutionMode::Disabled,
+            _owner: None,
+        }
+    }
+
+    #[test]
+    fn triangular_must_close_on_one_exchange() {
+        let binance = exchange("binance");
+        let eth_usdt = pair(&binance, "ETH", "USDT");
+        let eth_btc = pair(&binance, "ETH", "BTC");
+        let btc_usdt = pair(&binance, "BTC", "USDT");
+        let ltc_usdt = pair(&binance, "LTC", "USDT");
+        let kraken_btc_usdt = pair(&exchange("kraken"), "BTC", "USDT");
+        let pairs = [eth_usdt.clone(), eth_btc.clone(), btc_usdt.clone(), ltc_usdt.clone(), kraken_btc_usdt.clone()];
+        let triangular = |legs: &[(&PopulatedMarketPair, TradeSide)]| strategy(ArbitrageType::Triangular, legs);
+
+        assert!(ArbitrageValidationService::check(&triangular(&[(&eth_usdt, Buy), (&eth_btc, Sell), (&btc_usdt, Sell)]), &pairs, &EquivalenceRegistry::default()).is_empty());
+
+        let errors = ArbitrageValidationService::check(&triangular(&[(&eth_usdt, Buy), (&eth_btc, Sell), (&ltc_usdt, Sell)]), &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs[2].side", "Spends LTC but the previous leg receives BTC")]);
+
+        let errors = ArbitrageValidationService::check(&triangular(&[(&eth_usdt, Buy), (&eth_btc, Sell), (&kraken_btc_usdt, Sell)]), &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs[2]", "Must be on the same exchange as legs[0] (binance)")]);
+
+        let errors = ArbitrageValidationService::check(&triangular(&[(&eth_usdt, Buy), (&eth_btc, Sell), (&eth_usdt, Sell)]), &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs[2].market_pair", "Same market pair as legs[0]")]);
+
+        let mut wrong_exchange = triangular(&[(&eth_usdt, Buy), (&eth_btc, Sell), (&btc_usdt, Sell)]);
+        wrong_exchange.legs[1].exchange = kraken_btc_usdt.exchange.id.unwrap();
+        let errors = ArbitrageValidationService::check(&wrong_exchange, &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs[1].exchange", "Must be the exchange of the market pair (binance)")]);
+    }
+
+    #[test]
+    fn sides_must_chain_and_close_the_cycle() {
+        let ex = exchange("X");
+        let ltc_eth = pair(&ex, "LTC", "ETH");
+        let eth_btc = pair(&ex, "ETH", "BTC");
+        let ltc_btc = pair(&ex, "LTC", "BTC");
+        let pairs = [ltc_eth.clone(), eth_btc.clone(), ltc_btc.clone()];
+
+        let valid = strategy(ArbitrageType::TradingPair, &[(&ltc_eth, Sell), (&eth_btc, Sell), (&ltc_btc, Buy)]);
+        assert!(ArbitrageValidationService::check(&valid, &pairs, &EquivalenceRegistry::default()).is_empty());
+
+        let open = strategy(ArbitrageType::TradingPair, &[(&ltc_eth, Sell), (&eth_btc, Sell), (&ltc_btc, Sell)]);
+        let errors = ArbitrageValidationService::check(&open, &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs[2].side", "Spends LTC but the previous leg receives BTC")]);
+
+        let short = strategy(ArbitrageType::TradingPair, &[(&ltc_eth, Sell), (&eth_btc, Sell)]);
+        let errors = ArbitrageValidationService::check(&short, &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs", "TradingPair strategies need at least 3 legs")]);
+    }
+
+    #[test]
+    fn suggested_cycle_may_close_on_an_equivalent_stablecoin() {
+        let binance = exchange("binance");
+        let eth_usdt = pair(&binance, "ETH", "USDT");
+        let eth_btc = pair(&binance, "ETH", "BTC");
+        let btc_usdc = pair(&binance, "BTC", "USDC");
+        let pairs = [eth_usdt.clone(), eth_btc.clone(), btc_usdc.clone()];
+        let equivalences = EquivalenceRegistry::new(default_groups());
+
+        // USDT -> ETH -> BTC -> USDC, tal como lo propone el servicio de sugerencias
+        let legs = ArbitrageEvaluationService::plan_strategy_legs(&ArbitrageType::Triangular, &[&eth_usdt, &eth_btc, &btc_usdc], &equivalences).unwrap();
+        let suggested = SuggestedArbitrageStrategyService::suggestion(ArbitrageType::Triangular, legs);
+        assert_eq!(suggested.legs.iter().map(|leg| leg.side).collect::<Vec<_>>(), vec![Buy, Sell, Sell]);
+        assert!(ArbitrageValidationService::check(&suggested, &pairs, &equivalences).is_empty());
+
+        // Sin el grupo USD el ciclo no cierra
+        let errors = ArbitrageValidationService::check(&suggested, &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs[2]", "Must close the cycle back to USDT, but ends in USDC")]);
+    }
+
+    #[test]
+    fn exchange_and_geographic_shapes() {
+        let binance = exchange("binance");
+        let kraken = exchange("kraken");
+        let binance_btc_usdt = pair(&binance, "BTC", "USDT");
+        let kraken_btc_usdt = pair(&kraken, "BTC", "USDT");
+        let kraken_btc_eur = pair(&kraken, "BTC", "EUR");
+        let eur_usdt = pair(&kraken, "EUR", "USDT");
+        let usdt_eur = pair(&kraken, "USDT", "EUR");
+        let pairs = [binance_btc_usdt.clone(), kraken_btc_usdt.clone(), kraken_btc_eur.clone(), eur_usdt.clone(), usdt_eur.clone()];
+
+        let exchange_strategy = strategy(ArbitrageType::Exchange, &[(&binance_btc_usdt, Buy), (&kraken_btc_usdt, Sell)]);
+        assert!(ArbitrageValidationService::check(&exchange_strategy, &pairs, &EquivalenceRegistry::default()).is_empty());
+
+        let geographic = strategy(ArbitrageType::Geographic, &[(&binance_btc_usdt, Buy), (&kraken_btc_eur, Sell), (&eur_usdt, Sell)]);
+        assert!(ArbitrageValidationService::check(&geographic, &pairs, &EquivalenceRegistry::default()).is_empty());
+        let geographic = strategy(ArbitrageType::Geographic, &[(&binance_btc_usdt, Buy), (&kraken_btc_eur, Sell), (&usdt_eur, Buy)]);
+        assert!(ArbitrageValidationService::check(&geographic, &pairs, &EquivalenceRegistry::default()).is_empty());
+
+        let mut statistical = strategy(ArbitrageType::Statistical, &[(&binance_btc_usdt, Buy)]);
+        assert_eq!(
+            ArbitrageValidationService::check(&statistical, &pairs, &EquivalenceRegistry::default()),
+            vec![FieldError::new("statistical", "Required for Statistical strategies")]
+        );
+        statistical.statistical = Some(StatisticalParams { lookback_days: 30, entry_z: 0.5, exit_z: 2.0 });
+        assert_eq!(
+            ArbitrageValidationService::check(&statistical, &pairs, &EquivalenceRegistry::default()),
+            vec![FieldError::new("statistical.entry_z", "Must be greater than exit_z")]
+        );
+
+        let mismatched = ArbitrageStrategy { arbitrage_type: ArbitrageType::Triangular, ..exchange_strategy };
+        let errors = ArbitrageValidationService::check(&mismatched, &pairs, &EquivalenceRegistry::default());
+        assert_eq!(errors, vec![FieldError::new("legs", "Triangular strategies need 3 leg(s)")]);
+    }
+}
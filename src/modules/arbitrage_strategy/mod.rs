@@ -1,17 +1,28 @@
 pub mod arbitrage_strategy_schema;
 pub mod arbitrage_strategy_service;
 pub mod arbitrage_strategy_controller;
+pub mod arbitrage_evaluation_service;
+pub mod arbitrage_validation_service;
+pub mod arbitrage_strategy_migration_service;
+pub mod statistical_arbitrage_service;
+pub mod arbitrage_depth_service;
 pub mod suggested_arbitrage_strategy_service;
 pub mod suggested_arbitrage_strategy_controller;
+pub mod arbitrage_cycle_service;
+pub mod arbitrage_cycle_controller;
 
 use actix_web::web;
 
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(suggested_arbitrage_strategy_controller::get_suggested_strategies);
+    cfg.service(arbitrage_cycle_controller::detect_arbitrage_cycles);
     cfg.service(arbitrage_strategy_controller::create_arbitrage_strategy);
     cfg.service(arbitrage_strategy_controller::get_arbitrage_strategy);
     cfg.service(arbitrage_strategy_controller::update_arbitrage_strategy);
     cfg.service(arbitrage_strategy_controller::delete_arbitrage_strategy);
     cfg.service(arbitrage_strategy_controller::get_all_arbitrage_strategies);
+    cfg.service(arbitrage_strategy_controller::evaluate_arbitrage_strategy);
+    cfg.service(arbitrage_strategy_controller::get_arbitrage_strategy_z_score);
+    cfg.service(arbitrage_strategy_controller::get_arbitrage_strategy_depth);
     
 }
\ No newline at end of file
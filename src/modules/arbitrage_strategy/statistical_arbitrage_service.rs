@@ -0,0 +1,144 @@
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageType, TradeSide};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::market_data::market_data_schema::MarketQuote;
+use crate::modules::market_data::market_data_service::MarketDataService;
+use crate::modules::market_data::exchange_connector::market_symbol;
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum StatisticalSignal {
+    Entry,
+    Exit,
+    Neutral,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ZScoreResult {
+    #[schema(value_type = ObjectIdSchema)]
+    pub strategy_id: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub market_pair: ObjectId,
+    pub symbol: String,
+    pub lookback_days: u32,
+    pub samples: usize,
+    pub current: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub z_score: f64,
+    pub entry_z: f64,
+    pub exit_z: f64,
+    pub signal: StatisticalSignal,
+    // Lado sobre el activo base cuando hay señal de entrada: por debajo de la media se compra
+    pub side: Option<TradeSide>,
+}
+
+pub fn mid_price(quote: &MarketQuote) -> f64 {
+    (quote.bid + quote.ask) / 2.0
+}
+
+// Media y desviación estándar poblacional
+pub fn mean_std_dev(values: &[f64]) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    Some((mean, variance.sqrt()))
+}
+
+pub fn z_score(current: f64, mean: f64, std_dev: f64) -> f64 {
+    (current - mean) / std_dev
+}
+
+pub fn signal_for(z_score: f64, entry_z: f64, exit_z: f64) -> StatisticalSignal {
+    if z_score.abs() >= entry_z {
+        StatisticalSignal::Entry
+    } else if z_score.abs() <= exit_z {
+        StatisticalSignal::Exit
+    } else {
+        StatisticalSignal::Neutral
+    }
+}
+
+pub struct StatisticalArbitrageService;
+
+impl StatisticalArbitrageService {
+    pub async fn get_z_score(id: ObjectId, storage: &dyn Storage) -> Result<ZScoreResult, AppError> {
+        let strategy = ArbitrageStrategyService::get_arbitrage_strategy(id, storage).await?;
+        if strategy.arbitrage_type != ArbitrageType::Statistical {
+            return Err(AppError::Validation("Arbitrage strategy is not Statistical".to_string()));
+        }
+        let params = strategy.statistical.clone().ok_or_else(|| AppError::Internal("Statistical strategy without parameters".to_string()))?;
+        let (lookback_days, entry_z, exit_z) = (params.lookback_days, params.entry_z, params.exit_z);
+
+        let legs = ArbitrageStrategyService::populate_legs(&strategy.legs, storage).await?;
+        let pair = &legs[0].market_pair;
+        let pair_id = pair.id.ok_or_else(|| AppError::Internal("Market pair without id".to_string()))?;
+
+        let since = Utc::now().timestamp() as f64 - f64::from(lookback_days) * 86_400.0;
+        let history = MarketDataService::get_quote_history(pair_id, since, storage).await?;
+        let prices: Vec<f64> = history.iter().map(mid_price).collect();
+        if prices.len() < 2 {
+            return Err(AppError::Validation(format!("Not enough price history for the last {} days", lookback_days)));
+        }
+
+        let (mean, std_dev) = mean_std_dev(&prices).ok_or_else(|| AppError::Validation("Empty price history".to_string()))?;
+        if std_dev == 0.0 {
+            return Err(AppError::Validation("Price history has zero standard deviation".to_string()));
+        }
+
+        let current = mid_price(&MarketDataService::get_quote(pair_id, storage).await?);
+        let z = z_score(current, mean, std_dev);
+        let signal = signal_for(z, entry_z, exit_z);
+        let side = match signal {
+            StatisticalSignal::Entry if z < 0.0 => Some(TradeSide::Buy),
+            StatisticalSignal::Entry => Some(TradeSide::Sell),
+            _ => None,
+        };
+
+        Ok(ZScoreResult {
+            strategy_id: id,
+            market_pair: pair_id,
+            symbol: market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name),
+            lookback_days,
+            samples: prices.len(),
+            current,
+            mean,
+            std_dev,
+            z_score: z,
+            entry_z,
+            exit_z,
+            signal,
+            side,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readme_z_score_is_near_entry() {
+        // ETH/BTC: media 0.06, desviación 0.002, valor actual 0.0565 -> z = -1.75
+        let z = z_score(0.0565, 0.06, 0.002);
+        assert!((z - -1.75).abs() < 1e-9);
+        assert_eq!(signal_for(z, 2.0, 0.5), StatisticalSignal::Neutral);
+        assert_eq!(signal_for(-2.1, 2.0, 0.5), StatisticalSignal::Entry);
+        assert_eq!(signal_for(0.3, 2.0, 0.5), StatisticalSignal::Exit);
+    }
+
+    #[test]
+    fn population_std_dev() {
+        let (mean, std_dev) = mean_std_dev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).unwrap();
+        assert_eq!(mean, 5.0);
+        assert_eq!(std_dev, 2.0);
+    }
+}
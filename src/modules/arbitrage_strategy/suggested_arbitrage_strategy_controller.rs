@@ -1,44 +1,45 @@
-use actix_web::{get, web, HttpResponse, Responder};
-use serde::{Deserialize, Serialize};
+use actix_web::{get, web, HttpResponse};
+use serde::Deserialize;
+use utoipa::IntoParams;
 use crate::modules::auth::auth_response::ApiResponse;
-use crate::db::mongodb::MongoDbContext;
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::{parse_object_id, AppError};
 use crate::modules::arbitrage_strategy::suggested_arbitrage_strategy_service::SuggestedArbitrageStrategyService;
 use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType};
-use mongodb::bson::oid::ObjectId;  // Añadimos esta importación
+use crate::openapi::ErrorResponse;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 struct SuggestedStrategyQuery {
     exchange1: String,
     exchange2: String,
     strategy_type: ArbitrageType,
+    min_profit: Option<f64>, // Porcentaje neto de comisiones
+    amount: Option<f64>,
 }
 
-#[derive(Serialize)]
-struct SuggestedStrategyResponse {
-    strategies: Vec<ArbitrageStrategy>,
-}
-
+#[utoipa::path(
+    tag = "arbitrage_strategies",
+    params(SuggestedStrategyQuery),
+    responses(
+        (status = 200, description = "Profitable strategies between both exchanges (not paginated)", body = ApiResponse<Vec<ArbitrageStrategy>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+    ),
+)]
 #[get("/arbitrage-strategies/suggested")]
 pub async fn get_suggested_strategies(
-    db_context: web::Data<MongoDbContext>,
+    storage: web::Data<dyn Storage>,
     query: web::Query<SuggestedStrategyQuery>,
-) -> impl Responder {
-    let exchange1 = match ObjectId::parse_str(&query.exchange1) {
-        Ok(id) => id,
-        Err(_) => return HttpResponse::BadRequest().json(ApiResponse::<String>::error("Invalid exchange1 ID")),
-    };
-    let exchange2 = match ObjectId::parse_str(&query.exchange2) {
-        Ok(id) => id,
-        Err(_) => return HttpResponse::BadRequest().json(ApiResponse::<String>::error("Invalid exchange2 ID")),
-    };
+) -> Result<HttpResponse, AppError> {
+    let exchange1 = parse_object_id(&query.exchange1, "exchange1")?;
+    let exchange2 = parse_object_id(&query.exchange2, "exchange2")?;
 
-    match SuggestedArbitrageStrategyService::get_suggested_strategies(
-        &db_context,
+    let strategies = SuggestedArbitrageStrategyService::get_suggested_strategies(
+        storage.get_ref(),
         exchange1,
         exchange2,
         query.strategy_type.clone(),
-    ).await {
-        Ok(strategies) => HttpResponse::Ok().json(ApiResponse::success("Suggested strategies retrieved successfully", SuggestedStrategyResponse { strategies })),
-        Err(err) => HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err)),
-    }
-}
\ No newline at end of file
+        query.min_profit,
+        query.amount.unwrap_or(1.0),
+    ).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Suggested strategies retrieved successfully", strategies)))
+}
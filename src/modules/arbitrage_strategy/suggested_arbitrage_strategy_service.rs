@@ -1,34 +1,77 @@
-use crate::db::mongodb::MongoDbContext;
-use mongodb::bson::{doc, oid::ObjectId, Document};
-use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, ArbitrageDetails, GeographicArbitrage};
-use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
-use futures::stream::TryStreamExt;
-use mongodb::Collection;
-use mongodb::bson;
-use tracing::info;
+use crate::db::repositories::{MarketPairFilter, MarketPairRepository, Storage};
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, ExecutionMode, Leg};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::{ArbitrageEvaluationService, RouteSelection};
+use crate::modules::market_data::market_data_service::MarketDataService;
+use crate::modules::market_pair::market_pair_service::{MarketPairService, PopulatedMarketPair};
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
 pub struct SuggestedArbitrageStrategyService;
 
 impl SuggestedArbitrageStrategyService {
+    // Con min_profit solo se devuelven las sugerencias cuyo beneficio neto de comisiones,
+    // evaluado con las últimas cotizaciones y el importe indicado, alcanza ese mínimo
     pub async fn get_suggested_strategies(
-        db_context: &MongoDbContext,
+        storage: &dyn Storage,
         exchange1: ObjectId,
         exchange2: ObjectId,
         strategy_type: ArbitrageType,
-    ) -> Result<Vec<ArbitrageStrategy>, String> {
-        let db = db_context.get_database();
-        let market_pairs_collection = db.collection::<Document>("marketpairs");
-        let assets_collection = db.collection::<Document>("assets");
+        min_profit: Option<f64>,
+        amount: f64,
+    ) -> Result<Vec<ArbitrageStrategy>, AppError> {
+        let suggested_strategies = Self::find_suggested_strategies(storage, exchange1, exchange2, strategy_type).await?;
+        match min_profit {
+            Some(min_profit) => Self::filter_by_net_profit(suggested_strategies, min_profit, amount, storage).await,
+            None => Ok(suggested_strategies),
+        }
+    }
+
+    async fn filter_by_net_profit(
+        strategies: Vec<ArbitrageStrategy>,
+        min_profit: f64,
+        amount: f64,
+        storage: &dyn Storage
+    ) -> Result<Vec<ArbitrageStrategy>, AppError> {
+        let mut pair_ids: Vec<ObjectId> = strategies.iter()
+            .flat_map(|s| ArbitrageStrategyService::leg_pair_ids(&s.legs))
+            .collect();
+        pair_ids.sort();
+        pair_ids.dedup();
+
+        let pairs = MarketPairService::get_populated_market_pairs(storage, &pair_ids).await?;
+        let quotes = MarketDataService::get_quotes(&pair_ids, storage).await?;
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+
+        let total = strategies.len();
+        let profitable: Vec<ArbitrageStrategy> = strategies.into_iter()
+            .filter(|strategy| {
+                ArbitrageStrategyService::assemble_populated_legs(&strategy.legs, &pairs)
+                    .and_then(|legs| ArbitrageEvaluationService::evaluate(&strategy.arbitrage_type, &legs, amount, RouteSelection::Best, &quotes, &equivalences).ok())
+                    .is_some_and(|result| result.profit_percentage >= min_profit)
+            })
+            .collect();
+
+        info!("{} of {} suggested strategies reach {}% net profit", profitable.len(), total, min_profit);
+        Ok(profitable)
+    }
+
+    async fn find_suggested_strategies(
+        storage: &dyn Storage,
+        exchange1: ObjectId,
+        exchange2: ObjectId,
+        strategy_type: ArbitrageType,
+    ) -> Result<Vec<ArbitrageStrategy>, AppError> {
+        // Stablecoins y sus equivalencias registradas
+        let equivalences = EquivalenceRegistry::load(storage).await?;
 
         match strategy_type {
             ArbitrageType::Geographic => {
-                // Definir stablecoins y sus equivalencias
-                let stablecoins = doc! {
-                    "USD": ["USDT", "USDC", "BUSD", "DAI", "TUSD", "USDP", "GUSD", "FDUSD"],
-                    "EUR": ["EURS", "EURT", "SEUR", "CEUR", "EURE", "JEUR"]
-                };
-
                 // Obtener pares de mercado para exchange1
-                let exchange1_pairs = Self::get_exchange_pairs(&market_pairs_collection, exchange1).await?;
+                let exchange1_pairs = Self::get_exchange_pairs(storage, exchange1).await?;
+                let exchange2_pairs = Self::get_exchange_pairs(storage, exchange2).await?;
                 info!("Found {} pairs in exchange1", exchange1_pairs.len());
 
                 let mut suggested_strategies = Vec::new();
@@ -39,33 +82,24 @@ impl SuggestedArbitrageStrategyService {
                           pair1.base_asset.short_name, pair1.quote_asset.short_name);
 
                     // Buscar par correspondiente en exchange2
-                    let pair2 = Self::find_corresponding_pair(&market_pairs_collection, &assets_collection, pair1, exchange2, &stablecoins).await?;
+                    let pair2 = Self::find_corresponding_pair(pair1, &exchange2_pairs, &equivalences);
 
                     if let Some(pair2) = pair2 {
                         info!("Found corresponding pair in exchange2: {}/{}", 
                               pair2.base_asset.short_name, pair2.quote_asset.short_name);
 
                         // Buscar par de conversión
-                        let conversion_pair = Self::find_conversion_pair(&market_pairs_collection, &assets_collection, pair1, &pair2, &stablecoins).await?;
+                        let conversion_pair = Self::find_conversion_pair(storage, pair1, pair2, &equivalences).await?;
 
                         if let Some(conversion_pair) = conversion_pair {
                             info!("Found conversion pair: {}/{}", 
                                   conversion_pair.base_asset.short_name, conversion_pair.quote_asset.short_name);
 
-                            let strategy = ArbitrageStrategy {
-                                id: None,
-                                arbitrage_type: ArbitrageType::Geographic,
-                                details: ArbitrageDetails::Geographic(GeographicArbitrage {
-                                    pair1: pair1.id.unwrap(),
-                                    pair2: pair2.id.unwrap(),
-                                    conversion_pair: conversion_pair.id.unwrap(),
-                                }),
-                                created_at: 0.0,
-                                updated_at: 0.0,
-                                status: true,
-                            };
-                            suggested_strategies.push(strategy);
-                            info!("Added new strategy to suggestions");
+                            let planned = Self::planned(ArbitrageType::Geographic, &[pair1, pair2, &conversion_pair], &equivalences);
+                            if let Some(strategy) = planned {
+                                suggested_strategies.push(strategy);
+                                info!("Added new strategy to suggestions");
+                            }
                         } else {
                             info!("No suitable conversion pair found");
                         }
@@ -77,310 +111,274 @@ impl SuggestedArbitrageStrategyService {
                 info!("Total suggested strategies: {}", suggested_strategies.len());
                 Ok(suggested_strategies)
             },
-            _ => Err("Strategy type not implemented".to_string()),
-        }
-    }
+            ArbitrageType::Exchange => {
+                // Mismo base/quote (o variante de stablecoin) listado en ambos exchanges
+                let exchange1_pairs = Self::get_exchange_pairs(storage, exchange1).await?;
+                let exchange2_pairs = Self::get_exchange_pairs(storage, exchange2).await?;
+                info!("Found {} pairs in exchange1", exchange1_pairs.len());
 
-    async fn find_corresponding_pair(
-        market_pairs_collection: &Collection<Document>,
-        assets_collection: &Collection<Document>,
-        pair1: &PopulatedMarketPair,
-        exchange2: ObjectId,
-        stablecoins: &Document
-    ) -> Result<Option<PopulatedMarketPair>, String> {
-        info!("Searching for corresponding pair in exchange {} for {}/{}", 
-              exchange2, pair1.base_asset.short_name, pair1.quote_asset.short_name);
-    
-        let get_asset_variants = |asset: &str| -> Vec<String> {
-            let mut variants = vec![asset.to_string()];
-            for (fiat, coins) in stablecoins.iter() {
-                if let bson::Bson::Array(coin_array) = coins {
-                    if coin_array.iter().any(|c| c.as_str().unwrap() == asset) {
-                        variants.push(fiat.to_string());
-                        variants.extend(coin_array.iter().map(|c| c.as_str().unwrap().to_string()));
+                let mut suggested_strategies = Vec::new();
+                for pair1 in exchange1_pairs.iter() {
+                    if let Some(pair2) = Self::find_corresponding_pair(pair1, &exchange2_pairs, &equivalences) {
+                        suggested_strategies.extend(Self::planned(ArbitrageType::Exchange, &[pair1, pair2], &equivalences));
                     }
                 }
-            }
-            variants.sort();
-            variants.dedup();
-            variants
-        };
-    
-        let quote_variants = get_asset_variants(&pair1.quote_asset.short_name);
-    
-        let pipeline = vec![
-            doc! {
-                "$match": {
-                    "_exchange": exchange2
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_base_asset",
-                    "foreignField": "_id",
-                    "as": "base_asset"
-                }
-            },
-            doc! { "$unwind": "$base_asset" },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_quote_asset",
-                    "foreignField": "_id",
-                    "as": "quote_asset"
-                }
+
+                info!("Total suggested strategies: {}", suggested_strategies.len());
+                Ok(suggested_strategies)
             },
-            doc! { "$unwind": "$quote_asset" },
-            doc! {
-                "$match": {
-                    "base_asset.short_name": pair1.base_asset.short_name.clone(),
-                    "quote_asset.short_name": { "$in": quote_variants }
+            ArbitrageType::Triangular => {
+                let mut suggested_strategies = Vec::new();
+                for exchange_id in Self::distinct_exchanges(exchange1, exchange2) {
+                    let pairs = Self::get_exchange_pairs(storage, exchange_id).await?;
+                    let cycles = Self::find_triangular_cycles(&pairs, &equivalences);
+                    info!("Found {} triangular cycles in exchange {}", cycles.len(), exchange_id);
+
+                    suggested_strategies.extend(cycles.iter().filter_map(|pairs| Self::planned(ArbitrageType::Triangular, pairs, &equivalences)));
                 }
+
+                info!("Total suggested strategies: {}", suggested_strategies.len());
+                Ok(suggested_strategies)
             },
-            doc! {
-                "$lookup": {
-                    "from": "exchanges",
-                    "localField": "_exchange",
-                    "foreignField": "_id",
-                    "as": "exchange"
+            ArbitrageType::TradingPair => {
+                let mut suggested_strategies = Vec::new();
+                for exchange_id in Self::distinct_exchanges(exchange1, exchange2) {
+                    let pairs = Self::get_exchange_pairs(storage, exchange_id).await?;
+                    let combinations = Self::find_trading_pair_combinations(&pairs, &equivalences);
+                    info!("Found {} trading pair combinations in exchange {}", combinations.len(), exchange_id);
+
+                    suggested_strategies.extend(combinations.iter().filter_map(|pairs| Self::planned(ArbitrageType::TradingPair, pairs, &equivalences)));
                 }
+
+                info!("Total suggested strategies: {}", suggested_strategies.len());
+                Ok(suggested_strategies)
             },
-            doc! { "$unwind": "$exchange" },
-            doc! { "$limit": 1 }
-        ];
-    
-        let mut cursor = market_pairs_collection.aggregate(pipeline).await
-            .map_err(|e| format!("Failed to execute aggregation: {}", e))?;
-    
-        if let Some(doc) = cursor.try_next().await
-            .map_err(|e| format!("Error iterating cursor: {}", e))? {
-            let corresponding_pair: PopulatedMarketPair = bson::from_document(doc)
-                .map_err(|e| format!("Failed to deserialize market pair: {}", e))?;
-            info!("Found corresponding pair: {}/{}", 
-                  corresponding_pair.base_asset.short_name, corresponding_pair.quote_asset.short_name);
-            Ok(Some(corresponding_pair))
-        } else {
-            info!("No corresponding pair found");
-            Ok(None)
+            _ => Err(AppError::Validation("Strategy type not implemented".to_string())),
         }
     }
 
-    async fn get_asset_ids(assets_collection: &Collection<Document>, asset_names: &[String]) -> Result<Vec<ObjectId>, String> {
-        let pipeline = vec![
-            doc! {
-                "$match": {
-                    "short_name": { "$in": asset_names }
-                }
+    // Sugerencia con los lados de cada pata ya decididos; se descarta si los pares no forman un ciclo
+    fn planned(arbitrage_type: ArbitrageType, pairs: &[&PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Option<ArbitrageStrategy> {
+        match ArbitrageEvaluationService::plan_strategy_legs(&arbitrage_type, pairs, equivalences) {
+            Ok(legs) => Some(Self::suggestion(arbitrage_type, legs)),
+            Err(e) => {
+                warn!("Skipping suggested {:?} strategy: {}", arbitrage_type, e);
+                None
             },
-            doc! {
-                "$project": {
-                    "_id": 1
-                }
-            }
-        ];
+        }
+    }
+
+    pub fn suggestion(arbitrage_type: ArbitrageType, legs: Vec<Leg>) -> ArbitrageStrategy {
+        ArbitrageStrategy {
+            id: None,
+            arbitrage_type,
+            legs,
+            statistical: None,
+            created_at: 0.0,
+            updated_at: 0.0,
+            status: true,
+            execution_mode: ExecutionMode::Disabled,
+            _owner: None,
+        }
+    }
 
-        let mut cursor = assets_collection.aggregate(pipeline).await
-            .map_err(|e| format!("Failed to execute aggregation: {}", e))?;
+    fn distinct_exchanges(exchange1: ObjectId, exchange2: ObjectId) -> Vec<ObjectId> {
+        if exchange1 == exchange2 {
+            vec![exchange1]
+        } else {
+            vec![exchange1, exchange2]
+        }
+    }
 
-        let mut asset_ids = Vec::new();
-        while let Some(doc) = cursor.try_next().await
-            .map_err(|e| format!("Error iterating cursor: {}", e))? {
-            if let Some(id) = doc.get_object_id("_id").ok() {
-                asset_ids.push(id);
+    // Ciclos cerrados A -> B -> C -> A dentro de un exchange. pair1 y pair3 comparten el activo A,
+    // que es el quote de pair1.
+    pub fn find_triangular_cycles<'a>(pairs: &'a [PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Vec<[&'a PopulatedMarketPair; 3]> {
+        let nodes: Vec<(String, String)> = pairs.iter()
+            .map(|p| (
+                equivalences.canonical(&p.base_asset.short_name),
+                equivalences.canonical(&p.quote_asset.short_name),
+            ))
+            .collect();
+
+        let mut adjacency: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, (base, quote)) in nodes.iter().enumerate() {
+            if base == quote {
+                continue;
             }
+            adjacency.entry(base.as_str()).or_default().push(index);
+            adjacency.entry(quote.as_str()).or_default().push(index);
         }
 
-        Ok(asset_ids)
-    }
-    
-
-    
-        async fn find_conversion_pair(
-            market_pairs_collection: &Collection<Document>,
-            assets_collection: &Collection<Document>,
-            pair1: &PopulatedMarketPair,
-            pair2: &PopulatedMarketPair,
-            stablecoins: &Document
-        ) -> Result<Option<PopulatedMarketPair>, String> {
-            info!("Searching for conversion pair between {}/{} and {}/{}", 
-                  pair1.quote_asset.short_name, pair2.quote_asset.short_name,
-                  pair1.base_asset.short_name, pair2.base_asset.short_name);
-    
-            let get_asset_variants = |asset: &str| -> Vec<String> {
-                let mut variants = vec![asset.to_string()];
-                for (fiat, coins) in stablecoins.iter() {
-                    if let bson::Bson::Array(coin_array) = coins {
-                        if coin_array.iter().any(|c| c.as_str().unwrap() == asset) {
-                            variants.push(fiat.to_string());
-                            variants.extend(coin_array.iter().map(|c| c.as_str().unwrap().to_string()));
-                        }
-                    }
+        let other = |index: usize, node: &str| -> &str {
+            let (base, quote) = &nodes[index];
+            if base == node { quote.as_str() } else { base.as_str() }
+        };
+
+        let mut seen = HashSet::new();
+        let mut cycles = Vec::new();
+        for (i, (b, a)) in nodes.iter().enumerate() {
+            if a == b {
+                continue;
+            }
+            for &j in adjacency.get(b.as_str()).into_iter().flatten() {
+                let c = other(j, b);
+                if j == i || c == a {
+                    continue;
                 }
-                variants.sort();
-                variants.dedup();
-                variants
-            };
-    
-            let quote1_variants = get_asset_variants(&pair1.quote_asset.short_name);
-            let quote2_variants = get_asset_variants(&pair2.quote_asset.short_name);
-    
-            let quote1_ids = Self::get_asset_ids(assets_collection, &quote1_variants).await?;
-            let quote2_ids = Self::get_asset_ids(assets_collection, &quote2_variants).await?;
-    
-            let pipeline = vec![
-                doc! {
-                    "$match": {
-                        "$or": [
-                            {
-                                "_base_asset": { "$in": &quote1_ids },
-                                "_quote_asset": { "$in": &quote2_ids }
-                            },
-                            {
-                                "_base_asset": { "$in": &quote2_ids },
-                                "_quote_asset": { "$in": &quote1_ids }
-                            }
-                        ]
-                    }
-                },
-                doc! {
-                    "$lookup": {
-                        "from": "exchanges",
-                        "localField": "_exchange",
-                        "foreignField": "_id",
-                        "as": "exchange"
-                    }
-                },
-                doc! { "$unwind": "$exchange" },
-                doc! {
-                    "$lookup": {
-                        "from": "assets",
-                        "localField": "_base_asset",
-                        "foreignField": "_id",
-                        "as": "base_asset"
+                for &k in adjacency.get(c).into_iter().flatten() {
+                    if k == i || k == j || other(k, c) != a {
+                        continue;
                     }
-                },
-                doc! { "$unwind": "$base_asset" },
-                doc! {
-                    "$lookup": {
-                        "from": "assets",
-                        "localField": "_quote_asset",
-                        "foreignField": "_id",
-                        "as": "quote_asset"
+                    let mut key = [i, j, k];
+                    key.sort();
+                    if seen.insert(key) {
+                        cycles.push([&pairs[i], &pairs[j], &pairs[k]]);
                     }
-                },
-                doc! { "$unwind": "$quote_asset" },
-                doc! {
-                    "$addFields": {
-                        "priority": {
-                            "$switch": {
-                                "branches": [
-                                    { "case": { "$in": ["$base_asset.short_name", ["USD", "EUR"]] }, "then": 1 },
-                                    { "case": { "$in": ["$quote_asset.short_name", ["USD", "EUR"]] }, "then": 1 },
-                                    { "case": { "$in": ["$base_asset.short_name", ["USDT", "USDC", "BUSD", "DAI", "TUSD", "USDP", "GUSD", "FDUSD", "EURS", "EURT"]] }, "then": 2 },
-                                    { "case": { "$in": ["$quote_asset.short_name", ["USDT", "USDC", "BUSD", "DAI", "TUSD", "USDP", "GUSD", "FDUSD", "EURS", "EURT"]] }, "then": 2 }
-                                ],
-                                "default": 3
-                            }
-                        }
-                    }
-                },
-                doc! { "$sort": { "priority": 1 } },
-                doc! { "$limit": 1 }
-            ];
-    
-            let mut cursor = market_pairs_collection.aggregate(pipeline).await
-                .map_err(|e| format!("Failed to execute aggregation: {}", e))?;
-    
-            if let Some(doc) = cursor.try_next().await
-                .map_err(|e| format!("Error iterating cursor: {}", e))? {
-                let conversion_pair: PopulatedMarketPair = bson::from_document(doc)
-                    .map_err(|e| format!("Failed to deserialize market pair: {}", e))?;
-                info!("Found conversion pair: {}/{}", 
-                      conversion_pair.base_asset.short_name, conversion_pair.quote_asset.short_name);
-                Ok(Some(conversion_pair))
-            } else {
-                info!("No conversion pair found");
-                Ok(None)
+                }
             }
         }
-        async fn get_exchange_pairs(
-            collection: &Collection<Document>,
-            exchange_id: ObjectId
-        ) -> Result<Vec<PopulatedMarketPair>, String> {
-            let pipeline = vec![
-                // Match para filtrar por el exchange específico
-                doc! {
-                    "$match": {
-                        "_exchange": exchange_id
-                    }
-                },
-                // Lookup para obtener la información del exchange
-                doc! {
-                    "$lookup": {
-                        "from": "exchanges",
-                        "localField": "_exchange",
-                        "foreignField": "_id",
-                        "as": "exchange"
-                    }
-                },
-                // Unwind del array de exchange (debería ser solo uno)
-                doc! {
-                    "$unwind": "$exchange"
-                },
-                // Lookup para obtener la información del activo base
-                doc! {
-                    "$lookup": {
-                        "from": "assets",
-                        "localField": "_base_asset",
-                        "foreignField": "_id",
-                        "as": "base_asset"
-                    }
-                },
-                // Unwind del array de base_asset
-                doc! {
-                    "$unwind": "$base_asset"
-                },
-                // Lookup para obtener la información del activo quote
-                doc! {
-                    "$lookup": {
-                        "from": "assets",
-                        "localField": "_quote_asset",
-                        "foreignField": "_id",
-                        "as": "quote_asset"
-                    }
-                },
-                // Unwind del array de quote_asset
-                doc! {
-                    "$unwind": "$quote_asset"
-                },
-                // Proyección para dar formato a la salida
-                doc! {
-                    "$project": {
-                        "_id": 1,
-                        "exchange": 1,
-                        "base_asset": 1,
-                        "quote_asset": 1,
-                        "created_at": 1,
-                        "updated_at": 1,
-                        "status": 1
-                    }
+
+        cycles
+    }
+
+    // Combinaciones X/Y, Y/Z y X/Z dentro de un exchange, en ese orden
+    pub fn find_trading_pair_combinations<'a>(pairs: &'a [PopulatedMarketPair], equivalences: &EquivalenceRegistry) -> Vec<[&'a PopulatedMarketPair; 3]> {
+        let keys: Vec<(String, String)> = pairs.iter()
+            .map(|p| (
+                equivalences.canonical(&p.base_asset.short_name),
+                equivalences.canonical(&p.quote_asset.short_name),
+            ))
+            .collect();
+
+        let mut by_assets: HashMap<(&str, &str), usize> = HashMap::new();
+        let mut by_base: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, (base, quote)) in keys.iter().enumerate() {
+            if base == quote {
+                continue;
+            }
+            by_assets.entry((base.as_str(), quote.as_str())).or_insert(index);
+            by_base.entry(base.as_str()).or_default().push(index);
+        }
+
+        let mut combinations = Vec::new();
+        for (i, (x, y)) in keys.iter().enumerate() {
+            if x == y {
+                continue;
+            }
+            for &j in by_base.get(y.as_str()).into_iter().flatten() {
+                let z = keys[j].1.as_str();
+                if z == x || z == y {
+                    continue;
+                }
+                if let Some(&k) = by_assets.get(&(x.as_str(), z)) {
+                    combinations.push([&pairs[i], &pairs[j], &pairs[k]]);
                 }
-            ];
-    
-            let mut cursor = collection.aggregate(pipeline).await
-                .map_err(|e| format!("Failed to execute aggregation: {}", e))?;
-    
-            let mut market_pairs = Vec::new();
-    
-            while let Some(doc) = cursor.try_next().await
-                .map_err(|e| format!("Error iterating cursor: {}", e))? {
-                let market_pair: PopulatedMarketPair = bson::from_document(doc)
-                    .map_err(|e| format!("Failed to deserialize market pair: {}", e))?;
-                market_pairs.push(market_pair);
             }
-    
-            Ok(market_pairs)
         }
-    
-}
\ No newline at end of file
+
+        combinations
+    }
+
+    // Mismo activo base y una variante del quote de pair1 entre los pares del otro exchange
+    fn find_corresponding_pair<'a>(
+        pair1: &PopulatedMarketPair,
+        exchange2_pairs: &'a [PopulatedMarketPair],
+        equivalences: &EquivalenceRegistry
+    ) -> Option<&'a PopulatedMarketPair> {
+        info!("Searching for corresponding pair for {}/{}", pair1.base_asset.short_name, pair1.quote_asset.short_name);
+
+        let quote_variants = equivalences.variants(&pair1.quote_asset.short_name);
+        let corresponding_pair = exchange2_pairs.iter().find(|pair| {
+            pair.base_asset.short_name == pair1.base_asset.short_name && quote_variants.contains(&pair.quote_asset.short_name)
+        });
+
+        match corresponding_pair {
+            Some(pair) => info!("Found corresponding pair: {}/{}", pair.base_asset.short_name, pair.quote_asset.short_name),
+            None => info!("No corresponding pair found"),
+        }
+        corresponding_pair
+    }
+
+    // Par de cualquier exchange entre los quotes de ambos pares, prefiriendo fiat y luego los grupos por prioridad
+    async fn find_conversion_pair(
+        repo: &dyn MarketPairRepository,
+        pair1: &PopulatedMarketPair,
+        pair2: &PopulatedMarketPair,
+        equivalences: &EquivalenceRegistry
+    ) -> Result<Option<PopulatedMarketPair>, AppError> {
+        info!("Searching for conversion pair between {}/{} and {}/{}",
+              pair1.quote_asset.short_name, pair2.quote_asset.short_name,
+              pair1.base_asset.short_name, pair2.base_asset.short_name);
+
+        let quote1_variants = equivalences.variants(&pair1.quote_asset.short_name);
+        let quote2_variants = equivalences.variants(&pair2.quote_asset.short_name);
+
+        let candidates = repo.find_populated_market_pairs(MarketPairFilter::LinkingSymbols(quote1_variants, quote2_variants)).await?;
+        let conversion_pair = candidates.into_iter()
+            .min_by_key(|pair| equivalences.conversion_priority(&pair.base_asset.short_name, &pair.quote_asset.short_name));
+
+        match &conversion_pair {
+            Some(pair) => info!("Found conversion pair: {}/{}", pair.base_asset.short_name, pair.quote_asset.short_name),
+            None => info!("No conversion pair found"),
+        }
+        Ok(conversion_pair)
+    }
+
+    async fn get_exchange_pairs(repo: &dyn MarketPairRepository, exchange_id: ObjectId) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        repo.find_populated_market_pairs(MarketPairFilter::Exchange(exchange_id)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::{exchange, pair};
+    use crate::modules::asset_equivalence::asset_equivalence_schema::AssetEquivalence;
+
+    fn equivalences() -> EquivalenceRegistry {
+        EquivalenceRegistry::new(vec![AssetEquivalence {
+            id: None,
+            peg: "USD".to_string(),
+            symbols: vec!["USDT".to_string(), "USDC".to_string()],
+            priority: 1,
+            created_at: 0.0,
+            updated_at: 0.0,
+        }])
+    }
+
+    #[test]
+    fn triangular_cycles_are_closed_and_unique() {
+        let ex = exchange("X");
+        let pairs = vec![
+            pair(&ex, "ETH", "USDT"),
+            pair(&ex, "ETH", "BTC"),
+            pair(&ex, "BTC", "USDC"),
+            pair(&ex, "LTC", "BTC"),
+        ];
+
+        let cycles = SuggestedArbitrageStrategyService::find_triangular_cycles(&pairs, &equivalences());
+
+        assert_eq!(cycles.len(), 1);
+        let [pair1, _, pair3] = cycles[0];
+        assert_eq!(pair1.quote_asset.short_name, "USDT");
+        assert_eq!(pair3.quote_asset.short_name, "USDC");
+    }
+
+    #[test]
+    fn trading_pair_combinations_follow_x_y_z_order() {
+        let ex = exchange("Y");
+        let pairs = vec![
+            pair(&ex, "LTC", "BTC"),
+            pair(&ex, "ETH", "BTC"),
+            pair(&ex, "LTC", "ETH"),
+        ];
+
+        let combinations = SuggestedArbitrageStrategyService::find_trading_pair_combinations(&pairs, &equivalences());
+
+        assert_eq!(combinations.len(), 1);
+        let symbols: Vec<String> = combinations[0].iter()
+            .map(|p| format!("{}/{}", p.base_asset.short_name, p.quote_asset.short_name))
+            .collect();
+        assert_eq!(symbols, ["LTC/ETH", "ETH/BTC", "LTC/BTC"]);
+    }
+}
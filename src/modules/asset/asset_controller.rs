@@ -1,76 +1,116 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, put, delete, web, HttpResponse};
 use crate::modules::asset::asset_service::AssetService;
-use crate::db::mongodb::MongoDbContext;
+use crate::db::repositories::Storage;
+use crate::modules::integrity::integrity_schema::{CatalogDocuments, DeleteQuery};
 use crate::modules::asset::asset_schema::Asset;
-use mongodb::bson::oid::ObjectId;
+use crate::helpers::app_error::{parse_object_id, AppError};
 use serde::{Deserialize};
-use serde_json::json; 
+use utoipa::IntoParams;
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::openapi::ErrorResponse;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
 
 #[derive(Deserialize)]
 struct ObjectIdPath {
     id: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 struct AssetQuery {
-    page: Option<u64>,
-    per_page: Option<u64>,
     include_exchange: Option<bool>,
     search: Option<String>,
 }
 
-#[post("/assets")]
-pub async fn create_asset(asset: web::Json<Asset>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    match AssetService::create_asset(asset.into_inner(), &db_context).await {
-        Ok(asset) => HttpResponse::Ok().json(asset),
-        Err(err) => HttpResponse::BadRequest().body(err),
-    }
+#[utoipa::path(
+    tag = "assets",
+    request_body = Asset,
+    responses(
+        (status = 200, description = "Asset created", body = ApiResponse<Asset>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 409, description = "Conflict with an existing record", body = ErrorResponse),
+    ),
+)]
+#[post("/assets", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn create_asset(asset: web::Json<Asset>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let asset = AssetService::create_asset(asset.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset created successfully", asset)))
 }
 
+#[utoipa::path(
+    tag = "assets",
+    params(("id" = String, Path, description = "Asset ID")),
+    responses(
+        (status = 200, description = "Asset", body = ApiResponse<Asset>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
 #[get("/assets/{id}")]
-pub async fn get_asset(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match AssetService::get_asset(id, &db_context).await {
-        Ok(asset) => HttpResponse::Ok().json(asset),
-        Err(err) => HttpResponse::NotFound().body(err),
-    }
+pub async fn get_asset(path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "asset")?;
+    let asset = AssetService::get_asset(id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset retrieved successfully", asset)))
 }
 
-#[put("/assets/{id}")]
-pub async fn update_asset(path: web::Path<ObjectIdPath>, asset: web::Json<Asset>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match AssetService::update_asset(id, asset.into_inner(), &db_context).await {
-        Ok(asset) => HttpResponse::Ok().json(asset),
-        Err(err) => HttpResponse::BadRequest().body(err),
-    }
+#[utoipa::path(
+    tag = "assets",
+    params(("id" = String, Path, description = "Asset ID")),
+    request_body = Asset,
+    responses(
+        (status = 200, description = "Asset updated", body = ApiResponse<Asset>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[put("/assets/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn update_asset(path: web::Path<ObjectIdPath>, asset: web::Json<Asset>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "asset")?;
+    let asset = AssetService::update_asset(id, asset.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset updated successfully", asset)))
 }
 
-#[delete("/assets/{id}")]
-pub async fn delete_asset(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match AssetService::delete_asset(id, &db_context).await {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(err) => HttpResponse::BadRequest().body(err),
-    }
+#[utoipa::path(
+    tag = "assets",
+    params(("id" = String, Path, description = "Asset ID"), DeleteQuery),
+    responses(
+        (status = 200, description = "Asset deleted, with the dependents removed by cascade", body = ApiResponse<CatalogDocuments>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Still referenced and cascade not requested", body = ErrorResponse),
+    ),
+)]
+#[delete("/assets/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn delete_asset(
+    path: web::Path<ObjectIdPath>,
+    query: web::Query<DeleteQuery>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "asset")?;
+    let cascaded = AssetService::delete_asset(id, query.cascade.unwrap_or(false), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset deleted successfully", cascaded)))
 }
 
+#[utoipa::path(
+    tag = "assets",
+    params(PageQuery, AssetQuery),
+    responses(
+        (status = 200, description = "Page of assets; with include_exchange each one carries its exchange", body = ApiResponse<Paginated<Asset>>),
+    ),
+)]
 #[get("/assets")]
 pub async fn get_all_assets(
-    db_context: web::Data<MongoDbContext>,
+    storage: web::Data<dyn Storage>,
+    page: web::Query<PageQuery>,
     query: web::Query<AssetQuery>,
-) -> impl Responder {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
+) -> Result<HttpResponse, AppError> {
     let include_exchange = query.include_exchange.unwrap_or(false);
     let search = query.search.clone();
 
-    match AssetService::get_all_assets(&db_context, page, per_page, include_exchange, search).await {
-        Ok((assets, total)) => HttpResponse::Ok().json(json!({
-            "assets": assets,
-            "total": total,
-            "page": page,
-            "per_page": per_page
-        })),
-        Err(err) => HttpResponse::BadRequest().body(err),
-    }
-}
\ No newline at end of file
+    let assets = AssetService::get_all_assets(storage.get_ref(), &page, include_exchange, search).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Assets retrieved successfully", assets)))
+}
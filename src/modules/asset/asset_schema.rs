@@ -1,13 +1,20 @@
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
 use mongodb::bson::oid::ObjectId;
 
-#[derive(Serialize, Deserialize, Debug, Clone)] // Añadir Clone
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)] // Añadir Clone
 pub struct Asset {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
     pub _exchange: ObjectId,
     pub name: String,
     pub short_name: String,
+    // Comisión fija, en unidades del propio activo, por retirarlo de su exchange
+    #[serde(default)]
+    pub withdrawal_fee: f64,
     pub created_at: f64,
     pub updated_at: f64,
     pub status: bool,
@@ -1,16 +1,20 @@
-use crate::db::mongodb::MongoDbContext;
-use mongodb::bson::{doc, Document, oid::ObjectId, Regex};  // Añade Document aquí
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::helpers::app_error::{AppError, FieldError};
+use crate::db::repositories::{AssetRepository, Storage};
+use crate::modules::integrity::integrity_schema::CatalogDocuments;
+use crate::modules::integrity::integrity_service::{CatalogItem, IntegrityService};
+use mongodb::bson::{Document, oid::ObjectId};
 use crate::modules::asset::asset_schema::Asset;
-use tracing::error;
 use chrono::Utc;
-use futures::TryStreamExt;
 pub struct AssetService;
 
-impl AssetService {
-    pub async fn create_asset(asset: Asset, db_context: &MongoDbContext) -> Result<Asset, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Asset>("assets");
+fn not_found() -> AppError {
+    AppError::NotFound("Asset not found".to_string())
+}
 
+impl AssetService {
+    pub async fn create_asset(asset: Asset, storage: &dyn Storage) -> Result<Asset, AppError> {
+        Self::validate(&asset, None, storage).await?;
         let now = Utc::now().timestamp() as f64;
         let new_asset = Asset {
             created_at: now,
@@ -18,147 +22,61 @@ impl AssetService {
             ..asset
         };
 
-        let insert_result = collection.insert_one(new_asset.clone()).await
-            .map_err(|e| {
-                error!("Failed to insert asset: {}", e);
-                e.to_string()
-            })?;
-        
-        let new_asset = collection.find_one(doc! { "_id": insert_result.inserted_id }).await
-            .map_err(|e| {
-                error!("Failed to fetch created asset: {}", e);
-                e.to_string()
-            })?
-            .ok_or_else(|| {
-                let msg = "Failed to fetch created asset".to_string();
-                error!("{}", msg);
-                msg
-            })?;
-        
-        Ok(new_asset)
+        storage.insert_asset(new_asset).await
     }
 
-    pub async fn get_asset(id: ObjectId, db_context: &MongoDbContext) -> Result<Asset, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Asset>("assets");
-
-        let asset = collection.find_one(doc! { "_id": id }).await
-            .map_err(|e| {
-                error!("Failed to fetch asset: {}", e);
-                e.to_string()
-            })?
-            .ok_or_else(|| {
-                let msg = "Asset not found".to_string();
-                error!("{}", msg);
-                msg
-            })?;
-
-        Ok(asset)
+    pub async fn get_asset(id: ObjectId, repo: &dyn AssetRepository) -> Result<Asset, AppError> {
+        repo.find_asset(id).await?.ok_or_else(not_found)
     }
 
-    pub async fn update_asset(id: ObjectId, updated_asset: Asset, db_context: &MongoDbContext) -> Result<Asset, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Asset>("assets");
-
-        let now = Utc::now().timestamp() as f64;
-        let update_doc = doc! {
-            "$set": {
-                "name": updated_asset.name,
-                "short_name": updated_asset.short_name,
-                "updated_at": now,
-                "status": updated_asset.status,
-                "_exchange": updated_asset._exchange,
-            }
+    pub async fn update_asset(id: ObjectId, updated_asset: Asset, storage: &dyn Storage) -> Result<Asset, AppError> {
+        Self::validate(&updated_asset, Some(id), storage).await?;
+        let updated_asset = Asset {
+            updated_at: Utc::now().timestamp() as f64,
+            ..updated_asset
         };
 
-        collection.update_one(doc! { "_id": id }, update_doc).await
-            .map_err(|e| {
-                error!("Failed to update asset: {}", e);
-                e.to_string()
-            })?;
-
-        Self::get_asset(id, db_context).await
+        storage.update_asset(id, updated_asset).await?.ok_or_else(not_found)
     }
 
-    pub async fn delete_asset(id: ObjectId, db_context: &MongoDbContext) -> Result<(), String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Asset>("assets");
+    // El exchange debe existir. Un activo que ya usan pares no puede cambiar de exchange,
+    // porque los pares exigen que sus activos sean del mismo exchange que ellos.
+    async fn validate(asset: &Asset, current: Option<ObjectId>, storage: &dyn Storage) -> Result<(), AppError> {
+        let mut errors = Vec::new();
 
-        collection.delete_one(doc! { "_id": id }).await
-            .map_err(|e| {
-                error!("Failed to delete asset: {}", e);
-                e.to_string()
-            })?;
+        if asset.short_name.trim().is_empty() {
+            errors.push(FieldError::new("short_name", "Must not be empty"));
+        }
+        if asset.withdrawal_fee < 0.0 {
+            errors.push(FieldError::new("withdrawal_fee", "Must not be negative"));
+        }
+        if storage.find_exchange(asset._exchange).await?.is_none() {
+            errors.push(FieldError::new("_exchange", "Exchange not found"));
+        } else if let Some(id) = current {
+            let moved = storage.find_asset(id).await?.is_some_and(|stored| stored._exchange != asset._exchange);
+            if moved && !storage.find_market_pair_ids_referencing(None, &[id]).await?.is_empty() {
+                errors.push(FieldError::new("_exchange", "Asset is used by market pairs of its current exchange"));
+            }
+        }
 
+        if !errors.is_empty() {
+            return Err(AppError::invalid_fields(errors));
+        }
         Ok(())
     }
 
+    // Con dependientes falla con Conflict salvo que cascade sea true; devuelve lo borrado en cascada
+    pub async fn delete_asset(id: ObjectId, cascade: bool, storage: &dyn Storage) -> Result<CatalogDocuments, AppError> {
+        IntegrityService::delete(CatalogItem::Asset(id), cascade, storage).await
+    }
+
     pub async fn get_all_assets(
-        db_context: &MongoDbContext,
-        page: u64,
-        per_page: u64,
+        repo: &dyn AssetRepository,
+        page: &PageQuery,
         include_exchange: bool,
         search: Option<String>
-    ) -> Result<(Vec<Document>, u64), String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Document>("assets");
-
-        let skip = (page - 1) * per_page;
-        
-        // Construir el filtro basado en el término de búsqueda
-        let filter = match search {
-            Some(term) => doc! {
-                "$or": [
-                    { "name": Regex { pattern: format!(".*{}.*", term), options: "i".to_string() } },
-                    { "short_name": Regex { pattern: format!(".*{}.*", term), options: "i".to_string() } }
-                ]
-            },
-            None => doc! {},
-        };
-
-        let mut pipeline = vec![
-            doc! { "$match": filter.clone() },
-            doc! { "$skip": skip as i64 },
-            doc! { "$limit": per_page as i64 },
-        ];
-
-        if include_exchange {
-            pipeline.push(doc! {
-                "$lookup": {
-                    "from": "exchanges",
-                    "localField": "_exchange",
-                    "foreignField": "_id",
-                    "as": "exchange"
-                }
-            });
-            pipeline.push(doc! {
-                "$unwind": {
-                    "path": "$exchange",
-                    "preserveNullAndEmptyArrays": true
-                }
-            });
-        }
-
-        let mut cursor = collection.aggregate(pipeline).await
-            .map_err(|e| {
-                error!("Failed to fetch assets: {}", e);
-                e.to_string()
-            })?;
-
-        let mut assets = Vec::new();
-        while let Some(asset) = cursor.try_next().await.map_err(|e| {
-            error!("Failed to iterate through assets: {}", e);
-            e.to_string()
-        })? {
-            assets.push(asset);
-        }
-
-        let total = collection.count_documents(filter).await
-            .map_err(|e| {
-                error!("Failed to count assets: {}", e);
-                e.to_string()
-            })?;
-
-        Ok((assets, total))
+    ) -> Result<Paginated<Document>, AppError> {
+        let search = search.filter(|term| !term.is_empty());
+        repo.list_assets(page, search.as_deref(), include_exchange).await
     }
 }
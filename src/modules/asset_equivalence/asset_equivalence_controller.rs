@@ -0,0 +1,95 @@
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use crate::modules::asset_equivalence::asset_equivalence_service::AssetEquivalenceService;
+use crate::db::repositories::Storage;
+use crate::modules::asset_equivalence::asset_equivalence_schema::AssetEquivalence;
+use serde::{Deserialize};
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::openapi::ErrorResponse;
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
+
+#[derive(Deserialize)]
+struct ObjectIdPath {
+    id: String,
+}
+
+#[utoipa::path(
+    tag = "asset_equivalences",
+    request_body = AssetEquivalence,
+    responses(
+        (status = 200, description = "Asset equivalence created", body = ApiResponse<AssetEquivalence>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+    ),
+)]
+#[post("/asset_equivalences", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn create_asset_equivalence(equivalence: web::Json<AssetEquivalence>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let equivalence = AssetEquivalenceService::create_asset_equivalence(equivalence.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset equivalence created successfully", equivalence)))
+}
+
+#[utoipa::path(
+    tag = "asset_equivalences",
+    params(("id" = String, Path, description = "Asset equivalence ID")),
+    responses(
+        (status = 200, description = "Asset equivalence", body = ApiResponse<AssetEquivalence>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/asset_equivalences/{id}")]
+pub async fn get_asset_equivalence(path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "asset equivalence")?;
+    let equivalence = AssetEquivalenceService::get_asset_equivalence(id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset equivalence retrieved successfully", equivalence)))
+}
+
+#[utoipa::path(
+    tag = "asset_equivalences",
+    params(("id" = String, Path, description = "Asset equivalence ID")),
+    request_body = AssetEquivalence,
+    responses(
+        (status = 200, description = "Asset equivalence updated", body = ApiResponse<AssetEquivalence>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[put("/asset_equivalences/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn update_asset_equivalence(path: web::Path<ObjectIdPath>, equivalence: web::Json<AssetEquivalence>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "asset equivalence")?;
+    let equivalence = AssetEquivalenceService::update_asset_equivalence(id, equivalence.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset equivalence updated successfully", equivalence)))
+}
+
+#[utoipa::path(
+    tag = "asset_equivalences",
+    params(("id" = String, Path, description = "Asset equivalence ID")),
+    responses(
+        (status = 200, description = "Asset equivalence deleted"),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/asset_equivalences/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn delete_asset_equivalence(path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "asset equivalence")?;
+    AssetEquivalenceService::delete_asset_equivalence(id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset equivalence deleted successfully", ())))
+}
+
+#[utoipa::path(
+    tag = "asset_equivalences",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Page of asset equivalences", body = ApiResponse<Paginated<AssetEquivalence>>),
+    ),
+)]
+#[get("/asset_equivalences")]
+pub async fn get_all_asset_equivalences(page: web::Query<PageQuery>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let equivalences = AssetEquivalenceService::get_asset_equivalences_page(&page, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Asset equivalences retrieved successfully", equivalences)))
+}
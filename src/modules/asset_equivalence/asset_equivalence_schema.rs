@@ -0,0 +1,20 @@
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use mongodb::bson::oid::ObjectId;
+
+// Grupo de activos equivalentes a una moneda fiat (p. ej. USDT, USDC y DAI respecto a USD).
+// Un número de prioridad menor hace que sus pares se prefieran como pares de conversión.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AssetEquivalence {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    pub peg: String,
+    pub symbols: Vec<String>,
+    pub priority: i32,
+    #[serde(default)]
+    pub created_at: f64,
+    #[serde(default)]
+    pub updated_at: f64,
+}
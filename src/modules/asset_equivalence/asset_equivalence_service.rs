@@ -0,0 +1,194 @@
+use crate::db::repositories::AssetEquivalenceRepository;
+use crate::helpers::app_error::AppError;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use mongodb::bson::oid::ObjectId;
+use crate::modules::asset_equivalence::asset_equivalence_schema::AssetEquivalence;
+use tracing::info;
+use chrono::Utc;
+
+// Prioridad de los pares que incluyen directamente una moneda fiat y de los que no son equivalentes
+const PEG_PRIORITY: i32 = 0;
+const DEFAULT_PRIORITY: i32 = 1000;
+
+fn not_found() -> AppError {
+    AppError::NotFound("Asset equivalence not found".to_string())
+}
+
+// Grupos con los que se inicializa la colección vacía (unión de las listas que antes estaban en el código)
+pub fn default_groups() -> Vec<AssetEquivalence> {
+    let group = |peg: &str, symbols: &[&str], priority: i32| AssetEquivalence {
+        id: None,
+        peg: peg.to_string(),
+        symbols: symbols.iter().map(|s| s.to_string()).collect(),
+        priority,
+        created_at: 0.0,
+        updated_at: 0.0,
+    };
+
+    vec![
+        group("USD", &["USDT", "USDC", "BUSD", "DAI", "TUSD", "USDP", "GUSD", "FDUSD"], 1),
+        group("EUR", &["EURS", "EURT", "sEUR", "SEUR", "EURB", "EURe", "EURE", "cEUR", "CEUR", "EUROC", "JEUR"], 2),
+    ]
+}
+
+// Vista en memoria de los grupos de equivalencia usada por el código de emparejamiento
+#[derive(Debug, Clone, Default)]
+pub struct EquivalenceRegistry {
+    groups: Vec<AssetEquivalence>,
+}
+
+impl EquivalenceRegistry {
+    pub fn new(mut groups: Vec<AssetEquivalence>) -> Self {
+        groups.sort_by_key(|g| g.priority);
+        Self { groups }
+    }
+
+    pub async fn load(repo: &dyn AssetEquivalenceRepository) -> Result<Self, AppError> {
+        Ok(Self::new(repo.find_asset_equivalences().await?))
+    }
+
+    fn contains(group: &AssetEquivalence, symbol: &str) -> bool {
+        group.peg.eq_ignore_ascii_case(symbol) || group.symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol))
+    }
+
+    // El propio símbolo más la moneda fiat y todos los miembros de los grupos que lo contienen
+    pub fn variants(&self, symbol: &str) -> Vec<String> {
+        let mut variants = vec![symbol.to_string()];
+        for group in self.groups.iter().filter(|g| Self::contains(g, symbol)) {
+            variants.push(group.peg.clone());
+            variants.extend(group.symbols.iter().cloned());
+        }
+        variants.sort();
+        variants.dedup();
+        variants
+    }
+
+    // Moneda fiat del grupo o el propio símbolo si no pertenece a ninguno
+    pub fn canonical(&self, symbol: &str) -> String {
+        self.groups.iter()
+            .find(|g| Self::contains(g, symbol))
+            .map(|g| g.peg.clone())
+            .unwrap_or_else(|| symbol.to_string())
+    }
+
+    pub fn same_asset(&self, a: &str, b: &str) -> bool {
+        a.eq_ignore_ascii_case(b) || self.groups.iter().any(|g| Self::contains(g, a) && Self::contains(g, b))
+    }
+
+    // Orden de preferencia de un par de conversión: fiat primero, luego los grupos por prioridad
+    pub fn conversion_priority(&self, base: &str, quote: &str) -> i32 {
+        if self.groups.iter().any(|g| g.peg == base || g.peg == quote) {
+            return PEG_PRIORITY;
+        }
+        self.groups.iter()
+            .find(|g| g.symbols.iter().any(|s| s == base || s == quote))
+            .map(|g| g.priority)
+            .unwrap_or(DEFAULT_PRIORITY)
+    }
+}
+
+pub struct AssetEquivalenceService;
+
+impl AssetEquivalenceService {
+    fn validate(equivalence: &AssetEquivalence) -> Result<(), AppError> {
+        if equivalence.peg.trim().is_empty() {
+            return Err(AppError::Validation("peg is required".to_string()));
+        }
+        if equivalence.symbols.is_empty() || equivalence.symbols.iter().any(|s| s.trim().is_empty()) {
+            return Err(AppError::Validation("symbols must contain at least one non-empty symbol".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn ensure_defaults(repo: &dyn AssetEquivalenceRepository) -> Result<(), AppError> {
+        if !repo.find_asset_equivalences().await?.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now().timestamp() as f64;
+        for group in default_groups() {
+            repo.insert_asset_equivalence(AssetEquivalence { created_at: now, updated_at: now, ..group }).await?;
+        }
+
+        info!("Seeded default asset equivalences");
+        Ok(())
+    }
+
+    pub async fn create_asset_equivalence(equivalence: AssetEquivalence, repo: &dyn AssetEquivalenceRepository) -> Result<AssetEquivalence, AppError> {
+        Self::validate(&equivalence)?;
+
+        let now = Utc::now().timestamp() as f64;
+        let new_equivalence = AssetEquivalence {
+            id: None,
+            created_at: now,
+            updated_at: now,
+            ..equivalence
+        };
+
+        repo.insert_asset_equivalence(new_equivalence).await
+    }
+
+    pub async fn get_asset_equivalence(id: ObjectId, repo: &dyn AssetEquivalenceRepository) -> Result<AssetEquivalence, AppError> {
+        repo.find_asset_equivalence(id).await?.ok_or_else(not_found)
+    }
+
+    pub async fn update_asset_equivalence(id: ObjectId, updated: AssetEquivalence, repo: &dyn AssetEquivalenceRepository) -> Result<AssetEquivalence, AppError> {
+        Self::validate(&updated)?;
+
+        let updated = AssetEquivalence {
+            updated_at: Utc::now().timestamp() as f64,
+            ..updated
+        };
+        repo.update_asset_equivalence(id, updated).await?.ok_or_else(not_found)
+    }
+
+    pub async fn delete_asset_equivalence(id: ObjectId, repo: &dyn AssetEquivalenceRepository) -> Result<(), AppError> {
+        if !repo.delete_asset_equivalence(id).await? {
+            return Err(not_found());
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_asset_equivalences_page(page: &PageQuery, repo: &dyn AssetEquivalenceRepository) -> Result<Paginated<AssetEquivalence>, AppError> {
+        repo.list_asset_equivalences(page).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::InMemoryStorage;
+
+    #[test]
+    fn default_groups_match_across_casing() {
+        let registry = EquivalenceRegistry::new(default_groups());
+
+        assert!(registry.same_asset("USDT", "usd"));
+        assert!(registry.same_asset("sEUR", "EURT"));
+        assert!(!registry.same_asset("USDT", "EUR"));
+        assert_eq!(registry.canonical("FDUSD"), "USD");
+        assert_eq!(registry.canonical("BTC"), "BTC");
+        assert!(registry.variants("BUSD").contains(&"USDC".to_string()));
+
+        assert_eq!(registry.conversion_priority("EURT", "USD"), PEG_PRIORITY);
+        assert_eq!(registry.conversion_priority("EURT", "USDT"), 1);
+        assert_eq!(registry.conversion_priority("BTC", "EURT"), 2);
+        assert_eq!(registry.conversion_priority("BTC", "ETH"), DEFAULT_PRIORITY);
+    }
+
+    #[actix_web::test]
+    async fn missing_equivalences_are_not_found() {
+        let storage = InMemoryStorage::default();
+        let equivalence = default_groups().remove(0);
+        let id = ObjectId::new();
+
+        let err = AssetEquivalenceService::update_asset_equivalence(id, equivalence.clone(), &storage).await.unwrap_err();
+        assert_eq!(err, not_found());
+        assert_eq!(AssetEquivalenceService::delete_asset_equivalence(id, &storage).await.unwrap_err(), not_found());
+
+        let created = AssetEquivalenceService::create_asset_equivalence(equivalence, &storage).await.unwrap();
+        AssetEquivalenceService::delete_asset_equivalence(created.id.unwrap(), &storage).await.unwrap();
+        assert_eq!(AssetEquivalenceService::delete_asset_equivalence(created.id.unwrap(), &storage).await.unwrap_err(), not_found());
+    }
+}
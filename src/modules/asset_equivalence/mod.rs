@@ -0,0 +1,13 @@
+pub mod asset_equivalence_schema;
+pub mod asset_equivalence_service;
+pub mod asset_equivalence_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(asset_equivalence_controller::create_asset_equivalence);
+    cfg.service(asset_equivalence_controller::get_asset_equivalence);
+    cfg.service(asset_equivalence_controller::update_asset_equivalence);
+    cfg.service(asset_equivalence_controller::delete_asset_equivalence);
+    cfg.service(asset_equivalence_controller::get_all_asset_equivalences);
+}
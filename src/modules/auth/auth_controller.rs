@@ -1,23 +1,142 @@
-use actix_web::{post, web, HttpResponse, Responder};
-use crate::modules::auth::auth_service::AuthService;
-use crate::db::mongodb::MongoDbContext;
-use crate::modules::auth::auth_model::{RegisterRequest, LoginRequest};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header;
+use crate::modules::auth::auth_service::{AuthResponse, AuthService};
+use crate::modules::auth::mailer::mailer_from_env;
+use crate::modules::auth::session_service::{SessionInfo, SessionService, TokenPair};
+use crate::db::repositories::Storage;
+use crate::modules::auth::auth_model::{RegisterRequest, LoginRequest, RefreshRequest, ForgotPasswordRequest, ResetPasswordRequest};
 use crate::modules::auth::auth_response::ApiResponse;
+use crate::openapi::ErrorResponse;
+use crate::middleware::current_user::CurrentUser;
+use crate::helpers::app_error::AppError;
+use tracing::error;
 
+// Identifica el dispositivo de la sesión
+fn user_agent(req: &HttpRequest) -> &str {
+    req.headers().get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("unknown")
+}
+
+#[utoipa::path(
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, description = "Registered and logged in", body = ApiResponse<AuthResponse>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 409, description = "Conflict with an existing record", body = ErrorResponse),
+    ),
+    security(()),
+)]
 #[post("/register")]
-pub async fn register(data: web::Json<RegisterRequest>, db_context: web::Data<MongoDbContext>) -> impl Responder {
+pub async fn register(req: HttpRequest, data: web::Json<RegisterRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
     let request = data.into_inner();
-    match AuthService::register(&request.name, &request.email, &request.password, &db_context).await {
-        Ok(auth_response) => HttpResponse::Ok().json(ApiResponse::success("Registration successful", auth_response)),
-        Err(err) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&err)),
-    }
+    let auth_response = AuthService::register(&request.name, &request.email, &request.password, user_agent(&req), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Registration successful", auth_response)))
 }
 
+#[utoipa::path(
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = ApiResponse<AuthResponse>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+    security(()),
+)]
 #[post("/login")]
-pub async fn login(data: web::Json<LoginRequest>, db_context: web::Data<MongoDbContext>) -> impl Responder {
+pub async fn login(req: HttpRequest, data: web::Json<LoginRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
     let request = data.into_inner();
-    match AuthService::login(&request.email, &request.password, &db_context).await {
-        Ok(auth_response) => HttpResponse::Ok().json(ApiResponse::success("Login successful", auth_response)),
-        Err(err) => HttpResponse::Unauthorized().json(ApiResponse::<()>::error(&err)),
+    let auth_response = AuthService::login(&request.email, &request.password, user_agent(&req), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Login successful", auth_response)))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "New token pair; the refresh token is rotated", body = ApiResponse<TokenPair>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+    security(()),
+)]
+#[post("/refresh")]
+pub async fn refresh(req: HttpRequest, data: web::Json<RefreshRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let tokens = SessionService::refresh(&data.refresh_token, user_agent(&req), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Token refreshed successfully", tokens)))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    responses(
+        (status = 200, description = "Current session revoked"),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+)]
+#[post("/logout")]
+pub async fn logout(user: CurrentUser, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let Some(session_id) = user.session_id else {
+        return Err(AppError::Validation("Request is not authenticated with a session".to_string()));
+    };
+    SessionService::revoke(user.id, &session_id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Logged out successfully", ())))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    responses(
+        (status = 200, description = "Active sessions of the user", body = ApiResponse<Vec<SessionInfo>>),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+)]
+#[get("/sessions")]
+pub async fn get_sessions(user: CurrentUser, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let sessions = SessionService::list(user.id, user.session_id.as_deref(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Sessions retrieved successfully", sessions)))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    responses(
+        (status = 200, description = "All sessions revoked"),
+        (status = 401, description = "Missing or invalid credentials", body = ErrorResponse),
+    ),
+)]
+#[post("/sessions/revoke-all")]
+pub async fn revoke_all_sessions(user: CurrentUser, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    SessionService::revoke_all(user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("All sessions revoked successfully", ())))
+}
+
+// Misma respuesta exista o no el email
+#[utoipa::path(
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 200, description = "Same answer whether or not the email exists"),
+    ),
+    security(()),
+)]
+#[post("/password/forgot")]
+pub async fn forgot_password(data: web::Json<ForgotPasswordRequest>, storage: web::Data<dyn Storage>) -> impl Responder {
+    if let Err(err) = AuthService::forgot_password(data.email.trim(), storage.get_ref(), mailer_from_env()).await {
+        error!("Failed to start password reset: {}", err);
     }
+    HttpResponse::Ok().json(ApiResponse::success("If the email is registered, a password reset link has been sent", ()))
+}
+
+#[utoipa::path(
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password changed and sessions closed"),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+    ),
+    security(()),
+)]
+#[post("/password/reset")]
+pub async fn reset_password(data: web::Json<ResetPasswordRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    AuthService::reset_password(data.token.trim(), &data.password, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Password reset successfully", ())))
 }
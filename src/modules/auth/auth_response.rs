@@ -1,9 +1,17 @@
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::helpers::app_error::FieldError;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub message: String,
     pub data: Option<T>,
+    // Código de error legible por máquina (ver AppError); ausente en las respuestas correctas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    // Errores por campo de las respuestas de validación que los tienen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
 }
 
 impl<T> ApiResponse<T> {
@@ -11,6 +19,8 @@ impl<T> ApiResponse<T> {
         ApiResponse {
             message: message.to_string(),
             data: Some(data),
+            code: None,
+            errors: None,
         }
     }
 
@@ -18,6 +28,17 @@ impl<T> ApiResponse<T> {
         ApiResponse {
             message: message.to_string(),
             data: None,
+            code: None,
+            errors: None,
+        }
+    }
+
+    pub fn failure(code: &str, message: &str) -> Self {
+        ApiResponse {
+            message: message.to_string(),
+            data: None,
+            code: Some(code.to_string()),
+            errors: None,
         }
     }
 }
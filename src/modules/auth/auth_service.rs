@@ -1,23 +1,20 @@
-use crate::modules::user::user_schema::User;
-use mongodb::bson::{doc};
+use crate::modules::user::user_schema::{Role, User};
 use bcrypt::{hash, verify, DEFAULT_COST}; // Importar bcrypt
-use jsonwebtoken::{encode, Header, EncodingKey}; // Importaciones necesarias
-use tracing::{info, error};
-use crate::db::mongodb::MongoDbContext;
-use chrono::{Utc, Duration};
+use tracing::{info, error, warn};
+use crate::db::repositories::UserRepository;
 use std::env;
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::modules::auth::session_service::{generate_secret, hash_secret, SessionService};
+use crate::modules::auth::mailer::{MailMessage, Mailer};
+use chrono::{Duration, Utc};
+use crate::helpers::app_error::AppError;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Claims {
-    sub: String,
-    exp: usize,
-}
-
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct AuthResponse {
     pub id: String,           // Añadir campo id
     pub token: String,
+    pub refresh_token: String,
     pub name: String,
     pub email: String,
     pub _default_asset: Option<String>, // Enviar como String en la respuesta JSON
@@ -26,38 +23,39 @@ pub struct AuthResponse {
 
 pub struct AuthService;
 
-impl AuthService {
-    pub async fn login(email: &str, password: &str, db_context: &MongoDbContext) -> Result<AuthResponse, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<User>("users");
+// Mismo error para email desconocido y contraseña incorrecta, así no se revela qué cuentas existen
+fn invalid_credentials() -> AppError {
+    AppError::Unauthorized("Invalid email or password".to_string())
+}
 
+fn invalid_reset_token() -> AppError {
+    AppError::Validation("Invalid or expired reset token".to_string())
+}
+
+impl AuthService {
+    pub async fn login(email: &str, password: &str, user_agent: &str, repo: &dyn UserRepository) -> Result<AuthResponse, AppError> {
         // Buscar el usuario por email
-        let user = collection
-            .find_one(doc! { "email": email })
-            .await
-            .map_err(|e| e.to_string())?
+        let user = repo
+            .find_user_by_email(email)
+            .await?
             .ok_or_else(|| {
                 error!("User not found: {}", email);
-                "User not found".to_string()
+                invalid_credentials()
             })?;
 
         // Verificar la contraseña proporcionada con el hash almacenado
-        if !verify(password, &user.password).map_err(|e| e.to_string())? {
+        if !verify(password, &user.password).map_err(|e| AppError::Internal(e.to_string()))? {
             error!("Invalid password for user: {}", email);
-            return Err("Invalid password".to_string());
+            return Err(invalid_credentials());
         }
 
-        // Configurar el tiempo de expiración del token (por ejemplo, 12 horas)
-        let expiration = Utc::now() + Duration::hours(12);
-        let my_claims = Claims {
-            sub: user.id.unwrap().to_hex(),
-            exp: expiration.timestamp() as usize,
-        };
-        let token = encode(&Header::default(), &my_claims, &EncodingKey::from_secret(env::var("SECRET_KEY").expect("SECRET_KEY must be set").as_ref())).map_err(|e| e.to_string())?;
+        // Cada login abre una sesión: access token corto y refresh token rotatorio
+        let tokens = SessionService::create(user.id.unwrap(), user_agent, repo).await?;
 
         let auth_response = AuthResponse {
             id: user.id.unwrap().to_hex(), // Añadir el id del usuario
-            token,
+            token: tokens.token,
+            refresh_token: tokens.refresh_token,
             name: user.name,
             email: user.email,
             _default_asset: user._default_asset.map(|id| id.to_hex()), // Convertir ObjectId a String
@@ -67,22 +65,16 @@ impl AuthService {
         Ok(auth_response)
     }
 
-    pub async fn register(name: &str, email: &str, password: &str, db_context: &MongoDbContext) -> Result<AuthResponse, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<User>("users");
-
+    pub async fn register(name: &str, email: &str, password: &str, user_agent: &str, repo: &dyn UserRepository) -> Result<AuthResponse, AppError> {
         // Verificar si el usuario ya existe
-        if let Some(_) = collection
-            .find_one(doc! { "email": email })
-            .await
-            .map_err(|e| e.to_string())? {
-            return Err("User already exists".to_string());
+        if repo.find_user_by_email(email).await?.is_some() {
+            return Err(AppError::Conflict("User already exists".to_string()));
         }
 
         // Generar hash de la contraseña
-        let hashed_password = hash(password, DEFAULT_COST).map_err(|e| e.to_string())?;
+        let hashed_password = hash(password, DEFAULT_COST).map_err(|e| AppError::Internal(e.to_string()))?;
 
-        let mut user = User {
+        let user = User {
             id: None,
             name: name.to_string(),
             email: email.to_string(),
@@ -92,28 +84,19 @@ impl AuthService {
             password_reset_token: String::new(),
             password_reset_expires: chrono::Utc::now().naive_utc(),
             tokens: vec![],
-            role: "user".to_string(),
+            role: Role::Viewer, // El primer admin se asigna al arrancar con PROMOTE_ADMIN_EMAIL
         };
 
-        let insert_result = collection
-            .insert_one(&user)
-            .await
-            .map_err(|e| e.to_string())?;
+        // El repositorio devuelve el usuario con el _id generado
+        let user = repo.insert_user(user).await?;
 
-        // Actualizar el user.id con el _id generado por MongoDB
-        user.id = Some(insert_result.inserted_id.as_object_id().unwrap());
-
-        // Generar el token JWT para el nuevo usuario registrado
-        let expiration = Utc::now() + Duration::hours(12);
-        let my_claims = Claims {
-            sub: user.id.unwrap().to_hex(),
-            exp: expiration.timestamp() as usize,
-        };
-        let token = encode(&Header::default(), &my_claims, &EncodingKey::from_secret(env::var("SECRET_KEY").expect("SECRET_KEY must be set").as_ref())).map_err(|e| e.to_string())?;
+        // Abrir la primera sesión del nuevo usuario registrado
+        let tokens = SessionService::create(user.id.unwrap(), user_agent, repo).await?;
 
         let auth_response = AuthResponse {
             id: user.id.unwrap().to_hex(), // Añadir el id del usuario
-            token,
+            token: tokens.token,
+            refresh_token: tokens.refresh_token,
             name: user.name,
             email: user.email,
             _default_asset: user._default_asset.map(|id| id.to_hex()), // Convertir ObjectId a String
@@ -123,4 +106,154 @@ impl AuthService {
         info!("User registered: {}", email);
         Ok(auth_response)
     }
+
+    // Genera un token de un solo uso y lo envía por correo. Si el email no existe no hace nada,
+    // y el envío va en segundo plano para que la respuesta no dependa de ello.
+    pub async fn forgot_password(email: &str, repo: &dyn UserRepository, mailer: Box<dyn Mailer>) -> Result<(), AppError> {
+        let Some(user) = repo.find_user_by_email(email).await? else {
+            info!("Password reset requested for unknown email");
+            return Ok(());
+        };
+
+        let minutes = env::var("PASSWORD_RESET_MINUTES").ok().and_then(|v| v.parse().ok()).unwrap_or(30);
+        let token = generate_secret();
+        let expires = Utc::now().naive_utc() + Duration::minutes(minutes);
+        let user_id = user.id.ok_or_else(|| AppError::Internal("User without id".to_string()))?;
+        repo.set_password_reset(user_id, &hash_secret(&token), expires).await?;
+
+        let reset_url = env::var("PASSWORD_RESET_URL").unwrap_or_else(|_| "http://localhost:3000/reset-password".to_string());
+        let message = MailMessage {
+            to: user.email,
+            subject: "Password reset".to_string(),
+            body: format!(
+                "Hi {},\n\nUse this link to choose a new password: {}?token={}\n\nThe link expires in {} minutes and can only be used once. If you did not request it, ignore this email.",
+                user.name, reset_url, token, minutes
+            ),
+        };
+        actix_web::rt::spawn(async move {
+            if let Err(e) = mailer.send(&message).await {
+                error!("Failed to send password reset email: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+
+    // Consume el token: cambia la contraseña, invalida el token y cierra todas las sesiones
+    pub async fn reset_password(token: &str, password: &str, repo: &dyn UserRepository) -> Result<(), AppError> {
+        if password.len() < 8 {
+            return Err(AppError::Validation("Password must be at least 8 characters".to_string()));
+        }
+        if token.is_empty() {
+            return Err(invalid_reset_token());
+        }
+
+        let token_hash = hash_secret(token);
+
+        let user = repo
+            .find_user_by_reset_token(&token_hash)
+            .await?
+            .ok_or_else(invalid_reset_token)?;
+        let user_id = user.id.ok_or_else(invalid_reset_token)?;
+        if user.password_reset_expires < Utc::now().naive_utc() {
+            warn!("Expired password reset token used for user {:?}", user.id);
+            return Err(invalid_reset_token());
+        }
+
+        let hashed_password = hash(password, DEFAULT_COST).map_err(|e| AppError::Internal(e.to_string()))?;
+        if !repo.consume_password_reset(user_id, &token_hash, &hashed_password).await? {
+            return Err(invalid_reset_token());
+        }
+
+        info!("Password reset for user {}", user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::InMemoryStorage;
+    use async_trait::async_trait;
+    use mongodb::bson::oid::ObjectId;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+    const PASSWORD: &str = "password123";
+
+    // Entrega los correos al test en lugar de escribirlos
+    struct ChannelMailer(UnboundedSender<MailMessage>);
+
+    #[async_trait]
+    impl Mailer for ChannelMailer {
+        async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+            self.0.send(message.clone()).map_err(|e| AppError::Internal(e.to_string()))
+        }
+    }
+
+    async fn seed(storage: &InMemoryStorage) -> ObjectId {
+        std::env::set_var("SECRET_KEY", "test-secret");
+        let user = storage.insert_user(User {
+            id: None,
+            name: "Ana".to_string(),
+            email: "ana@example.com".to_string(),
+            password: hash(PASSWORD, 4).unwrap(),
+            _default_asset: None,
+            _default_market_pair: None,
+            password_reset_token: String::new(),
+            password_reset_expires: Utc::now().naive_utc(),
+            tokens: vec![],
+            role: Role::Viewer,
+        }).await.unwrap();
+        user.id.unwrap()
+    }
+
+    // Pide el reset y devuelve el token que llega en el correo
+    async fn request_reset(storage: &InMemoryStorage) -> String {
+        let (sender, mut outbox) = unbounded_channel();
+        AuthService::forgot_password("ana@example.com", storage, Box::new(ChannelMailer(sender))).await.unwrap();
+        let message = outbox.recv().await.unwrap();
+        message.body.split("token=").nth(1).unwrap().split_whitespace().next().unwrap().to_string()
+    }
+
+    #[actix_web::test]
+    async fn reset_token_works_once_and_closes_every_session() {
+        let storage = InMemoryStorage::default();
+        let user_id = seed(&storage).await;
+        SessionService::create(user_id, "first", &storage).await.unwrap();
+        SessionService::create(user_id, "second", &storage).await.unwrap();
+
+        let token = request_reset(&storage).await;
+        AuthService::reset_password(&token, "new-password", &storage).await.unwrap();
+
+        assert!(SessionService::list(user_id, None, &storage).await.unwrap().is_empty());
+        assert!(AuthService::login("ana@example.com", "new-password", "test", &storage).await.is_ok());
+        assert_eq!(AuthService::reset_password(&token, "another-password", &storage).await.unwrap_err(), invalid_reset_token());
+    }
+
+    #[actix_web::test]
+    async fn expired_reset_token_is_rejected() {
+        let storage = InMemoryStorage::default();
+        let user_id = seed(&storage).await;
+
+        let token = request_reset(&storage).await;
+        storage.set_password_reset(user_id, &hash_secret(&token), Utc::now().naive_utc() - Duration::minutes(1)).await.unwrap();
+
+        assert_eq!(AuthService::reset_password(&token, "new-password", &storage).await.unwrap_err(), invalid_reset_token());
+        assert!(AuthService::login("ana@example.com", PASSWORD, "test", &storage).await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn unknown_email_gets_the_same_answer_without_mail() {
+        let storage = InMemoryStorage::default();
+        seed(&storage).await;
+        let (sender, mut outbox) = unbounded_channel();
+
+        let unknown = AuthService::forgot_password("nobody@example.com", &storage, Box::new(ChannelMailer(sender.clone()))).await;
+        let known = AuthService::forgot_password("ana@example.com", &storage, Box::new(ChannelMailer(sender))).await;
+        assert_eq!(unknown, known);
+
+        // Solo el email registrado genera un correo; el canal se cierra al soltar los mailers
+        assert_eq!(outbox.recv().await.unwrap().to, "ana@example.com");
+        assert!(outbox.recv().await.is_none());
+    }
 }
@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::env;
+use std::path::PathBuf;
+use tracing::info;
+use crate::helpers::app_error::AppError;
+use crate::modules::auth::mailer::{MailMessage, Mailer};
+
+// Escribe cada correo como un archivo de texto en el directorio de salida
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(PathBuf::from(env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "mail_outbox".to_string())))
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| AppError::Internal(format!("Failed to create {}: {}", self.dir.display(), e)))?;
+
+        let recipient: String = message.to.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        let path = self.dir.join(format!("{}-{}.txt", Utc::now().timestamp_millis(), recipient));
+        let content = format!("To: {}\nSubject: {}\n\n{}\n", message.to, message.subject, message.body);
+        std::fs::write(&path, content)
+            .map_err(|e| AppError::Internal(format!("Failed to write {}: {}", path.display(), e)))?;
+
+        info!("Mail \"{}\" to {} written to {}", message.subject, message.to, path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn writes_message_to_outbox() {
+        let dir = env::temp_dir().join(format!("arbi-mail-{}", Utc::now().timestamp_nanos_opt().unwrap_or_default()));
+        let mailer = FileMailer::new(dir.clone());
+        let message = MailMessage { to: "ana@example.com".to_string(), subject: "Reset".to_string(), body: "token".to_string() };
+
+        mailer.send(&message).await.unwrap();
+
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().filter_map(Result::ok).collect();
+        assert_eq!(files.len(), 1);
+        let content = std::fs::read_to_string(files[0].path()).unwrap();
+        assert!(content.starts_with("To: ana@example.com\nSubject: Reset\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use crate::helpers::app_error::AppError;
+use crate::modules::auth::file_mailer::FileMailer;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError>;
+}
+
+// Resuelve el mailer configurado. Mientras no haya un proveedor SMTP, los correos
+// se escriben en MAIL_OUTBOX_DIR para poder consultarlos en local.
+pub fn mailer_from_env() -> Box<dyn Mailer> {
+    Box::new(FileMailer::from_env())
+}
@@ -2,10 +2,19 @@ pub mod auth_service;
 pub mod auth_controller;
 pub mod auth_model;
 pub mod auth_response;
+pub mod session_service;
+pub mod mailer;
+pub mod file_mailer;
 
 use actix_web::web;
 
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(auth_controller::login);
     cfg.service(auth_controller::register);
+    cfg.service(auth_controller::refresh);
+    cfg.service(auth_controller::logout);
+    cfg.service(auth_controller::get_sessions);
+    cfg.service(auth_controller::revoke_all_sessions);
+    cfg.service(auth_controller::forgot_password);
+    cfg.service(auth_controller::reset_password);
 }
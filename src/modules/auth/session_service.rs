@@ -0,0 +1,191 @@
+use crate::modules::user::user_schema::Session;
+use crate::middleware::auth_middleware::Claims;
+use crate::db::repositories::UserRepository;
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use jsonwebtoken::{encode, Header, EncodingKey};
+use chrono::Utc;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use sha2::{Digest, Sha256};
+use std::env;
+use tracing::{error, info, warn};
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+// Vista de una sesión para el listado; no incluye el hash del refresh token
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct SessionInfo {
+    pub id: String,
+    pub user_agent: String,
+    pub created_at: f64,
+    pub last_used_at: f64,
+    pub expires_at: f64,
+    pub current: bool,
+}
+
+fn env_duration(name: &str, default: i64) -> i64 {
+    env::var(name).ok().and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(default)
+}
+
+pub fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn generate_secret() -> String {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// El refresh token tiene la forma "{sid}.{secreto}" para localizar la sesión sin recorrer todas
+fn split_refresh_token(refresh_token: &str) -> Option<(&str, &str)> {
+    refresh_token.split_once('.').filter(|(sid, secret)| !sid.is_empty() && !secret.is_empty())
+}
+
+pub struct SessionService;
+
+impl SessionService {
+    fn access_token(user_id: ObjectId, session_id: &str) -> Result<String, AppError> {
+        let expiration = Utc::now() + chrono::Duration::minutes(env_duration("ACCESS_TOKEN_MINUTES", 15));
+        let claims = Claims {
+            sub: user_id.to_hex(),
+            exp: expiration.timestamp() as usize,
+            sid: Some(session_id.to_string()),
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(env::var("SECRET_KEY").expect("SECRET_KEY must be set").as_ref()))
+            .map_err(|e| AppError::Internal(e.to_string()))
+    }
+
+    // Abre una sesión nueva para el dispositivo y descarta las caducadas
+    pub async fn create(user_id: ObjectId, user_agent: &str, repo: &dyn UserRepository) -> Result<TokenPair, AppError> {
+        let now = Utc::now().timestamp() as f64;
+
+        let session_id = ObjectId::new().to_hex();
+        let secret = generate_secret();
+        let session = Session {
+            id: session_id.clone(),
+            refresh_token_hash: hash_secret(&secret),
+            user_agent: user_agent.to_string(),
+            created_at: now,
+            last_used_at: now,
+            expires_at: now + (env_duration("REFRESH_TOKEN_DAYS", 30) * 86_400) as f64,
+        };
+        repo.add_session(user_id, session, now).await?;
+
+        Ok(TokenPair {
+            token: Self::access_token(user_id, &session_id)?,
+            refresh_token: format!("{}.{}", session_id, secret),
+        })
+    }
+
+    // Rota el refresh token: el anterior deja de valer. Reutilizar uno ya rotado revoca la sesión.
+    pub async fn refresh(refresh_token: &str, user_agent: &str, repo: &dyn UserRepository) -> Result<TokenPair, AppError> {
+        let (session_id, secret) = split_refresh_token(refresh_token).ok_or_else(|| AppError::Unauthorized("Invalid refresh token".to_string()))?;
+        let now = Utc::now().timestamp() as f64;
+
+        let user = repo
+            .find_user_by_session(session_id)
+            .await?
+            .ok_or_else(|| AppError::Unauthorized("Invalid refresh token".to_string()))?;
+        let user_id = user.id.ok_or_else(|| AppError::Unauthorized("Invalid refresh token".to_string()))?;
+        let session = user.tokens.iter().find(|s| s.id == session_id)
+            .ok_or_else(|| AppError::Unauthorized("Invalid refresh token".to_string()))?;
+
+        if session.expires_at <= now {
+            Self::revoke(user_id, session_id, repo).await?;
+            return Err(AppError::Unauthorized("Refresh token expired".to_string()));
+        }
+        if session.refresh_token_hash != hash_secret(secret) {
+            warn!("Refresh token reuse detected for session {} of user {}", session_id, user_id);
+            Self::revoke(user_id, session_id, repo).await?;
+            return Err(AppError::Unauthorized("Refresh token already used; session revoked".to_string()));
+        }
+
+        // Solo rota si el hash no ha cambiado, así dos refresh simultáneos no rotan la misma sesión
+        let new_secret = generate_secret();
+        if !repo.rotate_session(user_id, session_id, &session.refresh_token_hash, &hash_secret(&new_secret), user_agent, now).await? {
+            return Err(AppError::Unauthorized("Invalid refresh token".to_string()));
+        }
+
+        Ok(TokenPair {
+            token: Self::access_token(user_id, session_id)?,
+            refresh_token: format!("{}.{}", session_id, new_secret),
+        })
+    }
+
+    // Comprueba que la sesión del token sigue activa y actualiza su último uso
+    pub async fn touch(claims: &Claims, repo: &dyn UserRepository) -> bool {
+        let (Ok(user_id), Some(session_id)) = (ObjectId::parse_str(&claims.sub), claims.sid.as_deref()) else {
+            return false;
+        };
+        let now = Utc::now().timestamp() as f64;
+
+        match repo.touch_session(user_id, session_id, now).await {
+            Ok(active) => active,
+            Err(e) => {
+                error!("Failed to check session: {}", e);
+                false
+            },
+        }
+    }
+
+    pub async fn revoke(user_id: ObjectId, session_id: &str, repo: &dyn UserRepository) -> Result<(), AppError> {
+        repo.remove_session(user_id, session_id).await?;
+
+        info!("Session {} of user {} revoked", session_id, user_id);
+        Ok(())
+    }
+
+    pub async fn revoke_all(user_id: ObjectId, repo: &dyn UserRepository) -> Result<(), AppError> {
+        repo.clear_sessions(user_id).await?;
+
+        info!("All sessions of user {} revoked", user_id);
+        Ok(())
+    }
+
+    pub async fn list(user_id: ObjectId, current_session: Option<&str>, repo: &dyn UserRepository) -> Result<Vec<SessionInfo>, AppError> {
+        let now = Utc::now().timestamp() as f64;
+
+        let user = repo
+            .find_user(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let mut sessions: Vec<SessionInfo> = user.tokens.into_iter()
+            .filter(|s| s.expires_at > now)
+            .map(|s| SessionInfo {
+                current: current_session == Some(s.id.as_str()),
+                id: s.id,
+                user_agent: s.user_agent,
+                created_at: s.created_at,
+                last_used_at: s.last_used_at,
+                expires_at: s.expires_at,
+            })
+            .collect();
+        sessions.sort_by(|a, b| b.last_used_at.total_cmp(&a.last_used_at));
+
+        Ok(sessions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_token_carries_session_and_hashed_secret() {
+        let secret = generate_secret();
+        let token = format!("{}.{}", ObjectId::new().to_hex(), secret);
+
+        let (_, parsed_secret) = split_refresh_token(&token).unwrap();
+        assert_eq!(hash_secret(parsed_secret), hash_secret(&secret));
+        assert_ne!(hash_secret(&secret), hash_secret(&generate_secret()));
+        assert!(split_refresh_token("no-separator").is_none());
+        assert!(split_refresh_token(".secret").is_none());
+    }
+}
@@ -0,0 +1,82 @@
+use actix_web::{get, post, web, HttpResponse};
+use crate::modules::backtest::backtest_service::{BacktestRequest, BacktestService};
+use crate::modules::backtest::backtest_schema::BacktestJob;
+use crate::db::repositories::Storage;
+use serde::{Deserialize};
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::openapi::ErrorResponse;
+use crate::middleware::current_user::CurrentUser;
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
+
+#[derive(Deserialize)]
+struct ObjectIdPath {
+    id: String,
+}
+
+// Crea el trabajo y lo ejecuta en segundo plano; el resultado se consulta en /backtests/{id}
+#[utoipa::path(
+    tag = "backtests",
+    params(("id" = String, Path, description = "Arbitrage strategy ID")),
+    request_body = BacktestRequest,
+    responses(
+        (status = 202, description = "Backtest job created and running", body = ApiResponse<BacktestJob>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[post("/arbitrage-strategies/{id}/backtest", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn create_backtest(
+    user: CurrentUser,
+    path: web::Path<ObjectIdPath>,
+    request: web::Json<BacktestRequest>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "arbitrage strategy")?;
+
+    let job = BacktestService::create_job(id, user.id, request.into_inner(), storage.get_ref()).await?;
+    if let Some(job_id) = job.id {
+        let storage = storage.clone();
+        actix_web::rt::spawn(async move {
+            BacktestService::run_job(job_id, storage.get_ref()).await;
+        });
+    }
+    Ok(HttpResponse::Accepted().json(ApiResponse::success("Backtest started successfully", job)))
+}
+
+#[utoipa::path(
+    tag = "backtests",
+    params(("id" = String, Path, description = "Arbitrage strategy ID"), PageQuery),
+    responses(
+        (status = 200, description = "Page of backtests of the strategy", body = ApiResponse<Paginated<BacktestJob>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/arbitrage-strategies/{id}/backtests")]
+pub async fn get_strategy_backtests(user: CurrentUser, path: web::Path<ObjectIdPath>, page: web::Query<PageQuery>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "arbitrage strategy")?;
+
+    let jobs = BacktestService::get_jobs_for_strategy(id, user.id, &page, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Backtests retrieved successfully", jobs)))
+}
+
+#[utoipa::path(
+    tag = "backtests",
+    params(("id" = String, Path, description = "Backtest ID")),
+    responses(
+        (status = 200, description = "Backtest job, with the report once finished", body = ApiResponse<BacktestJob>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/backtests/{id}")]
+pub async fn get_backtest(user: CurrentUser, path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "backtest")?;
+
+    let job = BacktestService::get_owned_job(id, user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Backtest retrieved successfully", job)))
+}
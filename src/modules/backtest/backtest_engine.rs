@@ -0,0 +1,249 @@
+use mongodb::bson::oid::ObjectId;
+use crate::helpers::app_error::AppError;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageType, StatisticalParams};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::PopulatedLeg;
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::{ArbitrageEvaluationService, RouteSelection};
+use crate::modules::arbitrage_strategy::statistical_arbitrage_service::{mean_std_dev, mid_price, signal_for, z_score, StatisticalSignal};
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use crate::modules::backtest::backtest_schema::{BacktestReport, EquityPoint};
+use crate::modules::market_data::market_data_schema::MarketQuote;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use std::collections::{HashMap, VecDeque};
+
+pub struct BacktestConfig {
+    pub amount: f64,
+    pub min_profit: f64,
+}
+
+// Acumula oportunidades y construye el informe
+struct ReportBuilder {
+    amount: f64,
+    snapshots: usize,
+    spreads: Vec<f64>,
+    durations: Vec<f64>,
+    pnl: f64,
+    equity_curve: Vec<EquityPoint>,
+}
+
+impl ReportBuilder {
+    fn new(amount: f64) -> Self {
+        Self { amount, snapshots: 0, spreads: Vec::new(), durations: Vec::new(), pnl: 0.0, equity_curve: Vec::new() }
+    }
+
+    fn start(&mut self, timestamp: f64) {
+        if self.equity_curve.is_empty() {
+            self.equity_curve.push(EquityPoint { timestamp, equity: self.amount });
+        }
+    }
+
+    fn realize(&mut self, timestamp: f64, pnl: f64) {
+        self.pnl += pnl;
+        self.equity_curve.push(EquityPoint { timestamp, equity: self.amount + self.pnl });
+    }
+
+    fn build(self) -> BacktestReport {
+        let average = |values: &[f64]| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+        let max = |values: &[f64]| values.iter().copied().fold(0.0, f64::max);
+
+        BacktestReport {
+            snapshots: self.snapshots,
+            opportunities: self.durations.len(),
+            average_spread: average(&self.spreads),
+            max_spread: max(&self.spreads),
+            average_duration: average(&self.durations),
+            max_duration: max(&self.durations),
+            theoretical_pnl: self.pnl,
+            equity_curve: self.equity_curve,
+        }
+    }
+}
+
+// Agrupa los snapshots (ordenados por timestamp) en instantes con la última cotización de cada par
+fn replay<F>(snapshots: &[MarketQuote], mut on_tick: F)
+where
+    F: FnMut(f64, &HashMap<ObjectId, MarketQuote>),
+{
+    let mut latest: HashMap<ObjectId, MarketQuote> = HashMap::new();
+    for (index, snapshot) in snapshots.iter().enumerate() {
+        latest.insert(snapshot._market_pair, snapshot.clone());
+        let last_of_instant = snapshots.get(index + 1).is_none_or(|next| next.timestamp != snapshot.timestamp);
+        if last_of_instant {
+            on_tick(snapshot.timestamp, &latest);
+        }
+    }
+}
+
+pub fn run_backtest(
+    arbitrage_type: &ArbitrageType,
+    legs: &[PopulatedLeg],
+    statistical: Option<&StatisticalParams>,
+    snapshots: &[MarketQuote],
+    config: &BacktestConfig,
+    equivalences: &EquivalenceRegistry
+) -> Result<BacktestReport, AppError> {
+    if config.amount <= 0.0 {
+        return Err(AppError::Validation("Amount must be greater than zero".to_string()));
+    }
+
+    match (arbitrage_type, statistical, legs) {
+        (ArbitrageType::Statistical, Some(params), [leg]) => {
+            Ok(run_statistical(&leg.market_pair, params.lookback_days, params.entry_z, params.exit_z, snapshots, config))
+        },
+        (ArbitrageType::Statistical, _, _) => Err(AppError::Validation("Statistical strategies need one leg and their parameters".to_string())),
+        _ => run_cycle(arbitrage_type, legs, snapshots, config, equivalences),
+    }
+}
+
+// Una oportunidad empieza cuando el beneficio neto alcanza min_profit y termina cuando deja de hacerlo.
+// Se ejecuta una vez, al abrirse, con el importe configurado.
+fn run_cycle(
+    arbitrage_type: &ArbitrageType,
+    legs: &[PopulatedLeg],
+    snapshots: &[MarketQuote],
+    config: &BacktestConfig,
+    equivalences: &EquivalenceRegistry
+) -> Result<BacktestReport, AppError> {
+    // Valida la forma del ciclo antes de recorrer los datos
+    for route in ArbitrageEvaluationService::cycle_routes(arbitrage_type, legs, RouteSelection::Stored)? {
+        ArbitrageEvaluationService::check_route(&route, equivalences)?;
+    }
+
+    let mut report = ReportBuilder::new(config.amount);
+    let mut open_since: Option<f64> = None;
+    let mut last_timestamp = None;
+
+    replay(snapshots, |timestamp, latest| {
+        let Ok(result) = ArbitrageEvaluationService::evaluate(arbitrage_type, legs, config.amount, RouteSelection::Stored, latest, equivalences) else {
+            return;
+        };
+        report.snapshots += 1;
+        report.start(timestamp);
+        last_timestamp = Some(timestamp);
+
+        if result.profit_percentage >= config.min_profit {
+            report.spreads.push(result.gross_profit_percentage);
+            if open_since.is_none() {
+                open_since = Some(timestamp);
+                report.realize(timestamp, result.profit);
+            }
+        } else if let Some(since) = open_since.take() {
+            report.durations.push(timestamp - since);
+        }
+    });
+
+    if let (Some(since), Some(last)) = (open_since, last_timestamp) {
+        report.durations.push(last - since);
+    }
+
+    Ok(report.build())
+}
+
+// Reversión a la media: entra cuando |z| >= entry_z y cierra cuando |z| <= exit_z, pagando la
+// comisión taker en ambas operaciones. El spread es la desviación porcentual respecto a la media.
+fn run_statistical(
+    pair: &PopulatedMarketPair,
+    lookback_days: u32,
+    entry_z: f64,
+    exit_z: f64,
+    snapshots: &[MarketQuote],
+    config: &BacktestConfig
+) -> BacktestReport {
+    let lookback = f64::from(lookback_days) * 86_400.0;
+    let fee = pair.effective_taker_fee();
+    let mut report = ReportBuilder::new(config.amount);
+    let mut window: VecDeque<(f64, f64)> = VecDeque::new();
+    // (timestamp de entrada, precio de entrada, compra)
+    let mut position: Option<(f64, f64, bool)> = None;
+    let mut last_timestamp = None;
+
+    for quote in snapshots.iter().filter(|q| Some(q._market_pair) == pair.id) {
+        let mid = mid_price(quote);
+        window.push_back((quote.timestamp, mid));
+        while window.front().is_some_and(|(t, _)| *t < quote.timestamp - lookback) {
+            window.pop_front();
+        }
+        if window.len() < 2 {
+            continue;
+        }
+        let prices: Vec<f64> = window.iter().map(|(_, p)| *p).collect();
+        let Some((mean, std_dev)) = mean_std_dev(&prices) else { continue };
+        if std_dev == 0.0 {
+            continue;
+        }
+
+        report.snapshots += 1;
+        report.start(quote.timestamp);
+        last_timestamp = Some(quote.timestamp);
+        let z = z_score(mid, mean, std_dev);
+
+        match (position, signal_for(z, entry_z, exit_z)) {
+            (None, StatisticalSignal::Entry) => {
+                let buy = z < 0.0;
+                let price = if buy { quote.ask } else { quote.bid };
+                report.spreads.push((mid - mean).abs() / mean * 100.0);
+                position = Some((quote.timestamp, price, buy));
+            },
+            (Some((since, entry_price, buy)), StatisticalSignal::Exit) => {
+                let ratio = if buy { quote.bid / entry_price } else { entry_price / quote.ask };
+                let final_amount = config.amount * ratio * (1.0 - fee).powi(2);
+                report.durations.push(quote.timestamp - since);
+                report.realize(quote.timestamp, final_amount - config.amount);
+                position = None;
+            },
+            _ => {},
+        }
+    }
+
+    // Una posición sin cerrar cuenta como oportunidad pero no realiza beneficio
+    if let (Some((since, _, _)), Some(last)) = (position, last_timestamp) {
+        report.durations.push(last - since);
+    }
+
+    report.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::{exchange, leg, pair, round};
+    use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::TradeSide;
+
+    fn snapshot(pair: &PopulatedMarketPair, timestamp: f64, bid: f64, ask: f64) -> MarketQuote {
+        MarketQuote {
+            id: None,
+            _market_pair: pair.id.unwrap(),
+            _exchange: pair.exchange.id.unwrap(),
+            bid,
+            ask,
+            bid_size: 0.0,
+            ask_size: 0.0,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn counts_opportunities_and_their_duration() {
+        // Spread del 2% entre A y B en t=10..20, cerrado en t=30
+        let (a, b) = (exchange("A"), exchange("B"));
+        let (pair1, pair2) = (pair(&a, "ETH", "USDT"), pair(&b, "ETH", "USDT"));
+        let snapshots = vec![
+            snapshot(&pair1, 0.0, 100.0, 100.0),
+            snapshot(&pair2, 0.0, 100.0, 100.0),
+            snapshot(&pair2, 10.0, 102.0, 102.0),
+            snapshot(&pair2, 20.0, 101.0, 101.0),
+            snapshot(&pair2, 30.0, 100.0, 100.0),
+        ];
+        let legs = vec![leg(pair1, TradeSide::Buy), leg(pair2, TradeSide::Sell)];
+        let config = BacktestConfig { amount: 1000.0, min_profit: 0.5 };
+
+        let report = run_backtest(&ArbitrageType::Exchange, &legs, None, &snapshots, &config, &EquivalenceRegistry::default()).unwrap();
+
+        assert_eq!(report.snapshots, 4);
+        assert_eq!(report.opportunities, 1);
+        assert_eq!(report.max_duration, 20.0);
+        assert_eq!(round(report.max_spread, 6), 2.0);
+        assert_eq!(round(report.average_spread, 6), 1.5);
+        assert_eq!(round(report.theoretical_pnl, 6), 20.0);
+        assert_eq!(report.equity_curve.last().map(|p| round(p.equity, 6)), Some(1020.0));
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::market_data::market_data_schema::OrderBookLevel;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum BacktestStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+// Origen de los snapshots: la colección "market_quote_history" o un archivo NDJSON
+// dentro de BACKTEST_DATA_DIR
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
+#[serde(tag = "kind")]
+pub enum BacktestSource {
+    #[default]
+    History,
+    File { name: String },
+}
+
+// Línea de un archivo NDJSON. De los libros de órdenes solo se usa el mejor nivel de cada lado.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SnapshotRecord {
+    Ticker {
+        market_pair: ObjectId,
+        bid: f64,
+        ask: f64,
+        timestamp: f64,
+    },
+    OrderBook {
+        market_pair: ObjectId,
+        bids: Vec<OrderBookLevel>,
+        asks: Vec<OrderBookLevel>,
+        timestamp: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct EquityPoint {
+    pub timestamp: f64,
+    pub equity: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct BacktestReport {
+    // Instantes evaluados (con cotización de todos los pares de la estrategia)
+    pub snapshots: usize,
+    pub opportunities: usize,
+    // Spread bruto en porcentaje mientras hubo oportunidad
+    pub average_spread: f64,
+    pub max_spread: f64,
+    // Segundos que cada oportunidad permaneció abierta
+    pub average_duration: f64,
+    pub max_duration: f64,
+    // Beneficio teórico neto de comisiones, ejecutando una vez por oportunidad con `amount`
+    pub theoretical_pnl: f64,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+// Trabajo de backtest (colección "backtests")
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct BacktestJob {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _arbitrage_strategy: ObjectId,
+    pub status: BacktestStatus,
+    pub source: BacktestSource,
+    pub from: f64,
+    pub to: f64,
+    pub amount: f64,
+    pub min_profit: f64,
+    pub report: Option<BacktestReport>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub created_at: f64,
+    #[serde(default)]
+    pub updated_at: f64,
+}
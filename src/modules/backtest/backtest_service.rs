@@ -0,0 +1,189 @@
+use crate::db::repositories::{BacktestRepository, Storage};
+use crate::helpers::app_error::AppError;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::Leg;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use crate::modules::backtest::backtest_engine::{run_backtest, BacktestConfig};
+use crate::modules::backtest::backtest_schema::{BacktestJob, BacktestReport, BacktestSource, BacktestStatus, SnapshotRecord};
+use crate::modules::market_data::market_data_schema::MarketQuote;
+use crate::modules::market_data::market_data_service::MarketDataService;
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use tracing::{error, info};
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct BacktestRequest {
+    pub from: f64,
+    pub to: f64,
+    #[serde(default)]
+    pub source: BacktestSource,
+    pub amount: Option<f64>,
+    pub min_profit: Option<f64>,
+}
+
+pub struct BacktestService;
+
+impl BacktestService {
+    // Crea el trabajo en estado Pending; la ejecución la lanza el controlador con run_job
+    pub async fn create_job(strategy_id: ObjectId, owner: ObjectId, request: BacktestRequest, storage: &dyn Storage) -> Result<BacktestJob, AppError> {
+        if request.from >= request.to {
+            return Err(AppError::Validation("from must be earlier than to".to_string()));
+        }
+        if let BacktestSource::File { name } = &request.source {
+            Self::data_file_path(name)?;
+        }
+        ArbitrageStrategyService::get_owned_arbitrage_strategy(strategy_id, owner, storage).await?;
+
+        let now = Utc::now().timestamp() as f64;
+        let job = BacktestJob {
+            id: None,
+            _arbitrage_strategy: strategy_id,
+            status: BacktestStatus::Pending,
+            source: request.source,
+            from: request.from,
+            to: request.to,
+            amount: request.amount.unwrap_or(1.0),
+            min_profit: request.min_profit.unwrap_or(0.0),
+            report: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        storage.insert_backtest(job).await
+    }
+
+    pub async fn run_job(job_id: ObjectId, storage: &dyn Storage) {
+        if let Err(e) = Self::set_status(job_id, BacktestStatus::Running, None, None, storage).await {
+            error!("Failed to start backtest {}: {}", job_id, e);
+            return;
+        }
+
+        let outcome = match Self::get_job(job_id, storage).await {
+            Ok(job) => Self::execute(&job, storage).await,
+            Err(e) => Err(e),
+        };
+
+        let saved = match outcome {
+            Ok(report) => {
+                info!("Backtest {} completed with {} opportunities", job_id, report.opportunities);
+                Self::set_status(job_id, BacktestStatus::Completed, Some(report), None, storage).await
+            },
+            Err(err) => {
+                error!("Backtest {} failed: {}", job_id, err);
+                Self::set_status(job_id, BacktestStatus::Failed, None, Some(err.to_string()), storage).await
+            },
+        };
+        if let Err(e) = saved {
+            error!("Failed to save backtest {} result: {}", job_id, e);
+        }
+    }
+
+    async fn execute(job: &BacktestJob, storage: &dyn Storage) -> Result<BacktestReport, AppError> {
+        let strategy = ArbitrageStrategyService::get_arbitrage_strategy(job._arbitrage_strategy, storage).await?;
+        let legs = ArbitrageStrategyService::populate_legs(&strategy.legs, storage).await?;
+        let pair_ids = ArbitrageStrategyService::leg_pair_ids(&strategy.legs);
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+
+        let snapshots = match &job.source {
+            BacktestSource::History => MarketDataService::get_quote_history_between(&pair_ids, job.from, job.to, storage).await?,
+            BacktestSource::File { name } => Self::load_file(name, &strategy.legs, job.from, job.to)?,
+        };
+        if snapshots.is_empty() {
+            return Err(AppError::Validation("No market data in the requested time range".to_string()));
+        }
+
+        let config = BacktestConfig { amount: job.amount, min_profit: job.min_profit };
+        run_backtest(&strategy.arbitrage_type, &legs, strategy.statistical.as_ref(), &snapshots, &config, &equivalences)
+    }
+
+    // Solo se aceptan nombres de archivo dentro de BACKTEST_DATA_DIR
+    fn data_file_path(name: &str) -> Result<PathBuf, AppError> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            return Err(AppError::Validation("Invalid backtest data file name".to_string()));
+        }
+        let dir = env::var("BACKTEST_DATA_DIR").unwrap_or_else(|_| "backtest_data".to_string());
+        Ok(PathBuf::from(dir).join(name))
+    }
+
+    // Lee el NDJSON y se queda con los snapshots de los pares de la estrategia dentro del rango
+    fn load_file(name: &str, legs: &[Leg], from: f64, to: f64) -> Result<Vec<MarketQuote>, AppError> {
+        let path = Self::data_file_path(name)?;
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| AppError::Validation(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        let exchanges: HashMap<ObjectId, ObjectId> = legs.iter()
+            .map(|leg| (leg.market_pair, leg.exchange))
+            .collect();
+
+        let mut snapshots = Vec::new();
+        for (number, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let record: SnapshotRecord = serde_json::from_str(line)
+                .map_err(|e| AppError::Validation(format!("Invalid snapshot on line {}: {}", number + 1, e)))?;
+
+            let (market_pair, bid, ask, timestamp) = match record {
+                SnapshotRecord::Ticker { market_pair, bid, ask, timestamp } => (market_pair, bid, ask, timestamp),
+                SnapshotRecord::OrderBook { market_pair, bids, asks, timestamp } => {
+                    let best_bid = bids.iter().map(|l| l.price).fold(f64::NAN, f64::max);
+                    let best_ask = asks.iter().map(|l| l.price).fold(f64::NAN, f64::min);
+                    if best_bid.is_nan() || best_ask.is_nan() {
+                        continue;
+                    }
+                    (market_pair, best_bid, best_ask, timestamp)
+                },
+            };
+            let Some(exchange) = exchanges.get(&market_pair) else { continue };
+            if timestamp < from || timestamp > to {
+                continue;
+            }
+
+            snapshots.push(MarketQuote {
+                id: None,
+                _market_pair: market_pair,
+                _exchange: *exchange,
+                bid,
+                ask,
+                bid_size: 0.0,
+                ask_size: 0.0,
+                timestamp,
+            });
+        }
+
+        snapshots.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        Ok(snapshots)
+    }
+
+    async fn set_status(
+        job_id: ObjectId,
+        status: BacktestStatus,
+        report: Option<BacktestReport>,
+        error_message: Option<String>,
+        repo: &dyn BacktestRepository
+    ) -> Result<(), AppError> {
+        repo.set_backtest_status(job_id, status, report, error_message, Utc::now().timestamp() as f64).await
+    }
+
+    pub async fn get_job(id: ObjectId, repo: &dyn BacktestRepository) -> Result<BacktestJob, AppError> {
+        repo.find_backtest(id).await?
+            .ok_or_else(|| AppError::NotFound("Backtest not found".to_string()))
+    }
+
+    // Un backtest es visible para quien posee la estrategia
+    pub async fn get_owned_job(id: ObjectId, owner: ObjectId, storage: &dyn Storage) -> Result<BacktestJob, AppError> {
+        let job = Self::get_job(id, storage).await?;
+        ArbitrageStrategyService::get_owned_arbitrage_strategy(job._arbitrage_strategy, owner, storage).await
+            .map_err(|_| AppError::NotFound("Backtest not found".to_string()))?;
+        Ok(job)
+    }
+
+    pub async fn get_jobs_for_strategy(strategy_id: ObjectId, owner: ObjectId, page: &PageQuery, storage: &dyn Storage) -> Result<Paginated<BacktestJob>, AppError> {
+        ArbitrageStrategyService::get_owned_arbitrage_strategy(strategy_id, owner, storage).await?;
+        storage.list_strategy_backtests(strategy_id, page).await
+    }
+}
@@ -0,0 +1,12 @@
+pub mod backtest_schema;
+pub mod backtest_engine;
+pub mod backtest_service;
+pub mod backtest_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(backtest_controller::create_backtest);
+    cfg.service(backtest_controller::get_strategy_backtests);
+    cfg.service(backtest_controller::get_backtest);
+}
@@ -1,71 +1,102 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, put, delete, web, HttpResponse};
 use crate::modules::exchange::exchange_service::ExchangeService;
-use crate::db::mongodb::MongoDbContext;
+use crate::db::repositories::Storage;
+use crate::modules::integrity::integrity_schema::{CatalogDocuments, DeleteQuery};
 use crate::modules::exchange::exchange_schema::Exchange;
-use mongodb::bson::oid::ObjectId;
+use crate::helpers::app_error::{parse_object_id, AppError};
 use serde::{Deserialize};
 use crate::modules::auth::auth_response::ApiResponse;
-use tracing::{ error};
+use crate::openapi::ErrorResponse;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
 
 #[derive(Deserialize)]
 struct ObjectIdPath {
     id: String,
 }
 
-#[post("/exchanges")]
-pub async fn create_exchange(exchange: web::Json<Exchange>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    match ExchangeService::create_exchange(exchange.into_inner(), &db_context).await {
-        Ok(exchange) => HttpResponse::Ok().json(ApiResponse::success("Exchange created successfully", exchange)),
-        Err(err) => {
-            error!("Failed to create exchange: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "exchanges",
+    request_body = Exchange,
+    responses(
+        (status = 200, description = "Exchange created", body = ApiResponse<Exchange>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 409, description = "Conflict with an existing record", body = ErrorResponse),
+    ),
+)]
+#[post("/exchanges", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn create_exchange(exchange: web::Json<Exchange>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let exchange = ExchangeService::create_exchange(exchange.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Exchange created successfully", exchange)))
 }
 
+#[utoipa::path(
+    tag = "exchanges",
+    params(("id" = String, Path, description = "Exchange ID")),
+    responses(
+        (status = 200, description = "Exchange", body = ApiResponse<Exchange>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
 #[get("/exchanges/{id}")]
-pub async fn get_exchange(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match ExchangeService::get_exchange(id, &db_context).await {
-        Ok(exchange) => HttpResponse::Ok().json(ApiResponse::success("Exchange retrieved successfully", exchange)),
-        Err(err) => {
-            error!("Failed to retrieve exchange: {}", err);
-            HttpResponse::NotFound().json(ApiResponse::<String>::error(&err))
-        },
-    }
+pub async fn get_exchange(path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "exchange")?;
+    let exchange = ExchangeService::get_exchange(id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Exchange retrieved successfully", exchange)))
 }
 
-#[put("/exchanges/{id}")]
-pub async fn update_exchange(path: web::Path<ObjectIdPath>, exchange: web::Json<Exchange>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match ExchangeService::update_exchange(id, exchange.into_inner(), &db_context).await {
-        Ok(exchange) => HttpResponse::Ok().json(ApiResponse::success("Exchange updated successfully", exchange)),
-        Err(err) => {
-            error!("Failed to update exchange: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "exchanges",
+    params(("id" = String, Path, description = "Exchange ID")),
+    request_body = Exchange,
+    responses(
+        (status = 200, description = "Exchange updated", body = ApiResponse<Exchange>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[put("/exchanges/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn update_exchange(path: web::Path<ObjectIdPath>, exchange: web::Json<Exchange>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "exchange")?;
+    let exchange = ExchangeService::update_exchange(id, exchange.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Exchange updated successfully", exchange)))
 }
 
-#[delete("/exchanges/{id}")]
-pub async fn delete_exchange(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match ExchangeService::delete_exchange(id, &db_context).await {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Exchange deleted successfully", ())),
-        Err(err) => {
-            error!("Failed to delete exchange: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "exchanges",
+    params(("id" = String, Path, description = "Exchange ID"), DeleteQuery),
+    responses(
+        (status = 200, description = "Exchange deleted, with the dependents removed by cascade", body = ApiResponse<CatalogDocuments>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Still referenced and cascade not requested", body = ErrorResponse),
+    ),
+)]
+#[delete("/exchanges/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn delete_exchange(
+    path: web::Path<ObjectIdPath>,
+    query: web::Query<DeleteQuery>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "exchange")?;
+    let cascaded = ExchangeService::delete_exchange(id, query.cascade.unwrap_or(false), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Exchange deleted successfully", cascaded)))
 }
 
+#[utoipa::path(
+    tag = "exchanges",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Page of exchanges", body = ApiResponse<Paginated<Exchange>>),
+    ),
+)]
 #[get("/exchanges")]
-pub async fn get_all_exchanges(db_context: web::Data<MongoDbContext>) -> impl Responder {
-    match ExchangeService::get_all_exchanges(&db_context).await {
-        Ok(exchanges) => HttpResponse::Ok().json(ApiResponse::success("Exchanges retrieved successfully", exchanges)),
-        Err(err) => {
-            error!("Failed to retrieve exchanges: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+pub async fn get_all_exchanges(page: web::Query<PageQuery>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let exchanges = ExchangeService::get_all_exchanges(storage.get_ref(), &page).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Exchanges retrieved successfully", exchanges)))
 }
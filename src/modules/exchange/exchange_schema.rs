@@ -1,13 +1,21 @@
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
 use mongodb::bson::oid::ObjectId;
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, ToSchema)]
 pub struct Exchange {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
     pub name: String,
     pub short_name: String,
     pub url: String,
+    // Comisiones de trading como fracción del importe (0.001 = 0.1%)
+    #[serde(default)]
+    pub maker_fee: f64,
+    #[serde(default)]
+    pub taker_fee: f64,
     pub created_at: f64,
     pub updated_at: f64,
 }
@@ -1,16 +1,34 @@
-use crate::db::mongodb::MongoDbContext;
-use mongodb::bson::{doc, oid::ObjectId};
+use crate::helpers::app_error::{AppError, FieldError};
+use crate::db::repositories::{ExchangeRepository, Storage};
+use crate::modules::integrity::integrity_schema::CatalogDocuments;
+use crate::modules::integrity::integrity_service::{CatalogItem, IntegrityService};
+use mongodb::bson::oid::ObjectId;
 use crate::modules::exchange::exchange_schema::Exchange;
-use tracing::error;
 use chrono::Utc;
-use futures::TryStreamExt;
+use crate::helpers::pagination::{PageQuery, Paginated};
 
 pub struct ExchangeService;
 
+fn not_found() -> AppError {
+    AppError::NotFound("Exchange not found".to_string())
+}
+
 impl ExchangeService {
-    pub async fn create_exchange(exchange: Exchange, db_context: &MongoDbContext) -> Result<Exchange, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Exchange>("exchanges");
+    fn validate(exchange: &Exchange) -> Result<(), AppError> {
+        let errors: Vec<FieldError> = [("maker_fee", exchange.maker_fee), ("taker_fee", exchange.taker_fee)]
+            .into_iter()
+            .filter(|(_, fee)| !(0.0..1.0).contains(fee))
+            .map(|(field, _)| FieldError::new(field, "Must be a fraction between 0 and 1"))
+            .collect();
+        if !errors.is_empty() {
+            return Err(AppError::invalid_fields(errors));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_exchange(exchange: Exchange, repo: &dyn ExchangeRepository) -> Result<Exchange, AppError> {
+        Self::validate(&exchange)?;
 
         let now = Utc::now().timestamp() as f64;
         let new_exchange = Exchange {
@@ -19,98 +37,30 @@ impl ExchangeService {
             ..exchange
         };
 
-        let insert_result = collection.insert_one(new_exchange.clone()).await
-            .map_err(|e| {
-                error!("Failed to insert exchange: {}", e);
-                e.to_string()
-            })?;
-        
-        let new_exchange = collection.find_one(doc! { "_id": insert_result.inserted_id }).await
-            .map_err(|e| {
-                error!("Failed to fetch created exchange: {}", e);
-                e.to_string()
-            })?
-            .ok_or_else(|| {
-                let msg = "Failed to fetch created exchange".to_string();
-                error!("{}", msg);
-                msg
-            })?;
-        
-        Ok(new_exchange)
+        repo.insert_exchange(new_exchange).await
     }
 
-    pub async fn get_exchange(id: ObjectId, db_context: &MongoDbContext) -> Result<Exchange, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Exchange>("exchanges");
-
-        let exchange = collection.find_one(doc! { "_id": id }).await
-            .map_err(|e| {
-                error!("Failed to fetch exchange: {}", e);
-                e.to_string()
-            })?
-            .ok_or_else(|| {
-                let msg = "Exchange not found".to_string();
-                error!("{}", msg);
-                msg
-            })?;
-
-        Ok(exchange)
+    pub async fn get_exchange(id: ObjectId, repo: &dyn ExchangeRepository) -> Result<Exchange, AppError> {
+        repo.find_exchange(id).await?.ok_or_else(not_found)
     }
 
-    pub async fn update_exchange(id: ObjectId, updated_exchange: Exchange, db_context: &MongoDbContext) -> Result<Exchange, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Exchange>("exchanges");
+    pub async fn update_exchange(id: ObjectId, updated_exchange: Exchange, repo: &dyn ExchangeRepository) -> Result<Exchange, AppError> {
+        Self::validate(&updated_exchange)?;
 
-        let now = Utc::now().timestamp() as f64;
-        let update_doc = doc! {
-            "$set": {
-                "name": updated_exchange.name,
-                "short_name": updated_exchange.short_name,
-                "url": updated_exchange.url,
-                "updated_at": now,
-            }
+        let updated_exchange = Exchange {
+            updated_at: Utc::now().timestamp() as f64,
+            ..updated_exchange
         };
 
-        collection.update_one(doc! { "_id": id }, update_doc).await
-            .map_err(|e| {
-                error!("Failed to update exchange: {}", e);
-                e.to_string()
-            })?;
-
-        Self::get_exchange(id, db_context).await
+        repo.update_exchange(id, updated_exchange).await?.ok_or_else(not_found)
     }
 
-    pub async fn delete_exchange(id: ObjectId, db_context: &MongoDbContext) -> Result<(), String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Exchange>("exchanges");
-
-        collection.delete_one(doc! { "_id": id }).await
-            .map_err(|e| {
-                error!("Failed to delete exchange: {}", e);
-                e.to_string()
-            })?;
-
-        Ok(())
+    // Con dependientes falla con Conflict salvo que cascade sea true; devuelve lo borrado en cascada
+    pub async fn delete_exchange(id: ObjectId, cascade: bool, storage: &dyn Storage) -> Result<CatalogDocuments, AppError> {
+        IntegrityService::delete(CatalogItem::Exchange(id), cascade, storage).await
     }
 
-    pub async fn get_all_exchanges(db_context: &MongoDbContext) -> Result<Vec<Exchange>, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<Exchange>("exchanges");
-
-        let mut cursor = collection.find(doc! {}).await
-            .map_err(|e| {
-                error!("Failed to fetch all exchanges: {}", e);
-                e.to_string()
-            })?;
-
-        let mut exchanges = Vec::new();
-        while let Some(exchange) = cursor.try_next().await.map_err(|e| {
-            error!("Failed to iterate through exchanges: {}", e);
-            e.to_string()
-        })? {
-            exchanges.push(exchange);
-        }
-
-        Ok(exchanges)
+    pub async fn get_all_exchanges(repo: &dyn ExchangeRepository, page: &PageQuery) -> Result<Paginated<Exchange>, AppError> {
+        repo.list_exchanges(page).await
     }
 }
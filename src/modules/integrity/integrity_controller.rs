@@ -0,0 +1,22 @@
+use actix_web::{get, web, HttpResponse};
+use crate::modules::integrity::integrity_service::IntegrityService;
+use crate::modules::integrity::integrity_schema::OrphanReport;
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::openapi::ErrorResponse;
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
+
+#[utoipa::path(
+    tag = "integrity",
+    responses(
+        (status = 200, description = "Documents whose references no longer exist", body = ApiResponse<OrphanReport>),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+    ),
+)]
+#[get("/integrity/orphans", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn get_orphans(storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let orphans = IntegrityService::orphans(storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Orphaned documents retrieved successfully", orphans)))
+}
@@ -0,0 +1,47 @@
+use serde::{Serialize, Deserialize};
+use utoipa::{IntoParams, ToSchema};
+use crate::openapi::ObjectIdSchema;
+use mongodb::bson::oid::ObjectId;
+
+// Ids de documentos del catálogo, de las estrategias que los usan y de los registros de esas
+// estrategias. Sirve tanto para los dependientes de un borrado como para el informe de huérfanos.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct CatalogDocuments {
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub exchanges: Vec<ObjectId>,
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub assets: Vec<ObjectId>,
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub market_pairs: Vec<ObjectId>,
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub strategies: Vec<ObjectId>,
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub alert_rules: Vec<ObjectId>,
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub backtests: Vec<ObjectId>,
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub paper_trades: Vec<ObjectId>,
+}
+
+impl CatalogDocuments {
+    pub fn is_empty(&self) -> bool {
+        self.exchanges.is_empty() && self.assets.is_empty() && self.market_pairs.is_empty() && self.strategies.is_empty()
+            && self.alert_rules.is_empty() && self.backtests.is_empty() && self.paper_trades.is_empty()
+    }
+}
+
+// Informe de /integrity/orphans: documentos con referencias rotas y estrategias que siguen con
+// el formato de details anterior porque la migración no pudo convertirlas
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct OrphanReport {
+    #[serde(flatten)]
+    pub documents: CatalogDocuments,
+    #[schema(value_type = Vec<ObjectIdSchema>)]
+    pub unmigrated_strategies: Vec<ObjectId>,
+}
+
+// ?cascade=true borra también los documentos que dependen del eliminado
+#[derive(Deserialize, Debug, Default, IntoParams)]
+pub struct DeleteQuery {
+    pub cascade: Option<bool>,
+}
@@ -0,0 +1,114 @@
+use crate::helpers::app_error::AppError;
+use crate::db::repositories::Storage;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::integrity::integrity_schema::{CatalogDocuments, OrphanReport};
+
+pub struct IntegrityService;
+
+// Documento del catálogo que se quiere borrar
+#[derive(Debug, Clone, Copy)]
+pub enum CatalogItem {
+    Exchange(ObjectId),
+    Asset(ObjectId),
+    MarketPair(ObjectId),
+}
+
+impl CatalogItem {
+    fn label(&self) -> &'static str {
+        match self {
+            CatalogItem::Exchange(_) => "Exchange",
+            CatalogItem::Asset(_) => "Asset",
+            CatalogItem::MarketPair(_) => "Market pair",
+        }
+    }
+}
+
+fn describe(ids: &[ObjectId], name: &str) -> Option<String> {
+    if ids.is_empty() {
+        return None;
+    }
+    let ids: Vec<String> = ids.iter().map(|id| id.to_hex()).collect();
+    Some(format!("{} {} ({})", ids.len(), name, ids.join(", ")))
+}
+
+impl IntegrityService {
+    // Todo lo que dejaría de ser válido si se borra el documento, sin incluirlo a él
+    pub async fn dependents(item: CatalogItem, storage: &dyn Storage) -> Result<CatalogDocuments, AppError> {
+        let mut documents = CatalogDocuments::default();
+        match item {
+            CatalogItem::Exchange(id) => {
+                documents.assets = storage.find_asset_ids_by_exchange(id).await?;
+                documents.market_pairs = storage.find_market_pair_ids_referencing(Some(id), &documents.assets).await?;
+                documents.strategies = storage.find_strategy_ids_referencing(&documents.market_pairs).await?;
+            }
+            CatalogItem::Asset(id) => {
+                documents.market_pairs = storage.find_market_pair_ids_referencing(None, &[id]).await?;
+                documents.strategies = storage.find_strategy_ids_referencing(&documents.market_pairs).await?;
+            }
+            CatalogItem::MarketPair(id) => {
+                documents.strategies = storage.find_strategy_ids_referencing(&[id]).await?;
+            }
+        }
+        documents.alert_rules = storage.find_alert_rule_ids_referencing(&documents.strategies).await?;
+        documents.backtests = storage.find_backtest_ids_referencing(&documents.strategies).await?;
+        documents.paper_trades = storage.find_paper_trade_ids_referencing(&documents.strategies).await?;
+        Ok(documents)
+    }
+
+    // Sin dependientes es un borrado normal. Con dependientes se rechaza con Conflict salvo
+    // que se pida cascade, en cuyo caso se borran todos juntos. Devuelve los dependientes borrados.
+    pub async fn delete(item: CatalogItem, cascade: bool, storage: &dyn Storage) -> Result<CatalogDocuments, AppError> {
+        let not_found = || AppError::NotFound(format!("{} not found", item.label()));
+        let dependents = Self::dependents(item, storage).await?;
+
+        if dependents.is_empty() {
+            let deleted = match item {
+                CatalogItem::Exchange(id) => storage.delete_exchange(id).await?,
+                CatalogItem::Asset(id) => storage.delete_asset(id).await?,
+                CatalogItem::MarketPair(id) => storage.delete_market_pair(id).await?,
+            };
+            return if deleted { Ok(dependents) } else { Err(not_found()) };
+        }
+
+        if !cascade {
+            let listed: Vec<String> = [
+                describe(&dependents.assets, "asset(s)"),
+                describe(&dependents.market_pairs, "market pair(s)"),
+                describe(&dependents.strategies, "strategy(ies)"),
+                describe(&dependents.alert_rules, "alert rule(s)"),
+                describe(&dependents.backtests, "backtest(s)"),
+                describe(&dependents.paper_trades, "paper trade(s)"),
+            ].into_iter().flatten().collect();
+            return Err(AppError::Conflict(format!(
+                "{} is still referenced by {}; pass cascade=true to delete them too",
+                item.label(),
+                listed.join(", ")
+            )));
+        }
+
+        let mut documents = dependents.clone();
+        match item {
+            CatalogItem::Exchange(id) => {
+                storage.find_exchange(id).await?.ok_or_else(not_found)?;
+                documents.exchanges.push(id);
+            }
+            CatalogItem::Asset(id) => {
+                storage.find_asset(id).await?.ok_or_else(not_found)?;
+                documents.assets.push(id);
+            }
+            CatalogItem::MarketPair(id) => {
+                storage.find_market_pair(id).await?.ok_or_else(not_found)?;
+                documents.market_pairs.push(id);
+            }
+        }
+        storage.delete_catalog_documents(&documents).await?;
+        Ok(dependents)
+    }
+
+    pub async fn orphans(storage: &dyn Storage) -> Result<OrphanReport, AppError> {
+        Ok(OrphanReport {
+            documents: storage.find_orphans().await?,
+            unmigrated_strategies: storage.find_unmigrated_strategy_ids().await?,
+        })
+    }
+}
@@ -0,0 +1,9 @@
+pub mod integrity_schema;
+pub mod integrity_service;
+pub mod integrity_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(integrity_controller::get_orphans);
+}
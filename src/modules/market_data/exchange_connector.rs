@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use crate::helpers::app_error::AppError;
+use crate::modules::market_data::market_data_schema::OrderBookLevel;
+use crate::modules::market_data::file_connector::FileConnector;
+
+// Mercado tal como lo lista el exchange, con el símbolo en formato "BASE/QUOTE"
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConnectorMarket {
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ticker {
+    pub symbol: String,
+    pub bid: f64,
+    pub ask: f64,
+    #[serde(default)]
+    pub bid_size: f64,
+    #[serde(default)]
+    pub ask_size: f64,
+    pub timestamp: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderBookSnapshot {
+    pub symbol: String,
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+    pub timestamp: f64,
+}
+
+#[async_trait]
+pub trait ExchangeConnector: Send + Sync {
+    async fn list_markets(&self) -> Result<Vec<ConnectorMarket>, AppError>;
+    async fn fetch_ticker(&self, symbol: &str) -> Result<Ticker, AppError>;
+    async fn fetch_order_book(&self, symbol: &str) -> Result<OrderBookSnapshot, AppError>;
+}
+
+pub fn market_symbol(base: &str, quote: &str) -> String {
+    format!("{}/{}", base, quote)
+}
+
+// Resuelve el conector a partir de Exchange.short_name. Mientras no haya conectores
+// en vivo, todos los exchanges se sirven desde archivos en MARKET_DATA_DIR.
+pub async fn connector_for(short_name: &str) -> Result<Box<dyn ExchangeConnector>, AppError> {
+    let connector = FileConnector::from_env(short_name).await?;
+    Ok(Box::new(connector))
+}
@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use std::env;
+use std::path::{Path, PathBuf};
+use crate::helpers::app_error::AppError;
+use crate::modules::market_data::exchange_connector::{ConnectorMarket, ExchangeConnector, OrderBookSnapshot, Ticker};
+
+// Contenido de {MARKET_DATA_DIR}/{short_name}.json
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MarketDataFile {
+    #[serde(default)]
+    pub markets: Vec<ConnectorMarket>,
+    #[serde(default)]
+    pub tickers: Vec<Ticker>,
+    #[serde(default)]
+    pub order_books: Vec<OrderBookSnapshot>,
+}
+
+// Conector sin red: lee el archivo del exchange una vez al crearse. Cada ingesta crea su
+// conector, así que basta con reemplazar el archivo para reproducir un nuevo estado del mercado.
+pub struct FileConnector {
+    data: MarketDataFile,
+}
+
+impl FileConnector {
+    pub async fn load(path: &Path) -> Result<Self, AppError> {
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(|e| AppError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        let data = serde_json::from_str(&content)
+            .map_err(|e| AppError::Internal(format!("Failed to parse {}: {}", path.display(), e)))?;
+        Ok(Self { data })
+    }
+
+    pub async fn from_env(short_name: &str) -> Result<Self, AppError> {
+        let dir = env::var("MARKET_DATA_DIR").unwrap_or_else(|_| "market_data".to_string());
+        let path = PathBuf::from(dir).join(format!("{}.json", short_name.to_lowercase()));
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return Err(AppError::Validation(format!("No market data connector for exchange {} ({} not found)", short_name, path.display())));
+        }
+        Self::load(&path).await
+    }
+}
+
+#[async_trait]
+impl ExchangeConnector for FileConnector {
+    async fn list_markets(&self) -> Result<Vec<ConnectorMarket>, AppError> {
+        Ok(self.data.markets.clone())
+    }
+
+    async fn fetch_ticker(&self, symbol: &str) -> Result<Ticker, AppError> {
+        self.data.tickers.iter()
+            .find(|t| t.symbol == symbol)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Ticker not found for {}", symbol)))
+    }
+
+    async fn fetch_order_book(&self, symbol: &str) -> Result<OrderBookSnapshot, AppError> {
+        self.data.order_books.iter()
+            .find(|b| b.symbol == symbol)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Order book not found for {}", symbol)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::market_data_dir;
+
+    #[actix_web::test]
+    async fn serves_the_file_as_loaded() {
+        let dir = market_data_dir("binance");
+        let path = dir.join("binance.json");
+        let connector = FileConnector::load(&path).await.unwrap();
+
+        // Reemplazar el archivo no afecta al conector ya creado
+        std::fs::write(&path, "{}").unwrap();
+
+        assert_eq!(connector.list_markets().await.unwrap()[0].symbol, "BTC/USDT");
+        assert_eq!(connector.fetch_ticker("BTC/USDT").await.unwrap().ask, 30010.0);
+        assert_eq!(connector.fetch_order_book("BTC/USDT").await.unwrap().bids[0].amount, 1.5);
+        assert!(matches!(connector.fetch_ticker("ETH/USDT").await, Err(AppError::NotFound(_))));
+        assert!(FileConnector::load(&path).await.unwrap().list_markets().await.unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
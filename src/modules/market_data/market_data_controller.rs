@@ -0,0 +1,78 @@
+use actix_web::{get, post, web, HttpResponse};
+use crate::modules::market_data::market_data_service::{IngestSummary, MarketDataService};
+use crate::modules::market_data::market_data_schema::{MarketQuote, OrderBook};
+use crate::modules::opportunity_stream::opportunity_hub::OpportunityHub;
+use crate::modules::opportunity_stream::opportunity_stream_service::OpportunityStreamService;
+use crate::db::repositories::Storage;
+use serde::{Deserialize};
+use utoipa::IntoParams;
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::openapi::ErrorResponse;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use tracing::{error};
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
+
+#[derive(Deserialize, IntoParams)]
+struct IngestQuery {
+    order_books: Option<bool>,
+}
+
+#[utoipa::path(
+    tag = "market_data",
+    params(("exchange_id" = String, Path, description = "Exchange ID"), IngestQuery),
+    responses(
+        (status = 200, description = "Quotes (and order books if requested) stored for the exchange pairs", body = ApiResponse<IngestSummary>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[post("/market_data/ingest/{exchange_id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn ingest_exchange(
+    exchange_id: web::Path<String>,
+    query: web::Query<IngestQuery>,
+    storage: web::Data<dyn Storage>,
+    hub: web::Data<OpportunityHub>
+) -> Result<HttpResponse, AppError> {
+    let exchange_id = parse_object_id(&exchange_id, "exchange")?;
+
+    let summary = MarketDataService::ingest_exchange(exchange_id, query.order_books.unwrap_or(false), storage.get_ref()).await?;
+    // La ingesta ya está guardada; un fallo al publicar no debe invalidarla
+    if let Err(err) = OpportunityStreamService::publish_for_exchange(exchange_id, &hub, storage.get_ref()).await {
+        error!("Failed to publish opportunity updates: {}", err);
+    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market data ingested successfully", summary)))
+}
+
+#[utoipa::path(
+    tag = "market_data",
+    params(("market_pair_id" = String, Path, description = "Market pair ID")),
+    responses(
+        (status = 200, description = "Latest quote of the pair", body = ApiResponse<MarketQuote>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/market_data/quotes/{market_pair_id}")]
+pub async fn get_quote(market_pair_id: web::Path<String>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let market_pair_id = parse_object_id(&market_pair_id, "market pair")?;
+    let quote = MarketDataService::get_quote(market_pair_id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market quote retrieved successfully", quote)))
+}
+
+#[utoipa::path(
+    tag = "market_data",
+    params(("market_pair_id" = String, Path, description = "Market pair ID")),
+    responses(
+        (status = 200, description = "Latest order book of the pair", body = ApiResponse<OrderBook>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[get("/market_data/order_books/{market_pair_id}")]
+pub async fn get_order_book(market_pair_id: web::Path<String>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let market_pair_id = parse_object_id(&market_pair_id, "market pair")?;
+    let order_book = MarketDataService::get_order_book(market_pair_id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Order book retrieved successfully", order_book)))
+}
@@ -0,0 +1,45 @@
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use mongodb::bson::oid::ObjectId;
+
+// Mejor bid/ask de un MarketPair. La colección "market_quotes" guarda solo el último
+// por par y "market_quote_history" guarda cada ingesta.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct MarketQuote {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _market_pair: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _exchange: ObjectId,
+    pub bid: f64,
+    pub ask: f64,
+    #[serde(default)]
+    pub bid_size: f64,
+    #[serde(default)]
+    pub ask_size: f64,
+    pub timestamp: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct OrderBookLevel {
+    pub price: f64,
+    pub amount: f64,
+}
+
+// Último snapshot del libro de órdenes de un MarketPair (colección "order_books")
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct OrderBook {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _market_pair: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _exchange: ObjectId,
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+    pub timestamp: f64,
+}
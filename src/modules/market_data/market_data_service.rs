@@ -0,0 +1,170 @@
+use crate::db::repositories::{MarketDataRepository, Storage};
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::market_data::market_data_schema::{MarketQuote, OrderBook};
+use crate::modules::market_data::exchange_connector::{connector_for, market_symbol};
+use crate::modules::exchange::exchange_service::ExchangeService;
+use crate::modules::market_pair::market_pair_service::MarketPairService;
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use tracing::{info, warn};
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct IngestSummary {
+    pub exchange: String,
+    pub quotes_ingested: usize,
+    pub order_books_ingested: usize,
+    pub missing_symbols: Vec<String>,
+}
+
+pub struct MarketDataService;
+
+impl MarketDataService {
+    pub async fn ingest_exchange(exchange_id: ObjectId, include_order_books: bool, storage: &dyn Storage) -> Result<IngestSummary, AppError> {
+        let exchange = ExchangeService::get_exchange(exchange_id, storage).await?;
+        let connector = connector_for(&exchange.short_name).await?;
+
+        let listed: HashSet<String> = connector.list_markets().await?
+            .into_iter()
+            .map(|m| m.symbol)
+            .collect();
+
+        let market_pairs = MarketPairService::get_all_market_pairs_by_exchange(storage, exchange_id).await?;
+
+        let mut summary = IngestSummary {
+            exchange: exchange.short_name.clone(),
+            quotes_ingested: 0,
+            order_books_ingested: 0,
+            missing_symbols: Vec::new(),
+        };
+
+        for pair in market_pairs.iter().filter(|p| p.status) {
+            let market_pair_id = match pair.id {
+                Some(id) => id,
+                None => continue,
+            };
+            let symbol = market_symbol(&pair.base_asset.short_name, &pair.quote_asset.short_name);
+            if !listed.contains(&symbol) {
+                summary.missing_symbols.push(symbol);
+                continue;
+            }
+
+            let ticker = match connector.fetch_ticker(&symbol).await {
+                Ok(ticker) => ticker,
+                Err(err) => {
+                    warn!("Skipping {} on {}: {}", symbol, exchange.short_name, err);
+                    summary.missing_symbols.push(symbol);
+                    continue;
+                }
+            };
+
+            let quote = MarketQuote {
+                id: None,
+                _market_pair: market_pair_id,
+                _exchange: exchange_id,
+                bid: ticker.bid,
+                ask: ticker.ask,
+                bid_size: ticker.bid_size,
+                ask_size: ticker.ask_size,
+                timestamp: ticker.timestamp,
+            };
+            storage.store_quote(quote).await?;
+            summary.quotes_ingested += 1;
+
+            if include_order_books {
+                match connector.fetch_order_book(&symbol).await {
+                    Ok(snapshot) => {
+                        let order_book = OrderBook {
+                            id: None,
+                            _market_pair: market_pair_id,
+                            _exchange: exchange_id,
+                            bids: snapshot.bids,
+                            asks: snapshot.asks,
+                            timestamp: snapshot.timestamp,
+                        };
+                        storage.store_order_book(order_book).await?;
+                        summary.order_books_ingested += 1;
+                    },
+                    Err(err) => warn!("No order book for {} on {}: {}", symbol, exchange.short_name, err),
+                }
+            }
+        }
+
+        info!("Ingested {} quotes and {} order books from {}", summary.quotes_ingested, summary.order_books_ingested, exchange.short_name);
+        Ok(summary)
+    }
+
+    pub async fn get_quote(market_pair_id: ObjectId, repo: &dyn MarketDataRepository) -> Result<MarketQuote, AppError> {
+        repo.find_quote(market_pair_id).await?
+            .ok_or_else(|| AppError::NotFound(format!("No quote for market pair {}", market_pair_id)))
+    }
+
+    pub async fn get_quotes(market_pair_ids: &[ObjectId], repo: &dyn MarketDataRepository) -> Result<HashMap<ObjectId, MarketQuote>, AppError> {
+        Ok(repo.find_quotes(market_pair_ids).await?.into_iter().map(|quote| (quote._market_pair, quote)).collect())
+    }
+
+    // Historial de cotizaciones de un par desde `since` (timestamp en segundos), en orden cronológico
+    pub async fn get_quote_history(market_pair_id: ObjectId, since: f64, repo: &dyn MarketDataRepository) -> Result<Vec<MarketQuote>, AppError> {
+        repo.find_quote_history(&[market_pair_id], since, f64::MAX).await
+    }
+
+    // Historial de varios pares entre `from` y `to` (timestamps en segundos, inclusivos), en orden cronológico
+    pub async fn get_quote_history_between(market_pair_ids: &[ObjectId], from: f64, to: f64, repo: &dyn MarketDataRepository) -> Result<Vec<MarketQuote>, AppError> {
+        repo.find_quote_history(market_pair_ids, from, to).await
+    }
+
+    pub async fn get_order_books(market_pair_ids: &[ObjectId], repo: &dyn MarketDataRepository) -> Result<HashMap<ObjectId, OrderBook>, AppError> {
+        Ok(repo.find_order_books(market_pair_ids).await?.into_iter().map(|book| (book._market_pair, book)).collect())
+    }
+
+    pub async fn get_order_book(market_pair_id: ObjectId, repo: &dyn MarketDataRepository) -> Result<OrderBook, AppError> {
+        repo.find_order_book(market_pair_id).await?
+            .ok_or_else(|| AppError::NotFound(format!("No order book for market pair {}", market_pair_id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::InMemoryStorage;
+    use crate::db::repositories::{AssetRepository, ExchangeRepository, MarketPairRepository};
+    use crate::helpers::test_fixtures::{asset, exchange, market_data_dir};
+    use crate::modules::market_pair::market_pair_schema::MarketPair;
+
+    #[actix_web::test]
+    async fn ingests_listed_pairs_from_the_market_data_file() {
+        let storage = InMemoryStorage::default();
+        let exchange = storage.insert_exchange(exchange("ingestx")).await.unwrap();
+        let exchange_id = exchange.id.unwrap();
+        let usdt = storage.insert_asset(asset(&exchange, "USDT")).await.unwrap();
+        let mut pairs = Vec::new();
+        for symbol in ["BTC", "ETH"] {
+            let base = storage.insert_asset(asset(&exchange, symbol)).await.unwrap();
+            let pair = storage.insert_market_pair(MarketPair {
+                id: None,
+                _exchange: exchange_id,
+                _base_asset: base.id.unwrap(),
+                _quote_asset: usdt.id.unwrap(),
+                maker_fee: None,
+                taker_fee: None,
+                created_at: 0.0,
+                updated_at: 0.0,
+                status: true,
+            }).await.unwrap();
+            pairs.push(pair.id.unwrap());
+        }
+        let dir = market_data_dir("ingestx");
+        std::env::set_var("MARKET_DATA_DIR", &dir);
+
+        let summary = MarketDataService::ingest_exchange(exchange_id, true, &storage).await.unwrap();
+
+        assert_eq!((summary.quotes_ingested, summary.order_books_ingested), (1, 1));
+        assert_eq!(summary.missing_symbols, vec!["ETH/USDT".to_string()]);
+        let quote = MarketDataService::get_quote(pairs[0], &storage).await.unwrap();
+        assert_eq!((quote.bid, quote.ask, quote._exchange), (29990.0, 30010.0, exchange_id));
+        assert_eq!(MarketDataService::get_order_book(pairs[0], &storage).await.unwrap().asks[0].amount, 2.0);
+        assert!(MarketDataService::get_quote(pairs[1], &storage).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
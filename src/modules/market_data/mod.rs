@@ -0,0 +1,13 @@
+pub mod market_data_schema;
+pub mod exchange_connector;
+pub mod file_connector;
+pub mod market_data_service;
+pub mod market_data_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(market_data_controller::ingest_exchange);
+    cfg.service(market_data_controller::get_quote);
+    cfg.service(market_data_controller::get_order_book);
+}
@@ -1,175 +1,190 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use crate::modules::market_pair::market_pair_service::MarketPairService;
-use crate::db::mongodb::MongoDbContext;
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use crate::modules::market_pair::market_pair_service::{MarketPairService, PopulatedMarketPair};
+use crate::db::repositories::Storage;
+use crate::modules::integrity::integrity_schema::{CatalogDocuments, DeleteQuery};
 use crate::modules::market_pair::market_pair_schema::MarketPair;
-use mongodb::bson::oid::ObjectId;
+use crate::helpers::app_error::{parse_object_id, AppError};
 use serde::{Deserialize};
+use utoipa::IntoParams;
 use crate::modules::auth::auth_response::ApiResponse;
-use tracing::{error};
-use serde_json::json;
+use crate::openapi::ErrorResponse;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
 
 #[derive(Deserialize)]
 struct ObjectIdPath {
     pub id: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 struct MarketPairQuery {
-    page: Option<u64>,
-    per_page: Option<u64>,
     exchange_id: Option<String>,
     search: Option<String>,
 }
 
+#[utoipa::path(
+    tag = "market_pairs",
+    params(ConversionPairsQuery),
+    responses(
+        (status = 200, description = "Pairs that convert between both market pairs (not paginated)", body = ApiResponse<Vec<PopulatedMarketPair>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+    ),
+)]
 #[get("/conversion_pairs")]
 pub async fn get_conversion_pairs(
     query: web::Query<ConversionPairsQuery>,
-    db_context: web::Data<MongoDbContext>
-) -> impl Responder {
-    let pair1 = match ObjectId::parse_str(&query.pair1) {
-        Ok(id) => id,
-        Err(_) => return HttpResponse::BadRequest().json(ApiResponse::<String>::error("Invalid pair1 ID")),
-    };
-    let pair2 = match ObjectId::parse_str(&query.pair2) {
-        Ok(id) => id,
-        Err(_) => return HttpResponse::BadRequest().json(ApiResponse::<String>::error("Invalid pair2 ID")),
-    };
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let pair1 = parse_object_id(&query.pair1, "pair1")?;
+    let pair2 = parse_object_id(&query.pair2, "pair2")?;
 
-    match MarketPairService::get_conversion_pairs(&db_context, pair1, pair2).await {
-        Ok(pairs) => HttpResponse::Ok().json(ApiResponse::success("Conversion pairs retrieved successfully", pairs)),
-        Err(err) => {
-            error!("Failed to retrieve conversion pairs: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+    let pairs = MarketPairService::get_conversion_pairs(storage.get_ref(), pair1, pair2).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Conversion pairs retrieved successfully", pairs)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 struct ConversionPairsQuery {
     pair1: String,
     pair2: String,
 }
 
+#[utoipa::path(
+    tag = "market_pairs",
+    params(("exchange_id" = String, Path, description = "Exchange ID"), PageQuery),
+    responses(
+        (status = 200, description = "Page of market pairs of the exchange", body = ApiResponse<Paginated<PopulatedMarketPair>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+    ),
+)]
 #[get("/market_pairs/by_exchange/{exchange_id}")]
 pub async fn get_market_pairs_by_exchange(
     exchange_id: web::Path<String>,
-    db_context: web::Data<MongoDbContext>
-) -> impl Responder {
-    match ObjectId::parse_str(&*exchange_id) {
-        Ok(oid) => {
-            match MarketPairService::get_all_market_pairs_by_exchange(&db_context, oid).await {
-                Ok(market_pairs) => HttpResponse::Ok().json(ApiResponse::success("Market pairs retrieved successfully", market_pairs)),
-                Err(err) => {
-                    error!("Failed to retrieve market pairs by exchange: {}", err);
-                    HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err.to_string()))
-                },
-            }
-        },
-        Err(_) => {
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error("Invalid exchange ID"))
-        }
-    }
+    page: web::Query<PageQuery>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let exchange_id = parse_object_id(&exchange_id, "exchange")?;
+    let market_pairs = MarketPairService::get_all_market_pairs_with_pagination(storage.get_ref(), &page, Some(exchange_id.to_hex()), None).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market pairs retrieved successfully", market_pairs)))
 }
 
+#[utoipa::path(
+    tag = "market_pairs",
+    params(PageQuery, MarketPairQuery),
+    responses(
+        (status = 200, description = "Page of market pairs with exchange and assets", body = ApiResponse<Paginated<PopulatedMarketPair>>),
+    ),
+)]
 #[get("/market_pairs/with_pagination")]
 pub async fn get_all_market_pairs_with_pagination(
-    db_context: web::Data<MongoDbContext>,
+    storage: web::Data<dyn Storage>,
+    page: web::Query<PageQuery>,
     query: web::Query<MarketPairQuery>,
-) -> impl Responder {
-    let page = query.page.unwrap_or(1);
-    let per_page = query.per_page.unwrap_or(20);
-
-    match MarketPairService::get_all_market_pairs_with_pagination(
-        &db_context,
-        page,
-        per_page,
+) -> Result<HttpResponse, AppError> {
+    let market_pairs = MarketPairService::get_all_market_pairs_with_pagination(
+        storage.get_ref(),
+        &page,
         query.exchange_id.clone(),
         query.search.clone(),
-    ).await {
-        Ok((market_pairs, total)) => HttpResponse::Ok().json(ApiResponse::success("Market pairs retrieved successfully", json!({
-            "market_pairs": market_pairs,
-            "total": total,
-            "page": page,
-            "per_page": per_page
-        }))),
-        Err(err) => {
-            error!("Failed to retrieve market pairs in GET /market_pairs/with_pagination: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+    ).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market pairs retrieved successfully", market_pairs)))
 }
 
 
-#[post("/market_pairs")]
-pub async fn create_market_pair(market_pair: web::Json<MarketPair>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    match MarketPairService::create_market_pair(market_pair.into_inner(), &db_context).await {
-        Ok(market_pair) => HttpResponse::Ok().json(ApiResponse::success("Market pair created successfully", market_pair)),
-        Err(err) => {
-            error!("Failed to create market pair in : {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "market_pairs",
+    request_body = MarketPair,
+    responses(
+        (status = 200, description = "Market pair created", body = ApiResponse<MarketPair>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 409, description = "Conflict with an existing record", body = ErrorResponse),
+    ),
+)]
+#[post("/market_pairs", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn create_market_pair(market_pair: web::Json<MarketPair>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let market_pair = MarketPairService::create_market_pair(market_pair.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market pair created successfully", market_pair)))
 }
 
+#[utoipa::path(
+    tag = "market_pairs",
+    params(("id" = String, Path, description = "Market pair ID")),
+    responses(
+        (status = 200, description = "Market pair", body = ApiResponse<MarketPair>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
 #[get("/market_pairs/{id}")]
-pub async fn get_market_pair(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match MarketPairService::get_market_pair(id, &db_context).await {
-        Ok(market_pair) => HttpResponse::Ok().json(ApiResponse::success("Market pair retrieved successfully", market_pair)),
-        Err(err) => {
-            error!("Failed to retrieve market pair: {}", err);
-            HttpResponse::NotFound().json(ApiResponse::<String>::error(&err))
-        },
-    }
+pub async fn get_market_pair(path: web::Path<ObjectIdPath>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "market pair")?;
+    let market_pair = MarketPairService::get_market_pair(id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market pair retrieved successfully", market_pair)))
 }
 
-#[put("/market_pairs/{id}")]
-pub async fn update_market_pair(path: web::Path<ObjectIdPath>, market_pair: web::Json<MarketPair>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match MarketPairService::update_market_pair(id, market_pair.into_inner(), &db_context).await {
-        Ok(market_pair) => HttpResponse::Ok().json(ApiResponse::success("Market pair updated successfully", market_pair)),
-        Err(err) => {
-            error!("Failed to update market pair: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "market_pairs",
+    params(("id" = String, Path, description = "Market pair ID")),
+    request_body = MarketPair,
+    responses(
+        (status = 200, description = "Market pair updated", body = ApiResponse<MarketPair>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Conflict with an existing record", body = ErrorResponse),
+    ),
+)]
+#[put("/market_pairs/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn update_market_pair(path: web::Path<ObjectIdPath>, market_pair: web::Json<MarketPair>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "market pair")?;
+    let market_pair = MarketPairService::update_market_pair(id, market_pair.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market pair updated successfully", market_pair)))
 }
 
-#[delete("/market_pairs/{id}")]
-pub async fn delete_market_pair(path: web::Path<ObjectIdPath>, db_context: web::Data<MongoDbContext>) -> impl Responder {
-    let id = ObjectId::parse_str(&path.id).expect("Invalid ObjectId");
-    match MarketPairService::delete_market_pair(id, &db_context).await {
-        Ok(_) => HttpResponse::Ok().json(ApiResponse::success("Market pair deleted successfully", ())),
-        Err(err) => {
-            error!("Failed to delete market pair: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+#[utoipa::path(
+    tag = "market_pairs",
+    params(("id" = String, Path, description = "Market pair ID"), DeleteQuery),
+    responses(
+        (status = 200, description = "Market pair deleted, with the dependents removed by cascade", body = ApiResponse<CatalogDocuments>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+        (status = 409, description = "Still referenced and cascade not requested", body = ErrorResponse),
+    ),
+)]
+#[delete("/market_pairs/{id}", wrap = "RequirePermission(Permission::ManageCatalog)")]
+pub async fn delete_market_pair(
+    path: web::Path<ObjectIdPath>,
+    query: web::Query<DeleteQuery>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let id = parse_object_id(&path.id, "market pair")?;
+    let cascaded = MarketPairService::delete_market_pair(id, query.cascade.unwrap_or(false), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Market pair deleted successfully", cascaded)))
 }
 
 
+#[utoipa::path(
+    tag = "market_pairs",
+    params(ConversionPairsQueryToArbitrage),
+    responses(
+        (status = 200, description = "Pairs that convert between both quote assets (not paginated)", body = ApiResponse<Vec<PopulatedMarketPair>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+    ),
+)]
 #[get("/market_pairs/conversion_pairs_for_arbitrage")]
 pub async fn get_conversion_pairs_for_arbitrage(
     query: web::Query<ConversionPairsQueryToArbitrage>,
-    db_context: web::Data<MongoDbContext>
-) -> impl Responder {
-    // Use query.pair1 and query.pair2 here
-    // Don't try to parse these as ObjectIds
-    match MarketPairService::get_conversion_pairs_for_arbitrage(&db_context, &query.quote_asset1, &query.quote_asset2).await {
-        Ok(pairs) => HttpResponse::Ok().json(ApiResponse::success("Conversion pairs for arbitrage retrieved successfully", pairs)),
-        Err(err) => {
-            error!("Failed to retrieve conversion pairs for arbitrage: {}", err);
-            HttpResponse::BadRequest().json(ApiResponse::<String>::error(&err))
-        },
-    }
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    // Los activos de cotización se buscan por símbolo, no por ObjectId
+    let pairs = MarketPairService::get_conversion_pairs_for_arbitrage(storage.get_ref(), &query.quote_asset1, &query.quote_asset2).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Conversion pairs for arbitrage retrieved successfully", pairs)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 struct ConversionPairsQueryToArbitrage {
     quote_asset1: String,
     quote_asset2: String,
 }
-
-
-
-
@@ -1,13 +1,24 @@
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
 use mongodb::bson::oid::ObjectId;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct MarketPair {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
     pub _exchange: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
     pub _base_asset: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
     pub _quote_asset: ObjectId,
+    // Sobrescriben las comisiones del exchange para este par
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maker_fee: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taker_fee: Option<f64>,
     pub created_at: f64,
     pub updated_at: f64,
     pub status: bool,
@@ -1,35 +1,53 @@
-use crate::db::mongodb::MongoDbContext;
-use mongodb::bson::{doc, Document, oid::ObjectId, Regex};
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::helpers::app_error::{AppError, FieldError};
+use crate::db::repositories::{MarketPairFilter, MarketPairRepository, Storage};
+use crate::modules::integrity::integrity_schema::CatalogDocuments;
+use crate::modules::integrity::integrity_service::{CatalogItem, IntegrityService};
+use mongodb::bson::oid::ObjectId;
 use crate::modules::market_pair::market_pair_schema::MarketPair;
-use tracing::error;
 use chrono::Utc;
-use futures::TryStreamExt;
 use serde::{Serialize, Deserialize};
-use mongodb::bson;
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
 
 use crate::modules::asset::asset_schema::Asset;
 use crate::modules::exchange::exchange_schema::Exchange;
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
 
 pub struct MarketPairService;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+fn not_found() -> AppError {
+    AppError::NotFound("Market pair not found".to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct PopulatedMarketPair {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
     pub id: Option<ObjectId>,
     pub exchange: Exchange,
     pub base_asset: Asset,
     pub quote_asset: Asset,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maker_fee: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taker_fee: Option<f64>,
     pub created_at: f64,
     pub updated_at: f64,
     pub status: bool,
 }
 
+impl PopulatedMarketPair {
+    // Comisión taker efectiva: la del par si está definida, si no la del exchange
+    pub fn effective_taker_fee(&self) -> f64 {
+        self.taker_fee.unwrap_or(self.exchange.taker_fee)
+    }
+}
+
 
 impl MarketPairService {
-    pub async fn create_market_pair(market_pair: MarketPair, db_context: &MongoDbContext) -> Result<MarketPair, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<MarketPair>("marketpairs");
-
+    pub async fn create_market_pair(market_pair: MarketPair, storage: &dyn Storage) -> Result<MarketPair, AppError> {
+        Self::validate(&market_pair, None, storage).await?;
         let now = Utc::now().timestamp() as f64;
         let new_market_pair = MarketPair {
             created_at: now,
@@ -37,425 +55,124 @@ impl MarketPairService {
             ..market_pair 
         };
 
-        let insert_result = collection.insert_one(new_market_pair.clone()).await
-            .map_err(|e| {
-                error!("Failed to insert market pair: {}", e);
-                e.to_string()
-            })?;
-        
-        let new_market_pair = collection.find_one(doc! { "_id": insert_result.inserted_id }).await
-            .map_err(|e| {
-                error!("Failed to fetch created market pair: {}", e);
-                e.to_string()
-            })?
-            .ok_or_else(|| {
-                let msg = "Failed to fetch created market pair".to_string();
-                error!("{}", msg);
-                msg
-            })?;
-        
-        Ok(new_market_pair)
+        storage.insert_market_pair(new_market_pair).await
     }
 
-    pub async fn get_market_pair(id: ObjectId, db_context: &MongoDbContext) -> Result<MarketPair, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<MarketPair>("marketpairs");
-
-        let market_pair = collection.find_one(doc! { "_id": id }).await
-            .map_err(|e| {
-                error!("Failed to fetch market pair: {}", e);
-                e.to_string()
-            })?
-            .ok_or_else(|| {
-                let msg = "Market pair not found".to_string();
-                error!("{}", msg);
-                msg
-            })?;
-
-        Ok(market_pair)
+    pub async fn get_market_pair(id: ObjectId, repo: &dyn MarketPairRepository) -> Result<MarketPair, AppError> {
+        repo.find_market_pair(id).await?.ok_or_else(not_found)
     }
 
-    pub async fn update_market_pair(id: ObjectId, updated_market_pair: MarketPair, db_context: &MongoDbContext) -> Result<MarketPair, String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<MarketPair>("marketpairs");
-
-        let now = Utc::now().timestamp() as f64;
-        let update_doc = doc! {
-            "$set": {
-                "_exchange": updated_market_pair._exchange,
-                "_base_asset": updated_market_pair._base_asset,
-                "_quote_asset": updated_market_pair._quote_asset,
-                "updated_at": now,
-                "status": updated_market_pair.status,
-            }
+    pub async fn update_market_pair(id: ObjectId, updated_market_pair: MarketPair, storage: &dyn Storage) -> Result<MarketPair, AppError> {
+        Self::validate(&updated_market_pair, Some(id), storage).await?;
+        let updated_market_pair = MarketPair {
+            updated_at: Utc::now().timestamp() as f64,
+            ..updated_market_pair
         };
 
-        collection.update_one(doc! { "_id": id }, update_doc).await
-            .map_err(|e| {
-                error!("Failed to update market pair: {}", e);
-                e.to_string()
-            })?;
-
-        Self::get_market_pair(id, db_context).await
+        storage.update_market_pair(id, updated_market_pair).await?.ok_or_else(not_found)
     }
 
-    pub async fn delete_market_pair(id: ObjectId, db_context: &MongoDbContext) -> Result<(), String> {
-        let db = db_context.get_database();
-        let collection = db.collection::<MarketPair>("marketpairs");
+    // Los dos activos deben existir, ser distintos y pertenecer al exchange del par, y no puede
+    // haber otro par con el mismo (exchange, base, quote). `current` es el par que se actualiza.
+    async fn validate(market_pair: &MarketPair, current: Option<ObjectId>, storage: &dyn Storage) -> Result<(), AppError> {
+        let mut errors = Vec::new();
 
-        collection.delete_one(doc! { "_id": id }).await
-            .map_err(|e| {
-                error!("Failed to delete market pair: {}", e);
-                e.to_string()
-            })?;
+        if storage.find_exchange(market_pair._exchange).await?.is_none() {
+            errors.push(FieldError::new("_exchange", "Exchange not found"));
+        }
+        if market_pair._base_asset == market_pair._quote_asset {
+            errors.push(FieldError::new("_quote_asset", "Must differ from _base_asset"));
+        }
+        for (field, asset_id) in [("_base_asset", market_pair._base_asset), ("_quote_asset", market_pair._quote_asset)] {
+            match storage.find_asset(asset_id).await? {
+                None => errors.push(FieldError::new(field, "Asset not found")),
+                Some(asset) if asset._exchange != market_pair._exchange => {
+                    errors.push(FieldError::new(field, "Asset belongs to a different exchange"))
+                }
+                Some(_) => {}
+            }
+        }
+        for (field, fee) in [("maker_fee", market_pair.maker_fee), ("taker_fee", market_pair.taker_fee)] {
+            if fee.is_some_and(|fee| !(0.0..1.0).contains(&fee)) {
+                errors.push(FieldError::new(field, "Must be a fraction between 0 and 1"));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(AppError::invalid_fields(errors));
+        }
 
+        let existing = storage.find_market_pair_by_assets(market_pair._exchange, market_pair._base_asset, market_pair._quote_asset).await?;
+        if existing.is_some_and(|existing| existing.id != current) {
+            return Err(AppError::Conflict("A market pair with the same exchange, base and quote assets already exists".to_string()));
+        }
         Ok(())
     }
 
+    // Con dependientes falla con Conflict salvo que cascade sea true; devuelve lo borrado en cascada
+    pub async fn delete_market_pair(id: ObjectId, cascade: bool, storage: &dyn Storage) -> Result<CatalogDocuments, AppError> {
+        IntegrityService::delete(CatalogItem::MarketPair(id), cascade, storage).await
+    }
+
     pub async fn get_all_market_pairs_with_pagination(
-        db_context: &MongoDbContext,
-        page: u64,
-        per_page: u64,
+        repo: &dyn MarketPairRepository,
+        page: &PageQuery,
         exchange_id: Option<String>,
         search: Option<String>
-    ) -> Result<(Vec<PopulatedMarketPair>, u64), String> {
-        let db = db_context.get_database();
-        let market_pairs_collection = db.collection::<Document>("marketpairs");
-    
-        let skip = (page - 1) * per_page;
-    
-        let mut initial_filter = doc! {};
-    
-        if let Some(exchange) = exchange_id.filter(|s| !s.is_empty()) {
-            if let Ok(oid) = ObjectId::parse_str(&exchange) {
-                initial_filter.insert("_exchange", oid);
-            }
-        }
-    
-        let mut pipeline = vec![
-            doc! { "$match": initial_filter },
-            doc! {
-                "$lookup": {
-                    "from": "exchanges",
-                    "localField": "_exchange",
-                    "foreignField": "_id",
-                    "as": "exchange"
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_base_asset",
-                    "foreignField": "_id",
-                    "as": "base_asset"
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_quote_asset",
-                    "foreignField": "_id",
-                    "as": "quote_asset"
-                }
-            },
-            doc! { "$unwind": "$exchange" },
-            doc! { "$unwind": "$base_asset" },
-            doc! { "$unwind": "$quote_asset" },
-        ];
-    
-        if let Some(term) = search.filter(|s| !s.is_empty()) {
-            let search_stage = doc! {
-                "$match": {
-                    "$or": [
-                        { "base_asset.short_name": Regex { pattern: format!(".*{}.*", term), options: "i".to_string() } },
-                        { "quote_asset.short_name": Regex { pattern: format!(".*{}.*", term), options: "i".to_string() } }
-                    ]
-                }
-            };
-            pipeline.push(search_stage);
-        }
-    
-        pipeline.push(doc! { "$skip": skip as i64 });
-        pipeline.push(doc! { "$limit": per_page as i64 });
-    
-        let mut cursor = market_pairs_collection.aggregate(pipeline.clone()).await
-            .map_err(|e| {
-                error!("Failed to aggregate market pairs: {}", e);
-                e.to_string()
-            })?;
-    
-        let mut populated_market_pairs = Vec::new();
-        while let Some(result) = cursor.try_next().await.map_err(|e| {
-            error!("Failed to iterate through aggregation results: {}", e);
-            e.to_string()
-        })? {
-            let populated_market_pair: PopulatedMarketPair = bson::from_document(result)
-                .map_err(|e| {
-                    error!("Failed to deserialize market pair: {}", e);
-                    e.to_string()
-                })?;
-    
-            populated_market_pairs.push(populated_market_pair);
-        }
-    
-        // Para contar el total, necesitamos quitar las etapas de paginación y agregar un $count
-        pipeline.pop(); // Quitar $limit
-        pipeline.pop(); // Quitar $skip
-        pipeline.push(doc! { "$count": "total" });
-    
-        let total = market_pairs_collection.aggregate(pipeline).await
-            .map_err(|e| {
-                error!("Failed to count market pairs: {}", e);
-                e.to_string()
-            })?
-            .try_next().await
-            .map_err(|e| {
-                error!("Failed to get count result: {}", e);
-                e.to_string()
-            })?
-            .and_then(|doc| doc.get_i64("total").ok())
-            .unwrap_or(0) as u64;
-    
-        Ok((populated_market_pairs, total))
+    ) -> Result<Paginated<PopulatedMarketPair>, AppError> {
+        // Un exchange_id mal formado se ignora, igual que uno vacío
+        let exchange = exchange_id.and_then(|id| ObjectId::parse_str(&id).ok());
+        let search = search.filter(|term| !term.is_empty());
+
+        repo.list_populated_market_pairs(page, exchange, search.as_deref()).await
     }
+
     pub async fn get_all_market_pairs_by_exchange(
-        db_context: &MongoDbContext,
+        repo: &dyn MarketPairRepository,
         exchange_id: ObjectId
-    ) -> Result<Vec<PopulatedMarketPair>, String> {
-        let db = db_context.get_database();
-        let market_pairs_collection = db.collection::<Document>("marketpairs");
-    
-        let pipeline = vec![
-            doc! { 
-                "$match": { 
-                    "_exchange": exchange_id 
-                } 
-            },
-            doc! {
-                "$lookup": {
-                    "from": "exchanges",
-                    "localField": "_exchange",
-                    "foreignField": "_id",
-                    "as": "exchange"
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_base_asset",
-                    "foreignField": "_id",
-                    "as": "base_asset"
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_quote_asset",
-                    "foreignField": "_id",
-                    "as": "quote_asset"
-                }
-            },
-            doc! { "$unwind": "$exchange" },
-            doc! { "$unwind": "$base_asset" },
-            doc! { "$unwind": "$quote_asset" },
-        ];
-    
-        let mut cursor = market_pairs_collection.aggregate(pipeline).await
-            .map_err(|e| {
-                error!("Failed to aggregate market pairs: {}", e);
-                e.to_string()
-            })?;
-    
-        let mut populated_market_pairs = Vec::new();
-        while let Some(result) = cursor.try_next().await.map_err(|e| {
-            error!("Failed to iterate through aggregation results: {}", e);
-            e.to_string()
-        })? {
-            let populated_market_pair: PopulatedMarketPair = bson::from_document(result)
-                .map_err(|e| {
-                    error!("Failed to deserialize market pair: {}", e);
-                    e.to_string()
-                })?;
-    
-            populated_market_pairs.push(populated_market_pair);
-        }
-    
-        Ok(populated_market_pairs)
+    ) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        repo.find_populated_market_pairs(MarketPairFilter::Exchange(exchange_id)).await
     }
+
+    pub async fn get_populated_market_pairs(
+        repo: &dyn MarketPairRepository,
+        ids: &[ObjectId]
+    ) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        repo.find_populated_market_pairs(MarketPairFilter::Ids(ids.to_vec())).await
+    }
+
+    // Pares activos de los exchanges indicados; sin exchanges se devuelven los de todo el catálogo
+    pub async fn get_active_market_pairs(
+        repo: &dyn MarketPairRepository,
+        exchange_ids: &[ObjectId]
+    ) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        repo.find_populated_market_pairs(MarketPairFilter::Active(exchange_ids.to_vec())).await
+    }
+
     pub async fn get_conversion_pairs(
-        db_context: &MongoDbContext,
+        repo: &dyn MarketPairRepository,
         pair1: ObjectId,
         pair2: ObjectId
-    ) -> Result<Vec<PopulatedMarketPair>, String> {
-        let db = db_context.get_database();
-        let market_pairs_collection = db.collection::<Document>("marketpairs");
-    
-        // Fetch the assets involved in pair1 and pair2
-        let pair1_doc = market_pairs_collection.find_one(doc! { "_id": pair1 }).await
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| "Pair1 not found".to_string())?;
-        let pair2_doc = market_pairs_collection.find_one(doc! { "_id": pair2 }).await
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| "Pair2 not found".to_string())?;
-    
-        let pair1: MarketPair = bson::from_document(pair1_doc).map_err(|e| e.to_string())?;
-        let pair2: MarketPair = bson::from_document(pair2_doc).map_err(|e| e.to_string())?;
-    
-        let pipeline = vec![
-            doc! {
-                "$match": {
-                    "$or": [
-                        { "_base_asset": pair1._quote_asset, "_quote_asset": pair2._quote_asset },
-                        { "_base_asset": pair2._quote_asset, "_quote_asset": pair1._quote_asset },
-                    ]
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "exchanges",
-                    "localField": "_exchange",
-                    "foreignField": "_id",
-                    "as": "exchange"
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_base_asset",
-                    "foreignField": "_id",
-                    "as": "base_asset"
-                }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_quote_asset",
-                    "foreignField": "_id",
-                    "as": "quote_asset"
-                }
-            },
-            doc! { "$unwind": "$exchange" },
-            doc! { "$unwind": "$base_asset" },
-            doc! { "$unwind": "$quote_asset" },
-        ];
-    
-        let mut cursor = market_pairs_collection.aggregate(pipeline).await
-            .map_err(|e| e.to_string())?;
-    
-        let mut populated_market_pairs = Vec::new();
-        while let Some(result) = cursor.try_next().await.map_err(|e| e.to_string())? {
-            let populated_market_pair: PopulatedMarketPair = bson::from_document(result)
-                .map_err(|e| e.to_string())?;
-            populated_market_pairs.push(populated_market_pair);
-        }
-    
-        Ok(populated_market_pairs)
+    ) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        // Pares que convierten entre los activos de cotización de pair1 y pair2
+        let pair1 = repo.find_market_pair(pair1).await?
+            .ok_or_else(|| AppError::NotFound("Pair1 not found".to_string()))?;
+        let pair2 = repo.find_market_pair(pair2).await?
+            .ok_or_else(|| AppError::NotFound("Pair2 not found".to_string()))?;
+
+        repo.find_populated_market_pairs(MarketPairFilter::Linking(pair1._quote_asset, pair2._quote_asset)).await
     }
-    // En src/modules/market_pair/market_pair_service.rs
 
     pub async fn get_conversion_pairs_for_arbitrage(
-        db_context: &MongoDbContext,
+        storage: &dyn Storage,
         quote_asset1: &str,
         quote_asset2: &str
-    ) -> Result<Vec<PopulatedMarketPair>, String> {
-        let db = db_context.get_database();
-        let market_pairs_collection = db.collection::<Document>("marketpairs");
-    
-        // Definir los posibles stablecoins y monedas fiat
-        let stablecoins = doc! {
-            "USD": ["USDT", "USDC", "DAI", "TUSD", "USDP", "GUSD", "USD"],
-            "EUR": ["EURS", "EURT", "sEUR", "EURB", "EURe", "cEUR", "EUROC", "EUR"]
-        };
-    
-        // Función para obtener todas las variantes de un asset
-        let get_asset_variants = |asset: &str| -> Vec<String> {
-            let mut variants = vec![asset.to_string()];
-            for (fiat, coins) in stablecoins.iter() {
-                if let bson::Bson::Array(coin_array) = coins {
-                    if coin_array.iter().any(|c| c.as_str().unwrap() == asset) {
-                        variants.push(fiat.to_string());
-                        variants.extend(coin_array.iter().map(|c| c.as_str().unwrap().to_string()));
-                    }
-                }
-            }
-            variants.sort();
-            variants.dedup();
-            variants
-        };
-    
-        let asset1_variants = get_asset_variants(quote_asset1);
-        let asset2_variants = get_asset_variants(quote_asset2);    
-   
-        let pipeline = vec![
-            // Lookup para obtener la información del base_asset
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_base_asset",
-                    "foreignField": "_id",
-                    "as": "base_asset"
-                }
-            },
-            doc! { "$unwind": "$base_asset" },
-            // Lookup para obtener la información del quote_asset
-            doc! {
-                "$lookup": {
-                    "from": "assets",
-                    "localField": "_quote_asset",
-                    "foreignField": "_id",
-                    "as": "quote_asset"
-                }
-            },
-            doc! { "$unwind": "$quote_asset" },
-            // Ahora podemos hacer el match utilizando la información de los assets
-            doc! {
-                "$match": {
-                    "$or": [
-                        { 
-                            "base_asset.short_name": { "$in": &asset1_variants },
-                            "quote_asset.short_name": { "$in": &asset2_variants }
-                        },
-                        { 
-                            "base_asset.short_name": { "$in": &asset2_variants },
-                            "quote_asset.short_name": { "$in": &asset1_variants }
-                        }
-                    ]
-                }
-            },
-            // Lookup para obtener la información del exchange
-            doc! {
-                "$lookup": {
-                    "from": "exchanges",
-                    "localField": "_exchange",
-                    "foreignField": "_id",
-                    "as": "exchange"
-                }
-            },
-            doc! { "$unwind": "$exchange" },
-            // Proyectar los campos necesarios
-            doc! {
-                "$project": {
-                    "_id": 1,
-                    "exchange": 1,
-                    "base_asset": 1,
-                    "quote_asset": 1,
-                    "created_at": 1,
-                    "updated_at": 1,
-                    "status": 1
-                }
-            }
-        ];
-    
-        let mut cursor = market_pairs_collection.aggregate(pipeline).await
-            .map_err(|e| e.to_string())?;
-    
-        let mut conversion_pairs = Vec::new();
-        while let Some(result) = cursor.try_next().await.map_err(|e| e.to_string())? {
-            let populated_market_pair: PopulatedMarketPair = bson::from_document(result)
-                .map_err(|e| e.to_string())?;
-            conversion_pairs.push(populated_market_pair);
-        }
-    
-        Ok(conversion_pairs)
+    ) -> Result<Vec<PopulatedMarketPair>, AppError> {
+        // Variantes según los grupos de equivalencia registrados (stablecoins y monedas fiat)
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+
+        let asset1_variants = equivalences.variants(quote_asset1);
+        let asset2_variants = equivalences.variants(quote_asset2);
+
+        storage.find_populated_market_pairs(MarketPairFilter::LinkingSymbols(asset1_variants, asset2_variants)).await
     }
 }
@@ -4,4 +4,12 @@ pub mod user;
 pub mod asset;
 pub mod market_pair;
 pub mod exchange;
-pub mod arbitrage_strategy;
\ No newline at end of file
+pub mod arbitrage_strategy;
+pub mod market_data;
+pub mod asset_equivalence;
+pub mod paper_trading;
+pub mod backtest;
+pub mod opportunity_stream;
+pub mod alert;
+pub mod api_key;
+pub mod integrity;
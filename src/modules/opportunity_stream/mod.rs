@@ -0,0 +1,10 @@
+pub mod opportunity_stream_schema;
+pub mod opportunity_hub;
+pub mod opportunity_stream_service;
+pub mod opportunity_stream_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(opportunity_stream_controller::stream_opportunities);
+}
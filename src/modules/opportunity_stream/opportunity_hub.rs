@@ -0,0 +1,33 @@
+use crate::modules::opportunity_stream::opportunity_stream_schema::OpportunityUpdate;
+use tokio::sync::broadcast;
+
+// Actualizaciones pendientes por cliente antes de que empiece a perder mensajes
+const CHANNEL_CAPACITY: usize = 1024;
+
+// Canal de difusión compartido (app_data) entre la ingesta de datos y las conexiones WebSocket
+#[derive(Clone)]
+pub struct OpportunityHub {
+    sender: broadcast::Sender<OpportunityUpdate>,
+}
+
+impl Default for OpportunityHub {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl OpportunityHub {
+    pub fn subscribe(&self) -> broadcast::Receiver<OpportunityUpdate> {
+        self.sender.subscribe()
+    }
+
+    pub fn has_listeners(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn publish(&self, update: OpportunityUpdate) {
+        // Sin receptores el envío falla y la actualización simplemente se descarta
+        let _ = self.sender.send(update);
+    }
+}
@@ -0,0 +1,110 @@
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use actix_ws::Message;
+use crate::middleware::auth_middleware::decode_claims;
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::modules::auth::session_service::SessionService;
+use crate::db::repositories::Storage;
+use crate::modules::opportunity_stream::opportunity_hub::OpportunityHub;
+use crate::modules::opportunity_stream::opportunity_stream_schema::{ClientMessage, ServerMessage};
+use crate::modules::opportunity_stream::opportunity_stream_service::SubscriptionState;
+use mongodb::bson::oid::ObjectId;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use crate::openapi::ErrorResponse;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+#[derive(Deserialize, IntoParams)]
+struct StreamQuery {
+    token: Option<String>, // Los navegadores no permiten cabeceras en el handshake
+}
+
+// El JWT puede llegar en Authorization (Bearer) o en ?token=
+fn request_token(req: &HttpRequest, query: &StreamQuery) -> Option<String> {
+    req.headers().get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| query.token.clone())
+}
+
+#[utoipa::path(
+    tag = "opportunities",
+    params(StreamQuery),
+    responses(
+        (status = 101, description = "WebSocket upgrade; the client sends subscribe/unsubscribe messages and receives opportunity updates"),
+        (status = 401, description = "Invalid or missing token", body = ErrorResponse),
+    ),
+)]
+#[get("/ws/opportunities")]
+pub async fn stream_opportunities(
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<StreamQuery>,
+    hub: web::Data<OpportunityHub>,
+    storage: web::Data<dyn Storage>,
+) -> actix_web::Result<HttpResponse> {
+    let Some(claims) = request_token(&req, &query).and_then(|token| decode_claims(&token)) else {
+        return Ok(HttpResponse::Unauthorized().json(ApiResponse::<String>::error("Invalid or missing token")));
+    };
+    let Ok(user_id) = ObjectId::parse_str(&claims.sub) else {
+        return Ok(HttpResponse::Unauthorized().json(ApiResponse::<String>::error("Invalid or missing token")));
+    };
+    if !SessionService::touch(&claims, storage.get_ref()).await {
+        return Ok(HttpResponse::Unauthorized().json(ApiResponse::<String>::error("Invalid or missing token")));
+    }
+
+    let (response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+    let mut updates = hub.subscribe();
+    info!("Opportunity stream opened for user {}", claims.sub);
+
+    actix_web::rt::spawn(async move {
+        let mut state = SubscriptionState::new(user_id);
+
+        loop {
+            let outgoing = tokio::select! {
+                message = messages.recv() => match message {
+                    Some(Ok(Message::Text(text))) => Some(match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(ClientMessage::Subscribe(subscription)) => {
+                            state.subscribe(subscription.clone());
+                            ServerMessage::Subscribed(subscription)
+                        },
+                        Ok(ClientMessage::Unsubscribe) => {
+                            state.unsubscribe();
+                            ServerMessage::Unsubscribed
+                        },
+                        Err(e) => ServerMessage::Error { message: format!("Invalid message: {}", e) },
+                    }),
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                        None
+                    },
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => None,
+                },
+                update = updates.recv() => match update {
+                    Ok(update) => state.message_for(update),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Opportunity stream for user {} skipped {} updates", claims.sub, skipped);
+                        None
+                    },
+                    Err(RecvError::Closed) => break,
+                },
+            };
+
+            if let Some(outgoing) = outgoing {
+                let Ok(text) = serde_json::to_string(&outgoing) else { continue };
+                if session.text(text).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        let _ = session.close(None).await;
+        info!("Opportunity stream closed for user {}", claims.sub);
+    });
+
+    Ok(response)
+}
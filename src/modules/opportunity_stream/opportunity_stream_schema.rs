@@ -0,0 +1,54 @@
+use serde::{Serialize, Deserialize};
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::ArbitrageType;
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::CycleDirection;
+
+// Spread evaluado de una estrategia tras una ingesta de cotizaciones
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpportunityUpdate {
+    pub strategy_id: ObjectId,
+    // Dueño de la estrategia; solo sus conexiones reciben la actualización
+    pub owner: ObjectId,
+    pub arbitrage_type: ArbitrageType,
+    pub exchanges: Vec<ObjectId>,
+    pub direction: CycleDirection,
+    pub start_asset: String,
+    pub profit_percentage: f64,
+    pub gross_profit_percentage: f64,
+    pub timestamp: f64,
+}
+
+// Filtro de un cliente. Una actualización se envía si coincide con cualquiera de los criterios;
+// sin criterios se reciben todas.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Subscription {
+    #[serde(default)]
+    pub strategy_ids: Vec<ObjectId>,
+    #[serde(default)]
+    pub arbitrage_types: Vec<ArbitrageType>,
+    #[serde(default)]
+    pub exchanges: Vec<ObjectId>,
+    // Con umbral solo se envían los spreads que lo alcanzan y el momento en que dejan de hacerlo
+    pub min_profit: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe(Subscription),
+    Unsubscribe,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed(Subscription),
+    Unsubscribed,
+    Opportunity {
+        #[serde(flatten)]
+        update: OpportunityUpdate,
+        // true cuando el spread acaba de caer por debajo del umbral de la suscripción
+        below_threshold: bool,
+    },
+    Error { message: String },
+}
@@ -0,0 +1,182 @@
+use crate::db::repositories::Storage;
+use crate::helpers::app_error::AppError;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::{ArbitrageEvaluationService, RouteSelection};
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use crate::modules::market_data::market_data_service::MarketDataService;
+use crate::modules::market_pair::market_pair_service::MarketPairService;
+use crate::modules::opportunity_stream::opportunity_hub::OpportunityHub;
+use crate::modules::opportunity_stream::opportunity_stream_schema::{OpportunityUpdate, ServerMessage, Subscription};
+use chrono::Utc;
+use std::collections::HashSet;
+use tracing::info;
+
+impl Subscription {
+    pub fn matches(&self, update: &OpportunityUpdate) -> bool {
+        if self.strategy_ids.is_empty() && self.arbitrage_types.is_empty() && self.exchanges.is_empty() {
+            return true;
+        }
+        self.strategy_ids.contains(&update.strategy_id)
+            || self.arbitrage_types.contains(&update.arbitrage_type)
+            || update.exchanges.iter().any(|e| self.exchanges.contains(e))
+    }
+}
+
+// Estado de una conexión: qué estrategias están por encima del umbral de su suscripción
+pub struct SubscriptionState {
+    // Usuario autenticado de la conexión; solo recibe las actualizaciones de sus estrategias
+    owner: ObjectId,
+    subscription: Option<Subscription>,
+    above_threshold: HashSet<ObjectId>,
+}
+
+impl SubscriptionState {
+    pub fn new(owner: ObjectId) -> Self {
+        Self { owner, subscription: None, above_threshold: HashSet::new() }
+    }
+
+    pub fn subscribe(&mut self, subscription: Subscription) {
+        self.subscription = Some(subscription);
+        self.above_threshold.clear();
+    }
+
+    pub fn unsubscribe(&mut self) {
+        self.subscription = None;
+        self.above_threshold.clear();
+    }
+
+    // Mensaje a enviar para la actualización, si corresponde
+    pub fn message_for(&mut self, update: OpportunityUpdate) -> Option<ServerMessage> {
+        let subscription = self.subscription.as_ref()?;
+        if update.owner != self.owner || !subscription.matches(&update) {
+            return None;
+        }
+        let Some(min_profit) = subscription.min_profit else {
+            return Some(ServerMessage::Opportunity { update, below_threshold: false });
+        };
+
+        if update.profit_percentage >= min_profit {
+            self.above_threshold.insert(update.strategy_id);
+            Some(ServerMessage::Opportunity { update, below_threshold: false })
+        } else if self.above_threshold.remove(&update.strategy_id) {
+            Some(ServerMessage::Opportunity { update, below_threshold: true })
+        } else {
+            None
+        }
+    }
+}
+
+pub struct OpportunityStreamService;
+
+impl OpportunityStreamService {
+    // Reevalúa las estrategias activas con algún par en el exchange y publica sus spreads.
+    // Las estadísticas no son ciclos y se siguen por el endpoint de z-score.
+    pub async fn publish_for_exchange(exchange_id: ObjectId, hub: &OpportunityHub, storage: &dyn Storage) -> Result<usize, AppError> {
+        if !hub.has_listeners() {
+            return Ok(0);
+        }
+
+        let strategies = ArbitrageStrategyService::get_active_arbitrage_strategies(storage).await?;
+        let mut pair_ids: Vec<ObjectId> = strategies.iter()
+            .flat_map(|s| ArbitrageStrategyService::leg_pair_ids(&s.legs))
+            .collect();
+        pair_ids.sort();
+        pair_ids.dedup();
+
+        let pairs = MarketPairService::get_populated_market_pairs(storage, &pair_ids).await?;
+        let quotes = MarketDataService::get_quotes(&pair_ids, storage).await?;
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+        let now = Utc::now().timestamp() as f64;
+
+        let mut published = 0;
+        for strategy in strategies.iter() {
+            let exchanges: Vec<ObjectId> = strategy.legs.iter().map(|leg| leg.exchange).collect();
+            if !exchanges.contains(&exchange_id) {
+                continue;
+            }
+            let (Some(strategy_id), Some(owner), Some(legs)) = (strategy.id, strategy._owner, ArbitrageStrategyService::assemble_populated_legs(&strategy.legs, &pairs)) else {
+                continue;
+            };
+            let Ok(result) = ArbitrageEvaluationService::evaluate(&strategy.arbitrage_type, &legs, 1.0, RouteSelection::Best, &quotes, &equivalences) else {
+                continue;
+            };
+
+            hub.publish(OpportunityUpdate {
+                strategy_id,
+                owner,
+                arbitrage_type: strategy.arbitrage_type.clone(),
+                exchanges,
+                direction: result.direction,
+                start_asset: result.start_asset,
+                profit_percentage: result.profit_percentage,
+                gross_profit_percentage: result.gross_profit_percentage,
+                timestamp: now,
+            });
+            published += 1;
+        }
+
+        info!("Published {} opportunity updates for exchange {}", published, exchange_id);
+        Ok(published)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::ArbitrageType;
+    use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::CycleDirection;
+
+    fn update(strategy_id: ObjectId, owner: ObjectId, profit_percentage: f64) -> OpportunityUpdate {
+        OpportunityUpdate {
+            strategy_id,
+            owner,
+            arbitrage_type: ArbitrageType::Triangular,
+            exchanges: vec![ObjectId::new()],
+            direction: CycleDirection::Forward,
+            start_asset: "USDT".to_string(),
+            profit_percentage,
+            gross_profit_percentage: profit_percentage,
+            timestamp: 0.0,
+        }
+    }
+
+    #[test]
+    fn sends_spreads_above_threshold_and_the_crossing_below() {
+        let (strategy_id, owner) = (ObjectId::new(), ObjectId::new());
+        let mut state = SubscriptionState::new(owner);
+        state.subscribe(Subscription { arbitrage_types: vec![ArbitrageType::Triangular], min_profit: Some(0.5), ..Default::default() });
+
+        assert!(state.message_for(update(strategy_id, owner, 0.2)).is_none());
+        assert!(matches!(state.message_for(update(strategy_id, owner, 0.7)), Some(ServerMessage::Opportunity { below_threshold: false, .. })));
+        assert!(matches!(state.message_for(update(strategy_id, owner, 0.1)), Some(ServerMessage::Opportunity { below_threshold: true, .. })));
+        assert!(state.message_for(update(strategy_id, owner, 0.1)).is_none());
+
+        state.subscribe(Subscription { strategy_ids: vec![ObjectId::new()], ..Default::default() });
+        assert!(state.message_for(update(strategy_id, owner, 0.7)).is_none());
+    }
+
+    #[test]
+    fn connections_only_receive_their_own_strategies() {
+        // Dos usuarios con estrategias en el mismo exchange y suscritos a ese exchange
+        let exchange = ObjectId::new();
+        let (alice, bob) = (ObjectId::new(), ObjectId::new());
+        let (alice_strategy, bob_strategy) = (ObjectId::new(), ObjectId::new());
+        let on_exchange = |strategy_id, owner| OpportunityUpdate { exchanges: vec![exchange], ..update(strategy_id, owner, 0.7) };
+
+        let mut alice_state = SubscriptionState::new(alice);
+        let mut bob_state = SubscriptionState::new(bob);
+        for state in [&mut alice_state, &mut bob_state] {
+            state.subscribe(Subscription { exchanges: vec![exchange], ..Default::default() });
+        }
+
+        assert!(alice_state.message_for(on_exchange(alice_strategy, alice)).is_some());
+        assert!(alice_state.message_for(on_exchange(bob_strategy, bob)).is_none());
+        assert!(bob_state.message_for(on_exchange(bob_strategy, bob)).is_some());
+        assert!(bob_state.message_for(on_exchange(alice_strategy, alice)).is_none());
+
+        // Ni siquiera pidiendo la estrategia ajena por id
+        alice_state.subscribe(Subscription { strategy_ids: vec![bob_strategy], ..Default::default() });
+        assert!(alice_state.message_for(on_exchange(bob_strategy, bob)).is_none());
+    }
+}
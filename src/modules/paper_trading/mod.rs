@@ -0,0 +1,13 @@
+pub mod paper_trading_schema;
+pub mod simulated_exchange;
+pub mod paper_trading_service;
+pub mod paper_trading_controller;
+
+use actix_web::web;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(paper_trading_controller::get_balances);
+    cfg.service(paper_trading_controller::deposit);
+    cfg.service(paper_trading_controller::execute_strategy);
+    cfg.service(paper_trading_controller::get_trades);
+}
@@ -0,0 +1,81 @@
+use actix_web::{get, post, web, HttpResponse};
+use crate::modules::paper_trading::paper_trading_service::{DepositRequest, PaperExecutionRequest, PaperTradingService};
+use crate::modules::paper_trading::paper_trading_schema::{PaperBalance, PaperTrade};
+use crate::db::repositories::Storage;
+use serde::{Deserialize};
+use crate::modules::auth::auth_response::ApiResponse;
+use crate::helpers::app_error::{parse_object_id, AppError};
+use crate::helpers::pagination::{PageQuery, Paginated};
+use crate::openapi::ErrorResponse;
+use crate::middleware::current_user::CurrentUser;
+use crate::middleware::permission_middleware::RequirePermission;
+use crate::modules::user::user_schema::Permission;
+
+#[derive(Deserialize)]
+struct ExecutionPath {
+    strategy_id: String,
+}
+
+#[utoipa::path(
+    tag = "paper_trading",
+    responses(
+        (status = 200, description = "Paper balances of the current user (not paginated)", body = ApiResponse<Vec<PaperBalance>>),
+    ),
+)]
+#[get("/paper_trading/balances")]
+pub async fn get_balances(user: CurrentUser, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let balances = PaperTradingService::get_balances(user.id, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Paper balances retrieved successfully", balances)))
+}
+
+#[utoipa::path(
+    tag = "paper_trading",
+    request_body = DepositRequest,
+    responses(
+        (status = 200, description = "Balances after the deposit", body = ApiResponse<Vec<PaperBalance>>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+    ),
+)]
+#[post("/paper_trading/balances", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn deposit(user: CurrentUser, deposit: web::Json<DepositRequest>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let balances = PaperTradingService::deposit(user.id, deposit.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Paper balance deposited successfully", balances)))
+}
+
+#[utoipa::path(
+    tag = "paper_trading",
+    params(("strategy_id" = String, Path, description = "Arbitrage strategy ID")),
+    request_body = PaperExecutionRequest,
+    responses(
+        (status = 200, description = "Simulated trade", body = ApiResponse<PaperTrade>),
+        (status = 400, description = "Invalid input", body = ErrorResponse),
+        (status = 403, description = "Insufficient permissions", body = ErrorResponse),
+        (status = 404, description = "Not found", body = ErrorResponse),
+    ),
+)]
+#[post("/paper_trading/execute/{strategy_id}", wrap = "RequirePermission(Permission::Trade)")]
+pub async fn execute_strategy(
+    user: CurrentUser,
+    path: web::Path<ExecutionPath>,
+    request: web::Json<PaperExecutionRequest>,
+    storage: web::Data<dyn Storage>
+) -> Result<HttpResponse, AppError> {
+    let strategy_id = parse_object_id(&path.strategy_id, "arbitrage strategy")?;
+
+    let trade = PaperTradingService::execute_strategy(user.id, strategy_id, request.into_inner(), storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Paper trade executed successfully", trade)))
+}
+
+#[utoipa::path(
+    tag = "paper_trading",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Page of paper trades of the current user", body = ApiResponse<Paginated<PaperTrade>>),
+    ),
+)]
+#[get("/paper_trading/trades")]
+pub async fn get_trades(user: CurrentUser, page: web::Query<PageQuery>, storage: web::Data<dyn Storage>) -> Result<HttpResponse, AppError> {
+    let trades = PaperTradingService::get_trades(user.id, &page, storage.get_ref()).await?;
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Paper trades retrieved successfully", trades)))
+}
@@ -0,0 +1,56 @@
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::ArbitrageType;
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::CycleDirection;
+use crate::modules::arbitrage_strategy::arbitrage_depth_service::DepthLegResult;
+
+// Saldo virtual de un usuario para un activo en un exchange (colección "paper_balances")
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PaperBalance {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _user: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _exchange: ObjectId,
+    pub asset: String,
+    pub amount: f64,
+    #[serde(default)]
+    pub updated_at: f64,
+}
+
+// Ejecución simulada de una pata contra el libro de órdenes
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PaperFill {
+    #[schema(value_type = ObjectIdSchema)]
+    pub _exchange: ObjectId,
+    #[serde(flatten)]
+    pub fill: DepthLegResult,
+}
+
+// Ciclo de arbitraje ejecutado en modo paper (colección "paper_trades")
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PaperTrade {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<ObjectIdSchema>)]
+    pub id: Option<ObjectId>,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _user: ObjectId,
+    #[schema(value_type = ObjectIdSchema)]
+    pub _arbitrage_strategy: ObjectId,
+    pub arbitrage_type: ArbitrageType,
+    pub direction: CycleDirection,
+    pub start_asset: String,
+    pub start_amount: f64,
+    pub final_amount: f64,
+    pub realized_pnl: f64,
+    pub profit_percentage: f64,
+    pub fills: Vec<PaperFill>,
+    // true si los libros se enviaron en la petición en lugar de usar los almacenados; esas
+    // ejecuciones son simulaciones que no mueven saldos ni se guardan
+    pub replayed: bool,
+    pub created_at: f64,
+}
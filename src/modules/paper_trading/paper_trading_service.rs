@@ -0,0 +1,254 @@
+use crate::db::repositories::{PaperTradingRepository, Storage};
+use crate::helpers::app_error::AppError;
+use crate::helpers::pagination::{PageQuery, Paginated};
+use mongodb::bson::oid::ObjectId;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::ExecutionMode;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::ArbitrageStrategyService;
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::{profit_percentage, ArbitrageEvaluationService, CycleDirection, RouteSelection};
+use crate::modules::asset_equivalence::asset_equivalence_service::EquivalenceRegistry;
+use crate::modules::exchange::exchange_service::ExchangeService;
+use crate::modules::market_data::market_data_schema::OrderBook;
+use crate::modules::market_data::market_data_service::MarketDataService;
+use crate::modules::paper_trading::paper_trading_schema::{PaperBalance, PaperFill, PaperTrade};
+use crate::modules::paper_trading::simulated_exchange::{Balances, SimulatedExchange};
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use crate::openapi::ObjectIdSchema;
+use std::collections::HashMap;
+use tracing::info;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DepositRequest {
+    #[schema(value_type = ObjectIdSchema)]
+    pub exchange: ObjectId,
+    pub asset: String,
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PaperExecutionRequest {
+    pub amount: f64,
+    // Si se indica, el ciclo solo se ejecuta cuando el beneficio neto alcanza este porcentaje
+    pub min_profit: Option<f64>,
+    // Snapshots a reproducir en lugar de los últimos libros almacenados; la ejecución es en seco
+    pub order_books: Option<Vec<OrderBook>>,
+    // Por defecto se ejecuta el recorrido guardado; "best" permite el inverso si rinde más
+    #[serde(default)]
+    pub direction: RouteSelection,
+}
+
+struct ExecutedRoute {
+    direction: CycleDirection,
+    start_asset: String,
+    fills: Vec<PaperFill>,
+    final_amount: f64,
+    simulated: SimulatedExchange,
+}
+
+pub struct PaperTradingService;
+
+impl PaperTradingService {
+    pub async fn deposit(user_id: ObjectId, deposit: DepositRequest, storage: &dyn Storage) -> Result<Vec<PaperBalance>, AppError> {
+        if deposit.amount <= 0.0 {
+            return Err(AppError::Validation("Deposit amount must be greater than zero".to_string()));
+        }
+        if deposit.asset.trim().is_empty() {
+            return Err(AppError::Validation("asset is required".to_string()));
+        }
+        ExchangeService::get_exchange(deposit.exchange, storage).await?;
+
+        let mut deltas = Balances::new();
+        deltas.insert((deposit.exchange, deposit.asset.trim().to_string()), deposit.amount);
+        storage.apply_paper_deltas(user_id, &deltas, Utc::now().timestamp() as f64).await?;
+
+        Self::get_balances(user_id, storage).await
+    }
+
+    pub async fn get_balances(user_id: ObjectId, repo: &dyn PaperTradingRepository) -> Result<Vec<PaperBalance>, AppError> {
+        repo.find_paper_balances(user_id).await
+    }
+
+    pub async fn get_trades(user_id: ObjectId, page: &PageQuery, repo: &dyn PaperTradingRepository) -> Result<Paginated<PaperTrade>, AppError> {
+        repo.list_paper_trades(user_id, page).await
+    }
+
+    // Ejecuta el recorrido seleccionado más rentable de la estrategia contra el exchange simulado y registra el ciclo
+    pub async fn execute_strategy(
+        user_id: ObjectId,
+        strategy_id: ObjectId,
+        request: PaperExecutionRequest,
+        storage: &dyn Storage
+    ) -> Result<PaperTrade, AppError> {
+        let strategy = ArbitrageStrategyService::get_owned_arbitrage_strategy(strategy_id, user_id, storage).await?;
+        if strategy.execution_mode != ExecutionMode::Paper {
+            return Err(AppError::Validation("Arbitrage strategy is not armed in paper mode".to_string()));
+        }
+
+        let legs = ArbitrageStrategyService::populate_legs(&strategy.legs, storage).await?;
+        let pair_ids = ArbitrageStrategyService::leg_pair_ids(&strategy.legs);
+        let replayed = request.order_books.is_some();
+        let books: HashMap<ObjectId, OrderBook> = match request.order_books {
+            Some(books) => books.into_iter().map(|b| (b._market_pair, b)).collect(),
+            None => MarketDataService::get_order_books(&pair_ids, storage).await?,
+        };
+        let equivalences = EquivalenceRegistry::load(storage).await?;
+
+        let balances: Balances = Self::get_balances(user_id, storage).await?
+            .into_iter()
+            .map(|b| ((b._exchange, b.asset), b.amount))
+            .collect();
+
+        let mut best: Option<ExecutedRoute> = None;
+        let mut last_error = None;
+        for route in ArbitrageEvaluationService::cycle_routes(&strategy.arbitrage_type, &legs, request.direction)? {
+            let mut simulated = SimulatedExchange::new(books.clone(), balances.clone());
+            let executed = ArbitrageEvaluationService::check_route(&route, &equivalences)
+                .and_then(|_| simulated.execute_cycle(request.amount, &route.legs));
+
+            match executed {
+                Ok((fills, final_amount)) => {
+                    if best.as_ref().is_none_or(|b| final_amount > b.final_amount) {
+                        best = Some(ExecutedRoute { direction: route.direction, start_asset: route.start_asset.clone(), fills, final_amount, simulated });
+                    }
+                },
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        let ExecutedRoute { direction, start_asset, fills, final_amount, simulated } = best
+            .ok_or_else(|| last_error.unwrap_or_else(|| AppError::Validation("No executable route for strategy".to_string())))?;
+        let profit = profit_percentage(request.amount, final_amount);
+        if let Some(min_profit) = request.min_profit {
+            if profit < min_profit {
+                return Err(AppError::Validation(format!("Net profit {:.4}% is below the minimum of {}%", profit, min_profit)));
+            }
+        }
+
+        let trade = PaperTrade {
+            id: None,
+            _user: user_id,
+            _arbitrage_strategy: strategy_id,
+            arbitrage_type: strategy.arbitrage_type,
+            direction,
+            start_asset,
+            start_amount: request.amount,
+            final_amount,
+            realized_pnl: final_amount - request.amount,
+            profit_percentage: profit,
+            fills,
+            replayed,
+            created_at: Utc::now().timestamp() as f64,
+        };
+
+        // Los libros del cliente no son de fiar: solo los almacenados pueden mover saldos
+        if replayed {
+            return Ok(trade);
+        }
+
+        let deltas: Balances = simulated.balances().iter()
+            .map(|(key, amount)| (key.clone(), amount - balances.get(key).copied().unwrap_or(0.0)))
+            .filter(|(_, delta)| *delta != 0.0)
+            .collect();
+        storage.apply_paper_deltas(user_id, &deltas, trade.created_at).await?;
+
+        let trade = storage.insert_paper_trade(trade).await?;
+        info!("Paper trade for strategy {} realized {:.6} {}", strategy_id, trade.realized_pnl, trade.start_asset);
+        Ok(trade)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::memory::InMemoryStorage;
+    use crate::db::repositories::{AssetRepository, ExchangeRepository, MarketDataRepository, MarketPairRepository, StrategyRepository};
+    use crate::helpers::test_fixtures::{asset, exchange};
+    use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{ArbitrageStrategy, ArbitrageType, Leg, TradeSide};
+    use crate::modules::asset_equivalence::asset_equivalence_service::AssetEquivalenceService;
+    use crate::modules::market_data::market_data_schema::OrderBookLevel;
+    use crate::modules::market_pair::market_pair_schema::MarketPair;
+
+    fn book(market_pair: ObjectId, exchange: ObjectId, bid: f64, ask: f64) -> OrderBook {
+        let level = |price| vec![OrderBookLevel { price, amount: 10.0 }];
+        OrderBook { id: None, _market_pair: market_pair, _exchange: exchange, bids: level(bid), asks: level(ask), timestamp: 0.0 }
+    }
+
+    // Estrategia Exchange en modo paper: compra ETH/USDT en A y lo vende en B; devuelve los ids (A, B, estrategia, par A, par B)
+    async fn seed(storage: &InMemoryStorage, owner: ObjectId) -> (ObjectId, ObjectId, ObjectId, ObjectId, ObjectId) {
+        AssetEquivalenceService::ensure_defaults(storage).await.unwrap();
+        let mut ids = Vec::new();
+        for name in ["A", "B"] {
+            let exchange = storage.insert_exchange(exchange(name)).await.unwrap();
+            let eth = storage.insert_asset(asset(&exchange, "ETH")).await.unwrap();
+            let usdt = storage.insert_asset(asset(&exchange, "USDT")).await.unwrap();
+            let pair = storage.insert_market_pair(MarketPair {
+                id: None,
+                _exchange: exchange.id.unwrap(),
+                _base_asset: eth.id.unwrap(),
+                _quote_asset: usdt.id.unwrap(),
+                maker_fee: None,
+                taker_fee: None,
+                created_at: 0.0,
+                updated_at: 0.0,
+                status: true,
+            }).await.unwrap();
+            ids.push((exchange.id.unwrap(), pair.id.unwrap()));
+        }
+        let [(a, pair_a), (b, pair_b)] = [ids[0], ids[1]];
+
+        let strategy = storage.insert_strategy(ArbitrageStrategy {
+            id: None,
+            arbitrage_type: ArbitrageType::Exchange,
+            legs: vec![
+                Leg { market_pair: pair_a, side: TradeSide::Buy, exchange: a },
+                Leg { market_pair: pair_b, side: TradeSide::Sell, exchange: b },
+            ],
+            statistical: None,
+            created_at: 0.0,
+            updated_at: 0.0,
+            status: true,
+            execution_mode: ExecutionMode::Paper,
+            _owner: Some(owner),
+        }).await.unwrap();
+        (a, b, strategy.id.unwrap(), pair_a, pair_b)
+    }
+
+    #[actix_web::test]
+    async fn only_stored_order_books_move_balances() {
+        let storage = InMemoryStorage::default();
+        let user = ObjectId::new();
+        let (a, b, strategy, pair_a, pair_b) = seed(&storage, user).await;
+        let deposit = DepositRequest { exchange: a, asset: "USDT".to_string(), amount: 500.0 };
+        PaperTradingService::deposit(user, deposit, &storage).await.unwrap();
+        let before = PaperTradingService::get_balances(user, &storage).await.unwrap();
+
+        // Libros inventados por el cliente con un spread enorme: se simula pero no cambia nada
+        let replay = PaperExecutionRequest {
+            amount: 200.0,
+            min_profit: None,
+            order_books: Some(vec![book(pair_a, a, 49.0, 50.0), book(pair_b, b, 100.0, 101.0)]),
+            direction: RouteSelection::Stored,
+        };
+        let trade = PaperTradingService::execute_strategy(user, strategy, replay, &storage).await.unwrap();
+        assert!(trade.replayed && trade.id.is_none());
+        assert_eq!(trade.final_amount, 400.0);
+        let after: Vec<f64> = PaperTradingService::get_balances(user, &storage).await.unwrap().iter().map(|b| b.amount).collect();
+        assert_eq!(after, before.iter().map(|b| b.amount).collect::<Vec<_>>());
+        assert_eq!(PaperTradingService::get_trades(user, &PageQuery::default(), &storage).await.unwrap().total, 0);
+
+        // Con los libros almacenados la ejecución sí se aplica y se registra
+        storage.store_order_book(book(pair_a, a, 99.0, 100.0)).await.unwrap();
+        storage.store_order_book(book(pair_b, b, 102.0, 103.0)).await.unwrap();
+        let stored = PaperExecutionRequest { amount: 200.0, min_profit: None, order_books: None, direction: RouteSelection::Stored };
+        let trade = PaperTradingService::execute_strategy(user, strategy, stored, &storage).await.unwrap();
+        assert!(!trade.replayed && trade.id.is_some());
+        let balances: HashMap<(ObjectId, String), f64> = PaperTradingService::get_balances(user, &storage).await.unwrap()
+            .into_iter()
+            .map(|b| ((b._exchange, b.asset), b.amount))
+            .collect();
+        assert_eq!(balances[&(a, "USDT".to_string())], 300.0);
+        assert_eq!(balances[&(b, "USDT".to_string())], 204.0);
+        assert_eq!(PaperTradingService::get_trades(user, &PageQuery::default(), &storage).await.unwrap().total, 1);
+    }
+}
@@ -0,0 +1,110 @@
+use mongodb::bson::oid::ObjectId;
+use crate::helpers::app_error::AppError;
+use crate::modules::arbitrage_strategy::arbitrage_depth_service::ArbitrageDepthService;
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::TradeSide;
+use crate::modules::market_data::market_data_schema::OrderBook;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use crate::modules::paper_trading::paper_trading_schema::PaperFill;
+use std::collections::HashMap;
+
+// Tolerancia para redondeos al comparar saldos, también con los guardados
+pub const BALANCE_EPSILON: f64 = 1e-9;
+
+// Saldos indexados por (exchange, símbolo del activo)
+pub type Balances = HashMap<(ObjectId, String), f64>;
+
+// Exchange simulado: llena órdenes contra snapshots de libros (almacenados o reproducidos)
+// y mueve saldos virtuales. Un ciclo se ejecuta entero o no se ejecuta.
+pub struct SimulatedExchange {
+    books: HashMap<ObjectId, OrderBook>,
+    balances: Balances,
+}
+
+impl SimulatedExchange {
+    pub fn new(books: HashMap<ObjectId, OrderBook>, balances: Balances) -> Self {
+        Self { books, balances }
+    }
+
+    pub fn balances(&self) -> &Balances {
+        &self.balances
+    }
+
+    pub fn execute_cycle(&mut self, start_amount: f64, legs: &[(&PopulatedMarketPair, TradeSide)]) -> Result<(Vec<PaperFill>, f64), AppError> {
+        if start_amount <= 0.0 {
+            return Err(AppError::Validation("Amount must be greater than zero".to_string()));
+        }
+        let (results, final_amount) = ArbitrageDepthService::walk_legs(start_amount, legs, &self.books)?;
+
+        let mut balances = self.balances.clone();
+        let mut fills = Vec::with_capacity(results.len());
+        for (index, (result, (pair, _))) in results.iter().zip(legs).enumerate() {
+            let exchange_id = pair.exchange.id.ok_or_else(|| AppError::Internal("Exchange without id".to_string()))?;
+
+            let source = balances.entry((exchange_id, result.asset_in.clone())).or_insert(0.0);
+            if *source + BALANCE_EPSILON < result.amount_in {
+                return Err(AppError::Validation(format!(
+                    "Insufficient {} balance on {}: {} available, {} required",
+                    result.asset_in, pair.exchange.short_name, source, result.amount_in
+                )));
+            }
+            *source = (*source - result.amount_in).max(0.0);
+
+            // Lo recibido se transfiere al exchange de la siguiente pata, con el símbolo que usa esa pata
+            let destination = match (legs.get(index + 1), results.get(index + 1)) {
+                (Some((next, _)), Some(next_result)) => {
+                    let next_exchange = next.exchange.id.ok_or_else(|| AppError::Internal("Exchange without id".to_string()))?;
+                    (next_exchange, next_result.asset_in.clone())
+                },
+                _ => (exchange_id, result.asset_out.clone()),
+            };
+            *balances.entry(destination).or_insert(0.0) += result.amount_out;
+
+            fills.push(PaperFill { _exchange: exchange_id, fill: result.clone() });
+        }
+
+        self.balances = balances;
+        Ok((fills, final_amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_fixtures::{exchange, pair};
+    use crate::modules::market_data::market_data_schema::OrderBookLevel;
+
+    fn book(pair: &PopulatedMarketPair, bid: f64, ask: f64) -> (ObjectId, OrderBook) {
+        let id = pair.id.unwrap();
+        let level = |price| vec![OrderBookLevel { price, amount: 10.0 }];
+        (id, OrderBook { id: None, _market_pair: id, _exchange: pair.exchange.id.unwrap(), bids: level(bid), asks: level(ask), timestamp: 0.0 })
+    }
+
+    #[test]
+    fn moves_balances_across_exchanges() {
+        let (a, b) = (exchange("A"), exchange("B"));
+        let (pair1, pair2) = (pair(&a, "ETH", "USDT"), pair(&b, "ETH", "USDT"));
+        let books: HashMap<_, _> = [book(&pair1, 99.0, 100.0), book(&pair2, 102.0, 103.0)].into_iter().collect();
+        let (a_id, b_id) = (a.id.unwrap(), b.id.unwrap());
+        let mut simulated = SimulatedExchange::new(books, [((a_id, "USDT".to_string()), 500.0)].into_iter().collect());
+
+        let (fills, final_amount) = simulated.execute_cycle(200.0, &[(&pair1, TradeSide::Buy), (&pair2, TradeSide::Sell)]).unwrap();
+
+        assert_eq!(fills.len(), 2);
+        assert_eq!(final_amount, 204.0);
+        assert_eq!(simulated.balances()[&(a_id, "USDT".to_string())], 300.0);
+        assert_eq!(simulated.balances()[&(b_id, "USDT".to_string())], 204.0);
+        assert_eq!(simulated.balances()[&(b_id, "ETH".to_string())], 0.0);
+    }
+
+    #[test]
+    fn rejects_cycle_without_funds() {
+        let a = exchange("A");
+        let (pair1, pair2) = (pair(&a, "ETH", "USDT"), pair(&a, "ETH", "USDC"));
+        let books: HashMap<_, _> = [book(&pair1, 99.0, 100.0), book(&pair2, 102.0, 103.0)].into_iter().collect();
+        let balances: Balances = [((a.id.unwrap(), "USDT".to_string()), 50.0)].into_iter().collect();
+        let mut simulated = SimulatedExchange::new(books, balances.clone());
+
+        assert!(simulated.execute_cycle(200.0, &[(&pair1, TradeSide::Buy), (&pair2, TradeSide::Sell)]).is_err());
+        assert_eq!(simulated.balances(), &balances);
+    }
+}
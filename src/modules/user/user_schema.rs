@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
 use mongodb::bson::oid::ObjectId;
 use chrono::NaiveDateTime;
 
@@ -13,6 +14,62 @@ pub struct User {
     pub _default_market_pair: Option<ObjectId>,
     pub password_reset_token: String,
     pub password_reset_expires: NaiveDateTime,
-    pub tokens: Vec<String>, // Almacenar tokens activos
-    pub role: String, // Almacenar el rol del usuario (e.g., "user", "admin")
+    #[serde(default)]
+    pub tokens: Vec<Session>, // Sesiones activas (una por dispositivo)
+    #[serde(default)]
+    pub role: Role, // Rol del usuario; los documentos antiguos con "user" se leen como viewer
+}
+
+// Sesión de un dispositivo. El access token lleva su id (sid) y el refresh token se guarda hasheado
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Session {
+    pub id: String,
+    pub refresh_token_hash: String,
+    pub user_agent: String,
+    pub created_at: f64,
+    pub last_used_at: f64,
+    pub expires_at: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Operator,
+    #[default]
+    #[serde(alias = "user")]
+    Viewer,
+}
+
+// Acciones protegidas por rol; la lectura está permitida a cualquier usuario autenticado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ManageCatalog, // Exchanges, assets, pares, equivalencias e ingesta de mercado
+    Trade,         // Estrategias, backtests, paper trading y alertas
+    ManageUsers,
+}
+
+impl Role {
+    pub fn allows(&self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Operator => permission != Permission::ManageUsers,
+            Role::Viewer => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_user_role_is_read_only() {
+        let role: Role = serde_json::from_str("\"user\"").unwrap();
+        assert_eq!(role, Role::Viewer);
+        assert!(!role.allows(Permission::ManageCatalog));
+        assert!(Role::Operator.allows(Permission::ManageCatalog));
+        assert!(!Role::Operator.allows(Permission::ManageUsers));
+        assert!(Role::Admin.allows(Permission::ManageUsers));
+    }
 }
@@ -0,0 +1,273 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use serde::Serialize;
+use std::sync::OnceLock;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+use crate::helpers::app_error::FieldError;
+use crate::middleware::auth_middleware::API_KEY_HEADER;
+use crate::modules::account::account_service::{UpdateRoleRequest, UpdateUserRequest, UserResponse};
+use crate::modules::alert::alert_schema::{AlertDelivery, AlertPayload, AlertRule, DeliveryAttempt, DeliveryStatus};
+use crate::modules::api_key::api_key_schema::{ApiKey as PersonalApiKey, ApiKeyScope};
+use crate::modules::api_key::api_key_service::{CreateApiKeyRequest, CreatedApiKey};
+use crate::modules::arbitrage_strategy::arbitrage_cycle_service::{CycleLeg, DetectedCycle};
+use crate::modules::arbitrage_strategy::arbitrage_depth_service::{DepthEvaluation, DepthLegResult};
+use crate::modules::arbitrage_strategy::arbitrage_evaluation_service::{CycleDirection, EvaluationResult, LegResult, RouteSelection};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::{
+    ArbitrageStrategy, ArbitrageType, ExecutionMode, Leg, StatisticalParams, TradeSide,
+};
+use crate::modules::arbitrage_strategy::arbitrage_strategy_service::{PopulatedArbitrageStrategy, PopulatedLeg};
+use crate::modules::arbitrage_strategy::statistical_arbitrage_service::{StatisticalSignal, ZScoreResult};
+use crate::modules::asset::asset_schema::Asset;
+use crate::modules::asset_equivalence::asset_equivalence_schema::AssetEquivalence;
+use crate::modules::auth::auth_model::{ForgotPasswordRequest, LoginRequest, RefreshRequest, RegisterRequest, ResetPasswordRequest};
+use crate::modules::auth::auth_service::AuthResponse;
+use crate::modules::auth::session_service::{SessionInfo, TokenPair};
+use crate::modules::backtest::backtest_schema::{BacktestJob, BacktestReport, BacktestSource, BacktestStatus, EquityPoint};
+use crate::modules::backtest::backtest_service::BacktestRequest;
+use crate::modules::exchange::exchange_schema::Exchange;
+use crate::modules::integrity::integrity_schema::{CatalogDocuments, OrphanReport};
+use crate::modules::market_pair::market_pair_schema::MarketPair;
+use crate::modules::market_data::market_data_schema::{MarketQuote, OrderBook, OrderBookLevel};
+use crate::modules::market_data::market_data_service::IngestSummary;
+use crate::modules::market_pair::market_pair_service::PopulatedMarketPair;
+use crate::modules::paper_trading::paper_trading_schema::{PaperBalance, PaperFill, PaperTrade};
+use crate::modules::paper_trading::paper_trading_service::{DepositRequest, PaperExecutionRequest};
+use crate::modules::user::user_schema::Role;
+
+// Así serializa serde_json un ObjectId de bson. En las peticiones también se acepta el hex sin envolver.
+#[derive(Serialize, ToSchema)]
+#[schema(as = ObjectId, example = json!({ "$oid": "66a1f0c2e4b0a1b2c3d4e5f6" }))]
+pub struct ObjectIdSchema {
+    #[serde(rename = "$oid")]
+    pub oid: String,
+}
+
+// Forma de todas las respuestas de error (ver AppError)
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub message: String,
+    pub code: String,
+    // Solo en algunos errores de validación
+    pub errors: Option<Vec<FieldError>>,
+}
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))));
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Arbi Server API", description = "Catálogo de exchanges y mercados, y estrategias de arbitraje"),
+    paths(
+        crate::modules::auth::auth_controller::register,
+        crate::modules::auth::auth_controller::login,
+        crate::modules::auth::auth_controller::refresh,
+        crate::modules::auth::auth_controller::logout,
+        crate::modules::auth::auth_controller::get_sessions,
+        crate::modules::auth::auth_controller::revoke_all_sessions,
+        crate::modules::auth::auth_controller::forgot_password,
+        crate::modules::auth::auth_controller::reset_password,
+        crate::modules::exchange::exchange_controller::create_exchange,
+        crate::modules::exchange::exchange_controller::get_exchange,
+        crate::modules::exchange::exchange_controller::update_exchange,
+        crate::modules::exchange::exchange_controller::delete_exchange,
+        crate::modules::exchange::exchange_controller::get_all_exchanges,
+        crate::modules::asset::asset_controller::create_asset,
+        crate::modules::asset::asset_controller::get_asset,
+        crate::modules::asset::asset_controller::update_asset,
+        crate::modules::asset::asset_controller::delete_asset,
+        crate::modules::asset::asset_controller::get_all_assets,
+        crate::modules::market_pair::market_pair_controller::create_market_pair,
+        crate::modules::market_pair::market_pair_controller::get_market_pair,
+        crate::modules::market_pair::market_pair_controller::update_market_pair,
+        crate::modules::market_pair::market_pair_controller::delete_market_pair,
+        crate::modules::market_pair::market_pair_controller::get_all_market_pairs_with_pagination,
+        crate::modules::market_pair::market_pair_controller::get_market_pairs_by_exchange,
+        crate::modules::market_pair::market_pair_controller::get_conversion_pairs,
+        crate::modules::market_pair::market_pair_controller::get_conversion_pairs_for_arbitrage,
+        crate::modules::asset_equivalence::asset_equivalence_controller::create_asset_equivalence,
+        crate::modules::asset_equivalence::asset_equivalence_controller::get_asset_equivalence,
+        crate::modules::asset_equivalence::asset_equivalence_controller::update_asset_equivalence,
+        crate::modules::asset_equivalence::asset_equivalence_controller::delete_asset_equivalence,
+        crate::modules::asset_equivalence::asset_equivalence_controller::get_all_asset_equivalences,
+        crate::modules::market_data::market_data_controller::ingest_exchange,
+        crate::modules::market_data::market_data_controller::get_quote,
+        crate::modules::market_data::market_data_controller::get_order_book,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::create_arbitrage_strategy,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::get_arbitrage_strategy,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::update_arbitrage_strategy,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::delete_arbitrage_strategy,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::get_all_arbitrage_strategies,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::evaluate_arbitrage_strategy,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::get_arbitrage_strategy_z_score,
+        crate::modules::arbitrage_strategy::arbitrage_strategy_controller::get_arbitrage_strategy_depth,
+        crate::modules::arbitrage_strategy::suggested_arbitrage_strategy_controller::get_suggested_strategies,
+        crate::modules::arbitrage_strategy::arbitrage_cycle_controller::detect_arbitrage_cycles,
+        crate::modules::backtest::backtest_controller::create_backtest,
+        crate::modules::backtest::backtest_controller::get_strategy_backtests,
+        crate::modules::backtest::backtest_controller::get_backtest,
+        crate::modules::paper_trading::paper_trading_controller::get_balances,
+        crate::modules::paper_trading::paper_trading_controller::deposit,
+        crate::modules::paper_trading::paper_trading_controller::execute_strategy,
+        crate::modules::paper_trading::paper_trading_controller::get_trades,
+        crate::modules::alert::alert_controller::create_alert_rule,
+        crate::modules::alert::alert_controller::get_alert_rule,
+        crate::modules::alert::alert_controller::update_alert_rule,
+        crate::modules::alert::alert_controller::delete_alert_rule,
+        crate::modules::alert::alert_controller::get_alert_rules,
+        crate::modules::alert::alert_controller::get_alert_deliveries,
+        crate::modules::opportunity_stream::opportunity_stream_controller::stream_opportunities,
+        crate::modules::account::account_controller::get_user,
+        crate::modules::account::account_controller::update_user,
+        crate::modules::account::account_controller::update_user_role,
+        crate::modules::api_key::api_key_controller::create_api_key,
+        crate::modules::api_key::api_key_controller::get_api_keys,
+        crate::modules::api_key::api_key_controller::delete_api_key,
+        crate::modules::integrity::integrity_controller::get_orphans,
+    ),
+    components(schemas(
+        ObjectIdSchema, ErrorResponse, FieldError,
+        RegisterRequest, LoginRequest, RefreshRequest, ForgotPasswordRequest, ResetPasswordRequest,
+        AuthResponse, TokenPair, SessionInfo,
+        Exchange, Asset, MarketPair, PopulatedMarketPair,
+        ArbitrageType, ExecutionMode, ArbitrageStrategy, Leg, TradeSide, StatisticalParams,
+        PopulatedArbitrageStrategy, PopulatedLeg,
+        AssetEquivalence,
+        MarketQuote, OrderBook, OrderBookLevel, IngestSummary,
+        RouteSelection, CycleDirection, EvaluationResult, LegResult,
+        DepthEvaluation, DepthLegResult, StatisticalSignal, ZScoreResult,
+        DetectedCycle, CycleLeg,
+        BacktestRequest, BacktestJob, BacktestStatus, BacktestSource, BacktestReport, EquityPoint,
+        DepositRequest, PaperExecutionRequest, PaperBalance, PaperTrade, PaperFill,
+        AlertRule, AlertDelivery, AlertPayload, DeliveryAttempt, DeliveryStatus,
+        UpdateUserRequest, UpdateRoleRequest, UserResponse, Role,
+        CreateApiKeyRequest, CreatedApiKey, PersonalApiKey, ApiKeyScope,
+        CatalogDocuments,
+        OrphanReport,
+    )),
+    modifiers(&SecuritySchemes),
+    security(("bearer_auth" = []), ("api_key" = [])),
+    tags(
+        (name = "auth", description = "Registro, login y sesiones"),
+        (name = "exchanges"),
+        (name = "assets"),
+        (name = "market_pairs"),
+        (name = "asset_equivalences", description = "Activos equivalentes a una moneda fiat"),
+        (name = "market_data", description = "Cotizaciones y libros de órdenes"),
+        (name = "arbitrage_strategies"),
+        (name = "backtests"),
+        (name = "paper_trading", description = "Ejecución simulada de estrategias"),
+        (name = "alert_rules", description = "Alertas por webhook sobre estrategias"),
+        (name = "opportunities", description = "Oportunidades en tiempo real por WebSocket"),
+        (name = "account"),
+        (name = "api_keys", description = "API keys personales (cabecera X-API-Key)"),
+        (name = "integrity", description = "Referencias rotas entre documentos del catálogo"),
+    )
+)]
+pub struct ApiDoc;
+
+// El documento se genera una sola vez, en la primera petición
+static SPEC: OnceLock<String> = OnceLock::new();
+
+#[get("/openapi.json")]
+pub async fn openapi_json() -> impl Responder {
+    let spec = SPEC.get_or_init(|| ApiDoc::openapi().to_pretty_json().expect("OpenAPI document must serialize"));
+    HttpResponse::Ok().content_type("application/json").body(spec.as_str())
+}
+
+// Página mínima que carga Swagger UI desde CDN contra /openapi.json
+#[get("/docs")]
+pub async fn docs() -> impl Responder {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(DOCS_PAGE)
+}
+
+const DOCS_PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>Arbi Server API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.onload = () => { window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" }); };
+  </script>
+</body>
+</html>
+"##;
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(openapi_json);
+    cfg.service(docs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_strategy_legs() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &spec["components"]["schemas"];
+        assert_eq!(schemas["ArbitrageStrategy"]["properties"]["legs"]["items"]["$ref"], "#/components/schemas/Leg");
+        assert_eq!(schemas["TradeSide"]["enum"], serde_json::json!(["Buy", "Sell"]));
+        assert!(spec["paths"]["/arbitrage-strategies/{id}"]["get"].is_object());
+    }
+
+    // Rutas declaradas con #[get("...")], #[post("...")], etc. en los archivos de src
+    fn declared_routes(dir: &std::path::Path, routes: &mut Vec<(String, String)>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                declared_routes(&path, routes);
+                continue;
+            }
+            if path.extension().is_none_or(|ext| ext != "rs") {
+                continue;
+            }
+            for line in std::fs::read_to_string(&path).unwrap().lines() {
+                let Some(attribute) = line.trim().strip_prefix("#[") else { continue };
+                for method in ["get", "post", "put", "delete", "patch"] {
+                    let Some(rest) = attribute.strip_prefix(method).and_then(|rest| rest.strip_prefix("(\"")) else { continue };
+                    let route = rest.split('"').next().unwrap();
+                    routes.push((method.to_string(), route.to_string()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn documents_every_route() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let mut routes = Vec::new();
+        declared_routes(&std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut routes);
+        assert!(routes.len() > 30);
+
+        let missing: Vec<_> = routes.iter()
+            .filter(|(_, route)| route != "/openapi.json" && route != "/docs")
+            .filter(|(method, route)| !spec["paths"][route.as_str()][method.as_str()].is_object())
+            .collect();
+        assert!(missing.is_empty(), "Routes missing from the OpenAPI document: {:?}", missing);
+    }
+
+    #[test]
+    fn declares_both_security_schemes() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemes = &spec["components"]["securitySchemes"];
+        assert_eq!(schemes["bearer_auth"]["scheme"], "bearer");
+        assert_eq!(schemes["api_key"]["in"], "header");
+        assert_eq!(schemes["api_key"]["name"], API_KEY_HEADER);
+        assert_eq!(spec["security"], serde_json::json!([{ "bearer_auth": [] }, { "api_key": [] }]));
+    }
+}
@@ -7,4 +7,405 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.configure(crate::modules::market_pair::init); // Añadir el módulo de market_pair
     cfg.configure(crate::modules::exchange::init);
     cfg.configure(crate::modules::arbitrage_strategy::init);
+    cfg.configure(crate::modules::market_data::init);
+    cfg.configure(crate::modules::asset_equivalence::init);
+    cfg.configure(crate::modules::paper_trading::init);
+    cfg.configure(crate::modules::backtest::init);
+    cfg.configure(crate::modules::opportunity_stream::init);
+    cfg.configure(crate::modules::alert::init);
+    cfg.configure(crate::modules::api_key::init);
+    cfg.configure(crate::modules::integrity::init);
+    cfg.configure(crate::openapi::init);
+}
+
+// Tests de la aplicación completa sobre InMemoryStorage: rutas, middleware y servicios reales sin MongoDB
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use actix_web::body::{BoxBody, EitherBody};
+    use actix_web::dev::{Service, ServiceResponse};
+    use actix_web::http::StatusCode;
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use crate::db::memory::InMemoryStorage;
+    use crate::db::repositories::{AlertRepository, AssetRepository, BacktestRepository, ExchangeRepository, MarketPairRepository, Storage, StrategyRepository, UserRepository};
+    use crate::helpers::test_fixtures;
+    use crate::middleware::auth_middleware::Auth;
+    use crate::modules::alert::alert_schema::AlertRule;
+    use crate::modules::arbitrage_strategy::arbitrage_strategy_schema::ArbitrageStrategy;
+    use crate::modules::asset_equivalence::asset_equivalence_service::AssetEquivalenceService;
+    use crate::modules::backtest::backtest_schema::{BacktestJob, BacktestSource, BacktestStatus};
+    use crate::modules::exchange::exchange_schema::Exchange;
+    use crate::modules::market_pair::market_pair_schema::MarketPair;
+    use crate::modules::user::user_schema::{Role, User};
+
+    const PASSWORD: &str = "correct horse";
+
+    // La aplicación de test responde con EitherBody por los middlewares
+    trait TestApp: Service<actix_http::Request, Response = ServiceResponse<EitherBody<BoxBody>>, Error = actix_web::Error> {}
+    impl<S> TestApp for S where S: Service<actix_http::Request, Response = ServiceResponse<EitherBody<BoxBody>>, Error = actix_web::Error> {}
+
+    async fn app(storage: Arc<InMemoryStorage>) -> impl TestApp {
+        std::env::set_var("SECRET_KEY", "test-secret");
+        AssetEquivalenceService::ensure_defaults(storage.as_ref()).await.unwrap();
+        test::init_service(
+            App::new()
+                .wrap(Auth)
+                .app_data(web::Data::from(storage as Arc<dyn Storage>))
+                .configure(configure),
+        ).await
+    }
+
+    async fn seed_user(storage: &InMemoryStorage, email: &str, role: Role) {
+        storage.insert_user(User {
+            id: None,
+            name: email.to_string(),
+            email: email.to_string(),
+            password: bcrypt::hash(PASSWORD, 4).unwrap(),
+            _default_asset: None,
+            _default_market_pair: None,
+            password_reset_token: String::new(),
+            password_reset_expires: chrono::Utc::now().naive_utc(),
+            tokens: vec![],
+            role,
+        }).await.unwrap();
+    }
+
+    // Guarda el exchange con sus activos BTC y USDT y el par BTC/USDT; devuelve el id del par
+    async fn seed_btc_usdt(storage: &InMemoryStorage, exchange: &Exchange) -> ObjectId {
+        let btc = test_fixtures::asset(exchange, "BTC");
+        let usdt = test_fixtures::asset(exchange, "USDT");
+        storage.insert_exchange(exchange.clone()).await.unwrap();
+        storage.insert_asset(btc.clone()).await.unwrap();
+        storage.insert_asset(usdt.clone()).await.unwrap();
+        let pair = storage.insert_market_pair(MarketPair {
+            id: None,
+            _exchange: exchange.id.unwrap(),
+            _base_asset: btc.id.unwrap(),
+            _quote_asset: usdt.id.unwrap(),
+            maker_fee: None,
+            taker_fee: None,
+            created_at: 0.0,
+            updated_at: 0.0,
+            status: true,
+        }).await.unwrap();
+        pair.id.unwrap()
+    }
+
+    fn leg(market_pair: ObjectId, side: &str, exchange: &Exchange) -> Value {
+        json!({ "market_pair": market_pair.to_hex(), "side": side, "exchange": exchange.id.unwrap().to_hex() })
+    }
+
+    async fn login(app: &impl TestApp, email: &str) -> String {
+        let req = test::TestRequest::post().uri("/login").set_json(json!({ "email": email, "password": PASSWORD })).to_request();
+        let body: Value = test::call_and_read_body_json(app, req).await;
+        body["data"]["token"].as_str().expect("login should return a token").to_string()
+    }
+
+    async fn call(
+        app: &impl TestApp,
+        req: test::TestRequest,
+        token: &str,
+    ) -> (StatusCode, Value) {
+        let resp = test::call_service(app, req.insert_header(("Authorization", format!("Bearer {}", token))).to_request()).await;
+        let status = resp.status();
+        (status, test::read_body_json(resp).await)
+    }
+
+    #[actix_web::test]
+    async fn catalog_crud_goes_through_auth_and_permissions() {
+        let storage = Arc::new(InMemoryStorage::default());
+        seed_user(&storage, "admin@example.com", Role::Admin).await;
+        seed_user(&storage, "viewer@example.com", Role::Viewer).await;
+        let app = app(storage).await;
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/exchanges").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let admin = login(&app, "admin@example.com").await;
+        let exchange = json!({ "name": "Kraken", "short_name": "kraken", "url": "", "created_at": 0.0, "updated_at": 0.0 });
+        let (status, created) = call(&app, test::TestRequest::post().uri("/exchanges").set_json(&exchange), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        let id = created["data"]["_id"]["$oid"].as_str().unwrap().to_string();
+
+        let (status, page) = call(&app, test::TestRequest::get().uri("/exchanges?per_page=10"), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["data"]["total"], 1);
+        assert_eq!(page["data"]["items"][0]["short_name"], "kraken");
+
+        let (status, error) = call(&app, test::TestRequest::get().uri("/exchanges/not-an-id"), &admin).await;
+        assert_eq!((status, error["code"].as_str()), (StatusCode::BAD_REQUEST, Some("validation_error")));
+        let (status, _) = call(&app, test::TestRequest::get().uri(&format!("/exchanges/{}", ObjectId::new())), &admin).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let viewer = login(&app, "viewer@example.com").await;
+        let (status, _) = call(&app, test::TestRequest::delete().uri(&format!("/exchanges/{}", id)), &viewer).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(&app, test::TestRequest::delete().uri(&format!("/exchanges/{}", id)), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn strategies_are_scoped_to_their_owner() {
+        let storage = Arc::new(InMemoryStorage::default());
+        seed_user(&storage, "alice@example.com", Role::Operator).await;
+        seed_user(&storage, "bob@example.com", Role::Operator).await;
+
+        let binance = test_fixtures::exchange("binance");
+        let kraken = test_fixtures::exchange("kraken");
+        let binance_pair = seed_btc_usdt(&storage, &binance).await;
+        let kraken_pair = seed_btc_usdt(&storage, &kraken).await;
+        let app = app(storage).await;
+
+        let alice = login(&app, "alice@example.com").await;
+        let strategy = json!({
+            "arbitrage_type": "Exchange",
+            "legs": [leg(binance_pair, "Buy", &binance), leg(kraken_pair, "Sell", &kraken)],
+            "status": true,
+        });
+        let (status, created) = call(&app, test::TestRequest::post().uri("/arbitrage-strategies").set_json(&strategy), &alice).await;
+        assert_eq!(status, StatusCode::OK);
+        let id = created["data"]["_id"]["$oid"].as_str().unwrap().to_string();
+
+        let (_, page) = call(&app, test::TestRequest::get().uri("/arbitrage-strategies"), &alice).await;
+        assert_eq!(page["data"]["total"], 1);
+        assert_eq!(page["data"]["items"][0]["legs"][1]["market_pair"]["exchange"]["short_name"], "kraken");
+        assert_eq!(page["data"]["items"][0]["legs"][1]["side"], "Sell");
+
+        // Dos compras seguidas no forman un ciclo
+        let broken = json!({
+            "arbitrage_type": "Exchange",
+            "legs": [leg(binance_pair, "Buy", &binance), leg(kraken_pair, "Buy", &kraken)],
+            "status": true,
+        });
+        let (status, error) = call(&app, test::TestRequest::post().uri("/arbitrage-strategies").set_json(&broken), &alice).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["errors"][0]["field"], "legs[1].side");
+
+        let bob = login(&app, "bob@example.com").await;
+        let (_, page) = call(&app, test::TestRequest::get().uri("/arbitrage-strategies"), &bob).await;
+        assert_eq!(page["data"]["total"], 0);
+        let (status, _) = call(&app, test::TestRequest::get().uri(&format!("/arbitrage-strategies/{}", id)), &bob).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    fn alert_rule(strategy: ObjectId) -> AlertRule {
+        AlertRule {
+            id: None,
+            _user: ObjectId::new(),
+            _arbitrage_strategy: strategy,
+            min_profit: 0.5,
+            min_duration: 0.0,
+            webhook_url: "https://example.com/hook".to_string(),
+            secret: String::new(),
+            status: true,
+            above_since: None,
+            fired: false,
+            created_at: 0.0,
+            updated_at: 0.0,
+        }
+    }
+
+    fn backtest(strategy: ObjectId) -> BacktestJob {
+        BacktestJob {
+            id: None,
+            _arbitrage_strategy: strategy,
+            status: BacktestStatus::Completed,
+            source: BacktestSource::History,
+            from: 0.0,
+            to: 0.0,
+            amount: 1.0,
+            min_profit: 0.0,
+            report: None,
+            error: None,
+            created_at: 0.0,
+            updated_at: 0.0,
+        }
+    }
+
+    #[actix_web::test]
+    async fn catalog_deletes_with_dependents_need_cascade() {
+        let storage = Arc::new(InMemoryStorage::default());
+        seed_user(&storage, "admin@example.com", Role::Admin).await;
+        let binance = test_fixtures::exchange("binance");
+        let kraken = test_fixtures::exchange("kraken");
+        let binance_pair = seed_btc_usdt(&storage, &binance).await;
+        let kraken_pair = seed_btc_usdt(&storage, &kraken).await;
+        let app = app(storage.clone()).await;
+
+        let admin = login(&app, "admin@example.com").await;
+        let strategy = json!({
+            "arbitrage_type": "Exchange",
+            "legs": [leg(binance_pair, "Buy", &binance), leg(kraken_pair, "Sell", &kraken)],
+            "status": true,
+        });
+        let (status, created) = call(&app, test::TestRequest::post().uri("/arbitrage-strategies").set_json(&strategy), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        let strategy_id = ObjectId::parse_str(created["data"]["_id"]["$oid"].as_str().unwrap()).unwrap();
+        let rule = storage.insert_alert_rule(alert_rule(strategy_id)).await.unwrap().id.unwrap();
+        let job = storage.insert_backtest(backtest(strategy_id)).await.unwrap().id.unwrap();
+
+        let uri = format!("/exchanges/{}", kraken.id.unwrap());
+        let (status, error) = call(&app, test::TestRequest::delete().uri(&uri), &admin).await;
+        assert_eq!((status, error["code"].as_str()), (StatusCode::CONFLICT, Some("conflict")));
+        assert!(error["message"].as_str().unwrap().contains(&kraken_pair.to_hex()));
+
+        let (status, deleted) = call(&app, test::TestRequest::delete().uri(&format!("{}?cascade=true", uri)), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(deleted["data"]["assets"].as_array().unwrap().len(), 2);
+        assert_eq!(deleted["data"]["market_pairs"][0]["$oid"], kraken_pair.to_hex());
+        assert_eq!(deleted["data"]["alert_rules"][0]["$oid"], rule.to_hex());
+        assert_eq!(deleted["data"]["backtests"][0]["$oid"], job.to_hex());
+        assert!(storage.find_backtest(job).await.unwrap().is_none());
+        let (_, page) = call(&app, test::TestRequest::get().uri("/arbitrage-strategies"), &admin).await;
+        assert_eq!(page["data"]["total"], 0);
+        let (_, page) = call(&app, test::TestRequest::get().uri("/market_pairs/with_pagination"), &admin).await;
+        assert_eq!(page["data"]["total"], 1);
+
+        // Un par que apunta a un activo borrado por fuera de la API queda huérfano
+        storage.delete_asset(storage.find_market_pair(binance_pair).await.unwrap().unwrap()._base_asset).await.unwrap();
+        let (status, orphans) = call(&app, test::TestRequest::get().uri("/integrity/orphans"), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(orphans["data"]["market_pairs"][0]["$oid"], binance_pair.to_hex());
+        assert_eq!(orphans["data"]["assets"].as_array().unwrap().len(), 0);
+        assert_eq!(orphans["data"]["unmigrated_strategies"].as_array().unwrap().len(), 0);
+        assert_eq!(orphans["data"]["backtests"].as_array().unwrap().len(), 0);
+
+        // Un backtest de una estrategia que ya no existe también queda huérfano
+        let stale = storage.insert_backtest(backtest(ObjectId::new())).await.unwrap().id.unwrap();
+        let (_, orphans) = call(&app, test::TestRequest::get().uri("/integrity/orphans"), &admin).await;
+        assert_eq!(orphans["data"]["backtests"][0]["$oid"], stale.to_hex());
+
+        // Una estrategia con el formato de details anterior que no se pudo migrar
+        let legacy: ArbitrageStrategy = serde_json::from_value(json!({
+            "arbitrage_type": "Exchange",
+            "details": { "Exchange": { "pair1": { "$oid": binance_pair.to_hex() }, "pair2": { "$oid": kraken_pair.to_hex() } } },
+            "status": true,
+        })).unwrap();
+        let legacy = storage.insert_strategy(legacy).await.unwrap().id.unwrap();
+        let (_, orphans) = call(&app, test::TestRequest::get().uri("/integrity/orphans"), &admin).await;
+        assert_eq!(orphans["data"]["unmigrated_strategies"][0]["$oid"], legacy.to_hex());
+    }
+
+    #[actix_web::test]
+    async fn exchange_fees_must_be_fractions() {
+        let storage = Arc::new(InMemoryStorage::default());
+        seed_user(&storage, "admin@example.com", Role::Admin).await;
+        let binance = test_fixtures::exchange("binance");
+        storage.insert_exchange(binance.clone()).await.unwrap();
+        let app = app(storage).await;
+        let admin = login(&app, "admin@example.com").await;
+
+        let mut exchange = serde_json::to_value(&binance).unwrap();
+        exchange.as_object_mut().unwrap().remove("_id");
+        exchange["taker_fee"] = json!(1.5);
+        let (status, error) = call(&app, test::TestRequest::post().uri("/exchanges").set_json(&exchange), &admin).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["errors"], json!([{ "field": "taker_fee", "message": "Must be a fraction between 0 and 1" }]));
+
+        exchange["taker_fee"] = json!(0.001);
+        exchange["maker_fee"] = json!(-0.1);
+        let uri = format!("/exchanges/{}", binance.id.unwrap());
+        let (status, error) = call(&app, test::TestRequest::put().uri(&uri).set_json(&exchange), &admin).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["errors"][0]["field"], "maker_fee");
+
+        exchange["maker_fee"] = json!(0.001);
+        let (status, _) = call(&app, test::TestRequest::put().uri(&uri).set_json(&exchange), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn market_pairs_are_validated_against_their_assets() {
+        let storage = Arc::new(InMemoryStorage::default());
+        seed_user(&storage, "admin@example.com", Role::Admin).await;
+        let binance = test_fixtures::exchange("binance");
+        let kraken = test_fixtures::exchange("kraken");
+        let btc_usdt = seed_btc_usdt(&storage, &binance).await;
+        let binance_usdt = storage.find_market_pair(btc_usdt).await.unwrap().unwrap()._quote_asset;
+        let eth = test_fixtures::asset(&binance, "ETH");
+        let kraken_usdt = test_fixtures::asset(&kraken, "USDT");
+        storage.insert_exchange(kraken.clone()).await.unwrap();
+        storage.insert_asset(eth.clone()).await.unwrap();
+        storage.insert_asset(kraken_usdt.clone()).await.unwrap();
+        let app = app(storage).await;
+        let admin = login(&app, "admin@example.com").await;
+
+        let pair = |base: ObjectId, quote: ObjectId| json!({
+            "_exchange": binance.id.unwrap().to_hex(),
+            "_base_asset": base.to_hex(),
+            "_quote_asset": quote.to_hex(),
+            "created_at": 0.0,
+            "updated_at": 0.0,
+            "status": true,
+        });
+
+        let (status, error) = call(&app, test::TestRequest::post().uri("/market_pairs").set_json(pair(eth.id.unwrap(), kraken_usdt.id.unwrap())), &admin).await;
+        assert_eq!((status, error["code"].as_str()), (StatusCode::BAD_REQUEST, Some("validation_error")));
+        assert_eq!(error["errors"], json!([{ "field": "_quote_asset", "message": "Asset belongs to a different exchange" }]));
+
+        let (status, error) = call(&app, test::TestRequest::post().uri("/market_pairs").set_json(pair(eth.id.unwrap(), eth.id.unwrap())), &admin).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["errors"][0]["field"], "_quote_asset");
+
+        let (status, _) = call(&app, test::TestRequest::post().uri("/market_pairs").set_json(pair(eth.id.unwrap(), binance_usdt)), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, error) = call(&app, test::TestRequest::post().uri("/market_pairs").set_json(pair(eth.id.unwrap(), binance_usdt)), &admin).await;
+        assert_eq!((status, error["code"].as_str()), (StatusCode::CONFLICT, Some("conflict")));
+
+        // ETH ya tiene un par en binance, así que no puede pasar a kraken
+        let mut moved = serde_json::to_value(&eth).unwrap();
+        moved["_exchange"] = json!(kraken.id.unwrap().to_hex());
+        let (status, error) = call(&app, test::TestRequest::put().uri(&format!("/assets/{}", eth.id.unwrap())).set_json(&moved), &admin).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["errors"][0]["field"], "_exchange");
+    }
+
+    #[actix_web::test]
+    async fn account_responses_hide_credentials() {
+        let storage = Arc::new(InMemoryStorage::default());
+        seed_user(&storage, "admin@example.com", Role::Admin).await;
+        seed_user(&storage, "viewer@example.com", Role::Viewer).await;
+        let app = app(storage.clone()).await;
+        let admin = login(&app, "admin@example.com").await;
+        let viewer = storage.find_user_by_email("viewer@example.com").await.unwrap().unwrap().id.unwrap();
+
+        let (status, updated) = call(&app, test::TestRequest::put().uri(&format!("/admin/users/{}/role", viewer)).set_json(json!({ "role": "operator" })), &admin).await;
+        assert_eq!(status, StatusCode::OK);
+        let operator = login(&app, "viewer@example.com").await;
+        let (status, account) = call(&app, test::TestRequest::get().uri(&format!("/account/{}", viewer)), &operator).await;
+        assert_eq!(status, StatusCode::OK);
+
+        for user in [&updated["data"], &account["data"]] {
+            assert_eq!(user["id"], viewer.to_hex());
+            assert_eq!(user["role"], "operator");
+            for secret in ["password", "tokens", "password_reset_token", "password_reset_expires"] {
+                assert!(user.get(secret).is_none(), "{} should not be exposed", secret);
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn api_keys_are_limited_to_their_scopes() {
+        let storage = Arc::new(InMemoryStorage::default());
+        seed_user(&storage, "bot@example.com", Role::Operator).await;
+        let app = app(storage).await;
+        let token = login(&app, "bot@example.com").await;
+
+        let request = json!({ "name": "reader", "scopes": ["read_catalog"] });
+        let (status, created) = call(&app, test::TestRequest::post().uri("/api_keys").set_json(&request), &token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(created["data"].get("secret_hash").is_none());
+        let key = created["data"]["key"].as_str().unwrap().to_string();
+
+        let with_key = |req: test::TestRequest| req.insert_header(("X-API-Key", key.clone())).to_request();
+        let resp = test::call_service(&app, with_key(test::TestRequest::get().uri("/exchanges"))).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = test::call_service(&app, with_key(test::TestRequest::get().uri("/arbitrage-strategies"))).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/exchanges").insert_header(("X-API-Key", "arbi_bad_key")).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
 }